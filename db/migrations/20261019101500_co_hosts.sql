@@ -0,0 +1,7 @@
+CREATE TABLE co_hosts (
+  user_id char(24) UNIQUE PRIMARY KEY NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+  kick boolean NOT NULL DEFAULT false,
+  remove_songs boolean NOT NULL DEFAULT false,
+  skip boolean NOT NULL DEFAULT false,
+  change_settings boolean NOT NULL DEFAULT false
+);
@@ -0,0 +1,5 @@
+CREATE TABLE handovers (
+  jam_id char(6) UNIQUE PRIMARY KEY NOT NULL REFERENCES jams (id) ON DELETE CASCADE,
+  user_id char(24) UNIQUE NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+  new_host_id char(24) UNIQUE REFERENCES hosts (id) ON DELETE SET NULL
+);
@@ -0,0 +1,2 @@
+ALTER TABLE jams DROP CONSTRAINT jams_host_id_key;
+CREATE INDEX jams_host_id_idx ON jams (host_id);
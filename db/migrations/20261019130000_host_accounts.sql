@@ -0,0 +1,25 @@
+ALTER TABLE hosts ADD COLUMN spotify_user_id varchar UNIQUE;
+
+ALTER TABLE jams ADD COLUMN queue_strategy varchar(16) NOT NULL DEFAULT 'votes';
+ALTER TABLE jams ADD COLUMN allow_explicit boolean NOT NULL DEFAULT true;
+ALTER TABLE jams ADD COLUMN created_at timestamptz NOT NULL DEFAULT now();
+
+ALTER TABLE songs ADD COLUMN added_at timestamptz NOT NULL DEFAULT now();
+
+CREATE TABLE past_jams (
+  id char(24) UNIQUE PRIMARY KEY NOT NULL,
+  jam_id char(6) NOT NULL,
+  host_id char(24) NOT NULL REFERENCES hosts (id) ON DELETE CASCADE,
+  name varchar(30) NOT NULL,
+  created_at timestamptz NOT NULL,
+  ended_at timestamptz NOT NULL DEFAULT now()
+);
+
+CREATE TABLE jam_templates (
+  id char(24) UNIQUE PRIMARY KEY NOT NULL,
+  host_id char(24) NOT NULL REFERENCES hosts (id) ON DELETE CASCADE,
+  name varchar(30) NOT NULL,
+  max_song_count smallint NOT NULL,
+  queue_strategy varchar(16) NOT NULL DEFAULT 'votes',
+  allow_explicit boolean NOT NULL DEFAULT true
+);
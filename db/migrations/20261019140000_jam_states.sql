@@ -0,0 +1,3 @@
+-- existing jams are already playing
+ALTER TABLE jams ADD COLUMN state varchar(16) NOT NULL DEFAULT 'playing';
+ALTER TABLE jams ADD COLUMN starts_at BIGINT;
@@ -0,0 +1,3 @@
+-- unix timestamps in milliseconds, song_started_at is moved forward by the length of every pause
+ALTER TABLE jams ADD COLUMN song_started_at BIGINT;
+ALTER TABLE jams ADD COLUMN paused_at BIGINT;
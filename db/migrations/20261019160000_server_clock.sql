@@ -0,0 +1,2 @@
+-- the position is computed from song_started_at, so it doesn't have to be stored anymore
+ALTER TABLE jams DROP COLUMN song_position;
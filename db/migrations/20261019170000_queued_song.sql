@@ -0,0 +1,4 @@
+-- the song that was already sent to the queue of spotify, so it plays right after the current one,
+-- queued_song_id is null if the song didn't come from the queue of the jam
+ALTER TABLE jams ADD COLUMN queued_spotify_id varchar;
+ALTER TABLE jams ADD COLUMN queued_song_id char(24) REFERENCES songs (id) ON DELETE SET NULL;
@@ -0,0 +1,11 @@
+CREATE TABLE played_songs (
+  id char(24) UNIQUE PRIMARY KEY NOT NULL,
+  jam_id char(6) NOT NULL REFERENCES jams (id) ON DELETE CASCADE,
+  spotify_id varchar NOT NULL,
+  -- how many votes the song had when it was played, the best liked songs seed the recommendations
+  votes int NOT NULL DEFAULT 0,
+  played_at BIGINT NOT NULL
+);
+CREATE INDEX played_songs_jam_id_idx ON played_songs (jam_id);
+
+ALTER TABLE jams ADD COLUMN fallback_playlist_id varchar;
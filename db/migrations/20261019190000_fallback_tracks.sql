@@ -0,0 +1,16 @@
+-- the fallback list of a jam, filled by importing a playlist or by the host adding tracks one by one
+CREATE TABLE fallback_tracks (
+  id char(24) UNIQUE PRIMARY KEY NOT NULL,
+  jam_id char(6) NOT NULL REFERENCES jams (id) ON DELETE CASCADE,
+  spotify_id varchar NOT NULL,
+  name varchar NOT NULL,
+  album varchar NOT NULL,
+  duration int NOT NULL,
+  artists varchar[] NOT NULL,
+  image_url varchar NOT NULL,
+  explicit boolean NOT NULL DEFAULT false,
+  position int NOT NULL
+);
+CREATE INDEX fallback_tracks_jam_id_idx ON fallback_tracks (jam_id);
+
+ALTER TABLE jams ADD COLUMN fallback_shuffle boolean NOT NULL DEFAULT false;
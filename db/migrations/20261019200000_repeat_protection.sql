@@ -0,0 +1,7 @@
+-- the normalized title and primary artist, so other releases of a played song are caught too
+ALTER TABLE played_songs ADD COLUMN song_key varchar NOT NULL DEFAULT '';
+ALTER TABLE songs ADD COLUMN song_key varchar NOT NULL DEFAULT '';
+CREATE INDEX songs_song_key_idx ON songs (song_key);
+
+-- how long a played song can't be added again, 0 allows it right away
+ALTER TABLE jams ADD COLUMN no_repeat_minutes int NOT NULL DEFAULT 60;
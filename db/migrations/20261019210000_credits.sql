@@ -0,0 +1,9 @@
+-- how users are limited in adding songs, 'max_songs' or 'credits'
+ALTER TABLE jams ADD COLUMN song_limit varchar(16) NOT NULL DEFAULT 'max_songs';
+
+-- the balance is topped up lazily, credits_updated_at is when the last credit was earned over time, in milliseconds
+ALTER TABLE users ADD COLUMN credits int NOT NULL DEFAULT 3;
+ALTER TABLE users ADD COLUMN credits_updated_at BIGINT NOT NULL DEFAULT (extract(epoch from now()) * 1000)::BIGINT;
+
+-- every boost counts like an extra vote
+ALTER TABLE songs ADD COLUMN boosts int NOT NULL DEFAULT 0;
@@ -0,0 +1,5 @@
+-- details shown to guests so they can check a song before adding it, release_year and popularity can be missing
+ALTER TABLE songs ADD COLUMN preview_url varchar NULL;
+ALTER TABLE songs ADD COLUMN release_year int NULL;
+ALTER TABLE songs ADD COLUMN explicit boolean NOT NULL DEFAULT false;
+ALTER TABLE songs ADD COLUMN popularity int NULL;
@@ -0,0 +1,10 @@
+-- spotify tokens of guests who logged in to add songs from their own library,
+-- deleted with the user, so when they are kicked or the jam ends
+CREATE TABLE user_access_tokens (
+  id char(24) UNIQUE PRIMARY KEY NOT NULL,
+  user_id char(24) NOT NULL UNIQUE REFERENCES users (id) ON DELETE CASCADE,
+  access_token varchar NOT NULL,
+  expires_at BIGINT NOT NULL,
+  scope varchar NOT NULL,
+  refresh_token varchar NOT NULL
+);
@@ -0,0 +1,15 @@
+-- the metadata of every track the server fetched from spotify, shared by all jams,
+-- so adding a song from the search doesn't ask spotify again, cached_at is in milliseconds
+CREATE TABLE track_cache (
+  spotify_id varchar NOT NULL UNIQUE PRIMARY KEY,
+  name varchar NOT NULL,
+  album varchar NOT NULL,
+  duration int NOT NULL,
+  artists varchar[] NOT NULL,
+  image_url varchar NOT NULL,
+  preview_url varchar NULL,
+  release_year int NULL,
+  explicit boolean NOT NULL,
+  popularity int NULL,
+  cached_at BIGINT NOT NULL
+);
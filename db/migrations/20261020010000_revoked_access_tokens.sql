@@ -0,0 +1,3 @@
+-- set when spotify rejects the refresh token, the host has to log in again,
+-- logging in again replaces the token, so a new one is never revoked
+ALTER TABLE access_tokens ADD COLUMN revoked boolean NOT NULL DEFAULT false;
@@ -0,0 +1,3 @@
+-- the spotify connect device the host chose to play the jam on,
+-- null means the player in the browser of the host
+ALTER TABLE jams ADD COLUMN device_id varchar NULL;
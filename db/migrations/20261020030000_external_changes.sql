@@ -0,0 +1,2 @@
+-- what happens when the host skips, pauses or picks a song in the spotify app, adopt or override
+ALTER TABLE jams ADD COLUMN external_changes varchar NOT NULL DEFAULT 'adopt';
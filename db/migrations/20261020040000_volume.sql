@@ -0,0 +1,2 @@
+-- the volume the host set for the player, from 0 to 100
+ALTER TABLE jams ADD COLUMN volume_percent smallint NOT NULL DEFAULT 100;
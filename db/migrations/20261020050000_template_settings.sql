@@ -0,0 +1,5 @@
+-- templates hold every setting a jam is created with, so a saved template recreates the jam
+ALTER TABLE jam_templates ADD COLUMN song_limit varchar(16) NOT NULL DEFAULT 'max_songs';
+ALTER TABLE jam_templates ADD COLUMN no_repeat_minutes int NOT NULL DEFAULT 60;
+ALTER TABLE jam_templates ADD COLUMN fallback_shuffle boolean NOT NULL DEFAULT false;
+ALTER TABLE jam_templates ADD COLUMN external_changes varchar NOT NULL DEFAULT 'adopt';
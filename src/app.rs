@@ -1,5 +1,5 @@
 use crate::model;
-use crate::model::real_time::SearchResult;
+use crate::model::real_time::{SearchKind, SearchQuery, SearchResult};
 use leptos::{prelude::*, task::spawn_local};
 use leptos_meta::*;
 use leptos_router::{
@@ -53,9 +53,17 @@ pub fn App() -> impl IntoView {
                 <Routes fallback=|| "Page not found.".into_view()>
                     <Route path=path!("/") view=pages::HomePage />
                     <Route path=path!("/create-host") view=pages::CreateHostPage />
+                    <Route path=path!("/connect-spotify") view=pages::ConnectSpotifyPage />
                     <Route path=path!("/create-user/:id") view=pages::CreateUserPage />
                     <Route path=path!("/jam/host/:id") view=pages::HostPage />
+                    <Route
+                        path=path!("/jam/remote/:id")
+                        view=|| view! { <pages::HostPage remote=true /> }
+                    />
+                    <Route path=path!("/jam/output/:id") view=pages::OutputPage />
                     <Route path=path!("/jam/:id") view=pages::UserPage />
+                    <Route path=path!("/jam/tv/:id") view=pages::TvPage />
+                    <Route path=path!("/dashboard") view=pages::DashboardPage />
                     <Route path=path!("/test-bar") view=UserBartTest />
                     <Route path=path!("/test-share") view=ShareTest />
                     <Route path=path!("/test-search") view=SearchTest />
@@ -93,6 +101,7 @@ pub fn SongListTest() -> impl IntoView {
             votes: 0,
             have_you_voted: None,
         },
+        details: Default::default(),
     };
     let songs = {
         let mut songs = Vec::new();
@@ -111,6 +120,7 @@ pub fn SongListTest() -> impl IntoView {
         add_vote: Callback::new(|id| log!("add vote with id:{}", id)),
         remove_vote: Callback::new(|id| log!("remove vote with id:{}", id)),
         remove_song: Callback::new(|id| log!("remove song with id:{}", id)),
+        boost: None,
     };
     view! { <SongList songs votes max_song_count song_list_action /> }
 }
@@ -125,46 +135,55 @@ pub fn UserBartTest() -> impl IntoView {
             id: "tb0k2ujdagg6bvvqeqlx2qgq".to_string(),
             jam_id: "niggaa".to_string(),
             name: "kaka".to_string(),
+            permissions: Default::default(),
         },
         User {
             id: "coe7474an5pkiptmjls2bq0w".to_string(),
             jam_id: "niggaa".to_string(),
             name: "kakamaka".to_string(),
+            permissions: Default::default(),
         },
         User {
             id: "bl0m5ktr6bs51hnbmkp8bs0c".to_string(),
             jam_id: "niggaa".to_string(),
             name: "kakamakanaka".to_string(),
+            permissions: Default::default(),
         },
         User {
             id: "tb0k2ujdagg6bvvqeqlx2qgq".to_string(),
             jam_id: "niggaa".to_string(),
             name: "kaka".to_string(),
+            permissions: Default::default(),
         },
         User {
             id: "coe7474an5pkiptmjls2bq0w".to_string(),
             jam_id: "niggaa".to_string(),
             name: "kakamaka".to_string(),
+            permissions: Default::default(),
         },
         User {
             id: "bl0m5ktr6bs51hnbmkp8bs0c".to_string(),
             jam_id: "niggaa".to_string(),
             name: "kakamakanaka".to_string(),
+            permissions: Default::default(),
         },
         User {
             id: "tb0k2ujdagg6bvvqeqlx2qgq".to_string(),
             jam_id: "niggaa".to_string(),
             name: "kaka".to_string(),
+            permissions: Default::default(),
         },
         User {
             id: "coe7474an5pkiptmjls2bq0w".to_string(),
             jam_id: "niggaa".to_string(),
             name: "kakamaka".to_string(),
+            permissions: Default::default(),
         },
         User {
             id: "bl0m5ktr6bs51hnbmkp8bs0c".to_string(),
             jam_id: "niggaa".to_string(),
             name: "kakamakanaka".to_string(),
+            permissions: Default::default(),
         },
     ];
     let (users, set_users) = signal(Some(users));
@@ -212,6 +231,7 @@ fn SearchTest() -> impl IntoView {
             votes: 0,
             have_you_voted: None,
         },
+        details: Default::default(),
     };
 
     let songs = {
@@ -226,13 +246,24 @@ fn SearchTest() -> impl IntoView {
     let (search_result, set_search_result) = signal(Some(SearchResult {
         search_id: "lol".to_string(),
         songs: songs.clone(),
+        collections: vec![],
+        query: SearchQuery::Text {
+            query: "lol".to_string(),
+            kind: SearchKind::Track,
+        },
+        offset: 0,
+        next_offset: None,
     }));
-    let search = move |id: (String, String)| {
+    let search = move |(query, offset, id): (SearchQuery, u32, String)| {
+        log!("search for {:?} from {}", query, offset);
         set_search_result.set(Some(SearchResult {
             songs: songs.clone(),
-            search_id: id.1,
+            collections: vec![],
+            search_id: id,
+            query,
+            offset,
+            next_offset: None,
         }));
-        log!("search with id:{}", id.0)
     };
     let search = Callback::new(search);
 
@@ -260,6 +291,7 @@ fn UserPlayerTest() -> impl IntoView {
             votes: 0,
             have_you_voted: None,
         },
+        details: Default::default(),
     }));
     let position = Signal::derive(|| 0.7);
 
@@ -0,0 +1,9 @@
+//! Serves the mock spotify server, the address can be changed with `MOCK_SPOTIFY_ADDR`
+
+#[tokio::main]
+async fn main() {
+    let addr = std::env::var("MOCK_SPOTIFY_ADDR").unwrap_or("127.0.0.1:3002".to_string());
+    if let Err(e) = music_jam::mock_spotify::serve(&addr).await {
+        eprintln!("could not serve the mock spotify server: {:?}", e);
+    }
+}
@@ -4,9 +4,10 @@ use leptos::task::spawn_local;
 use leptos::{either::*, logging::log, prelude::*};
 use leptos_router::{hooks::use_navigate, *};
 
+/// also used to log in again, if spotify doesn't accept the login of a host anymore
 #[server]
-async fn redirect_to_spotify_oauth() -> Result<(), ServerFnError> {
-    use crate::model::AppState;
+pub async fn redirect_to_spotify_oauth() -> Result<(), ServerFnError> {
+    use crate::model::{spotify_authorize_url, AppState};
     use leptos_axum::*;
     use sqlx::*;
     let app_state = expect_context::<AppState>();
@@ -15,40 +16,35 @@ async fn redirect_to_spotify_oauth() -> Result<(), ServerFnError> {
     let query = query!("INSERT INTO hosts(id) VALUES ($1)", &host_id);
     let pool = app_state.db.pool;
     query.execute(&pool).await?;
-    redirect(
-        format!(
-            "https://accounts.spotify.com/authorize?response_type=code&client_id={}&scope={}&redirect_uri={}/create-host&state={}&show_dialog=true"
-            ,app_state.spotify_credentials.id
-            ,"user-read-playback-state user-modify-playback-state user-read-currently-playing streaming user-read-private user-read-email user-read-recently-played user-top-read"
-            ,app_state.site_url
-            ,host_id
-        ).as_str()
-    );
+    redirect(&spotify_authorize_url(
+        &app_state.spotify_credentials,
+        &app_state.site_url,
+        &host_id,
+    ));
     Ok(())
 }
 
 #[server]
 async fn create_jam(
-    name: String,
     host_id: String,
-    max_song_count: i16,
+    settings: JamTemplate,
+    starts_at: Option<i64>,
 ) -> Result<JamId, ServerFnError> {
-    use crate::model::{create_jam, AppState, Error};
+    use crate::model::{create_jam, AppState};
     let app_state = expect_context::<AppState>();
     let mut transaction = app_state.db.pool.begin().await?;
     let credentials = app_state.spotify_credentials;
 
     let jam_id = match create_jam(
-        &name,
         &host_id,
-        max_song_count,
+        &settings,
+        starts_at,
         &mut transaction,
         credentials.clone(),
     )
     .await
     {
         Ok(jam_id) => jam_id,
-        Err(Error::HostAlreadyInJam { jam_id }) => jam_id,
         Err(e) => {
             return Err(ServerFnError::Request(format!(
                 "Error creating jam: {:#?}",
@@ -58,9 +54,40 @@ async fn create_jam(
     };
 
     transaction.commit().await?;
+    // the clock starts scheduled jams, even if the host page isn't open when they are due
+    app_state
+        .jam_clocks
+        .start(jam_id.clone(), app_state.db.pool.clone(), credentials);
     Ok(jam_id)
 }
 
+#[server]
+async fn get_templates(host_id: String) -> Result<Vec<JamTemplate>, ServerFnError> {
+    use crate::model::{functions, AppState};
+    let app_state = expect_context::<AppState>();
+    functions::get_templates(&host_id, &app_state.db.pool)
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))
+}
+
+#[server]
+async fn save_template(host_id: String, template: JamTemplate) -> Result<String, ServerFnError> {
+    use crate::model::{functions, AppState};
+    let app_state = expect_context::<AppState>();
+    functions::save_template(&host_id, &template, &app_state.db.pool)
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))
+}
+
+#[server]
+async fn delete_template(host_id: String, template_id: String) -> Result<(), ServerFnError> {
+    use crate::model::{functions, AppState};
+    let app_state = expect_context::<AppState>();
+    functions::delete_template(&host_id, &template_id, &app_state.db.pool)
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))
+}
+
 #[component]
 pub fn CreateIsland() -> impl IntoView {
     use gloo::storage::{errors::StorageError, LocalStorage, Storage};
@@ -69,6 +96,14 @@ pub fn CreateIsland() -> impl IntoView {
 
     let (name, set_name) = signal(String::from(""));
     let (max_song_count, set_max_song_count) = signal::<i16>(1);
+    let (queue_strategy, set_queue_strategy) = signal(QueueStrategy::default());
+    let (allow_explicit, set_allow_explicit) = signal(true);
+    let (song_limit, set_song_limit) = signal(SongLimit::default());
+    let (no_repeat_minutes, set_no_repeat_minutes) = signal::<u32>(60);
+    let (fallback_shuffle, set_fallback_shuffle) = signal(false);
+    let (external_changes, set_external_changes) = signal(ExternalChanges::default());
+    // unix timestamp in seconds, none starts the jam right away
+    let (starts_at, set_starts_at) = signal(None::<i64>);
 
     let (error_message, set_error_message) =
         signal(String::from("there is no error lol, this is a bug"));
@@ -92,14 +127,84 @@ pub fn CreateIsland() -> impl IntoView {
         });
     };
 
+    let templates = Resource::new(
+        move || host_id.get(),
+        move |host_id| async move {
+            match host_id {
+                Some(host_id) => get_templates(host_id).await,
+                None => Ok(Vec::new()),
+            }
+        },
+    );
+
+    let apply_template = move |template_id: String| {
+        if let Some(template) = templates
+            .get_untracked()
+            .and_then(Result::ok)
+            .and_then(|templates| templates.into_iter().find(|t| t.id == template_id))
+        {
+            set_name.set(template.name);
+            set_max_song_count.set(template.max_song_count as i16);
+            set_queue_strategy.set(template.queue_strategy);
+            set_allow_explicit.set(template.allow_explicit);
+            set_song_limit.set(template.song_limit);
+            set_no_repeat_minutes.set(template.no_repeat_minutes);
+            set_fallback_shuffle.set(template.fallback_shuffle);
+            set_external_changes.set(template.external_changes);
+        }
+    };
+
+    // the settings in the form, a jam is created from them and they are saved as a template
+    let current_settings = move || JamTemplate {
+        id: String::new(),
+        name: name.get_untracked(),
+        max_song_count: max_song_count.get_untracked().clamp(1, u8::MAX as i16) as u8,
+        queue_strategy: queue_strategy.get_untracked(),
+        allow_explicit: allow_explicit.get_untracked(),
+        song_limit: song_limit.get_untracked(),
+        no_repeat_minutes: no_repeat_minutes.get_untracked(),
+        fallback_shuffle: fallback_shuffle.get_untracked(),
+        external_changes: external_changes.get_untracked(),
+    };
+
+    let save_as_template = Action::new(move |_: &()| {
+        let template = current_settings();
+        async move {
+            if let Some(host_id) = host_id.get_untracked() {
+                match save_template(host_id, template).await {
+                    Ok(_) => templates.refetch(),
+                    Err(e) => {
+                        set_error_message.set(format!("Error saving template: {}", e));
+                        set_show_dialog.set(true);
+                    }
+                }
+            }
+        }
+    });
+
+    let remove_template = Action::new(move |template_id: &String| {
+        let template_id = template_id.clone();
+        async move {
+            if let Some(host_id) = host_id.get_untracked() {
+                match delete_template(host_id, template_id).await {
+                    Ok(_) => templates.refetch(),
+                    Err(e) => {
+                        set_error_message.set(format!("Error deleting template: {}", e));
+                        set_show_dialog.set(true);
+                    }
+                }
+            }
+        }
+    });
+
     let create = Action::new(move |_: &()| {
-        let name = name.get_untracked();
-        let max_song_count = max_song_count.get();
+        let settings = current_settings();
+        let starts_at = starts_at.get_untracked();
         async move {
             match host_id.get_untracked() {
                 Some(host_id) => {
-                    if !name.is_empty() {
-                        match create_jam(name, host_id, max_song_count).await {
+                    if !settings.name.is_empty() {
+                        match create_jam(host_id, settings, starts_at).await {
                             Ok(jam_id) => {
                                 let navigate = use_navigate();
                                 navigate(
@@ -134,6 +239,40 @@ pub fn CreateIsland() -> impl IntoView {
                 if host_id.with(Option::is_some) {
                     Either::Left(
                         view! {
+                            <div class="templates">
+                                <For
+                                    each=move || {
+                                        templates.get().and_then(Result::ok).unwrap_or_default()
+                                    }
+                                    key=|template| template.id.clone()
+                                    children=move |template| {
+                                        let template_id = template.id.clone();
+                                        view! {
+                                            <div class="template">
+                                                <button
+                                                    class="apply"
+                                                    title="use this template"
+                                                    on:click={
+                                                        let template_id = template_id.clone();
+                                                        move |_| apply_template(template_id.clone())
+                                                    }
+                                                >
+                                                    {template.name.clone()}
+                                                </button>
+                                                <button
+                                                    class="delete"
+                                                    title="delete this template"
+                                                    on:click=move |_| {
+                                                        remove_template.dispatch(template_id.clone());
+                                                    }
+                                                >
+                                                    "×"
+                                                </button>
+                                            </div>
+                                        }
+                                    }
+                                />
+                            </div>
                             <div class="jam-name">
                                 <label for="create-jam-name">"Jam Name"</label>
                                 <input
@@ -160,6 +299,122 @@ pub fn CreateIsland() -> impl IntoView {
                                     pattern="^[2-9]\\d*$"
                                 />
                             </div>
+                            <div class="queue-strategy">
+                                <label for="create-jam-queue-strategy">"Next Song"</label>
+                                <select
+                                    id="create-jam-queue-strategy"
+                                    on:change=move |ev| {
+                                        set_queue_strategy
+                                            .set(QueueStrategy::from_db_str(&event_target_value(&ev)))
+                                    }
+                                >
+                                    {QueueStrategy::all()
+                                        .into_iter()
+                                        .map(|strategy| {
+                                            view! {
+                                                <option
+                                                    value=strategy.as_db_str()
+                                                    prop:selected=move || queue_strategy.get() == strategy
+                                                >
+                                                    {strategy.label()}
+                                                </option>
+                                            }
+                                        })
+                                        .collect_view()}
+                                </select>
+                            </div>
+                            <label class="allow-explicit">
+                                <input
+                                    type="checkbox"
+                                    prop:checked=allow_explicit
+                                    on:change=move |ev| set_allow_explicit.set(event_target_checked(&ev))
+                                />
+                                "Allow explicit songs"
+                            </label>
+                            <div class="song-limit">
+                                <label for="create-jam-song-limit">"Song Limit"</label>
+                                <select
+                                    id="create-jam-song-limit"
+                                    on:change=move |ev| {
+                                        set_song_limit
+                                            .set(SongLimit::from_db_str(&event_target_value(&ev)))
+                                    }
+                                >
+                                    {SongLimit::all()
+                                        .into_iter()
+                                        .map(|limit| {
+                                            view! {
+                                                <option
+                                                    value=limit.as_db_str()
+                                                    prop:selected=move || song_limit.get() == limit
+                                                >
+                                                    {limit.label()}
+                                                </option>
+                                            }
+                                        })
+                                        .collect_view()}
+                                </select>
+                            </div>
+                            <div class="no-repeat">
+                                <label for="create-jam-no-repeat">"No Repeat (minutes)"</label>
+                                <input
+                                    type="number"
+                                    prop:value=no_repeat_minutes
+                                    on:input=move |ev| {
+                                        set_no_repeat_minutes
+                                            .set(event_target_value(&ev).parse().unwrap_or(0))
+                                    }
+                                    placeholder="ex. 60"
+                                    id="create-jam-no-repeat"
+                                />
+                            </div>
+                            <div class="external-changes">
+                                <label for="create-jam-external-changes">
+                                    "Changes in the Spotify App"
+                                </label>
+                                <select
+                                    id="create-jam-external-changes"
+                                    on:change=move |ev| {
+                                        set_external_changes
+                                            .set(ExternalChanges::from_db_str(&event_target_value(&ev)))
+                                    }
+                                >
+                                    {ExternalChanges::all()
+                                        .into_iter()
+                                        .map(|policy| {
+                                            view! {
+                                                <option
+                                                    value=policy.as_db_str()
+                                                    prop:selected=move || external_changes.get() == policy
+                                                >
+                                                    {policy.label()}
+                                                </option>
+                                            }
+                                        })
+                                        .collect_view()}
+                                </select>
+                            </div>
+                            <label class="fallback-shuffle">
+                                <input
+                                    type="checkbox"
+                                    prop:checked=fallback_shuffle
+                                    on:change=move |ev| set_fallback_shuffle.set(event_target_checked(&ev))
+                                />
+                                "Shuffle the fallback list"
+                            </label>
+                            <div class="starts-at">
+                                <label for="create-jam-starts-at">"Start At (optional)"</label>
+                                <input
+                                    type="datetime-local"
+                                    id="create-jam-starts-at"
+                                    on:input=move |ev| {
+                                        // the input is in local time, which the browser knows how to parse
+                                        let millis = js_sys::Date::parse(&event_target_value(&ev));
+                                        set_starts_at
+                                            .set((!millis.is_nan()).then(|| (millis / 1000.0) as i64))
+                                    }
+                                />
+                            </div>
 
                             <button
                                 on:click=move |_| {
@@ -170,16 +425,28 @@ pub fn CreateIsland() -> impl IntoView {
                             >
                                 "Create"
                             </button>
+                            <button
+                                on:click=move |_| {
+                                    save_as_template.dispatch(());
+                                }
+
+                                class="save-template"
+                            >
+                                "Save as template"
+                            </button>
+                            <a href="/dashboard" class="dashboard-link">
+                                "My Jams"
+                            </a>
                         },
                     )
                 } else {
                     Either::Right(
                         view! {
                             <div class="to-create-jam">
-                                "You need to connect your Spotify account to create a jam"
+                                "Log in with your Spotify account to create a jam, your jams and templates are saved to it"
                             </div>
                             <button on:click=move |_| redirect_to_oauth() class="connect-spotify">
-                                <div>"Connect Spotify"</div>
+                                <div>"Log in with Spotify"</div>
                                 <svg xmlns="http://www.w3.org/2000/svg" width="64" height="64">
                                     <path d="M32 0C14.3 0 0 14.337 0 32c0 17.7 14.337 32 32 32 17.7 0 32-14.337 32-32S49.663 0 32 0zm14.68 46.184c-.573.956-1.797 1.223-2.753.65-7.532-4.588-16.975-5.62-28.14-3.097-1.07.23-2.14-.42-2.37-1.49s.42-2.14 1.49-2.37c12.196-2.79 22.67-1.606 31.082 3.556a2 2 0 0 1 .688 2.753zm3.9-8.717c-.726 1.185-2.256 1.53-3.44.84-8.602-5.276-21.716-6.805-31.885-3.747-1.338.382-2.714-.344-3.097-1.644-.382-1.338.344-2.714 1.682-3.097 11.622-3.517 26.074-1.835 35.976 4.244 1.147.688 1.49 2.217.765 3.403zm.344-9.1c-10.323-6.117-27.336-6.69-37.2-3.708-1.568.497-3.25-.42-3.747-1.988s.42-3.25 1.988-3.747c11.317-3.44 30.127-2.753 41.98 4.282 1.415.84 1.873 2.676 1.032 4.09-.765 1.453-2.638 1.912-4.053 1.07z"></path>
                                 </svg>
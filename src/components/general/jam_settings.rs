@@ -0,0 +1,141 @@
+use crate::components::Modal;
+use crate::model::{ExternalChanges, Jam, SongLimit};
+use leptos::prelude::*;
+
+/// A modal for the host and co-hosts to change the name, the song limit and the no repeat window of the jam,
+/// the song limit is either a max song count per user or credits,
+/// the spotify app setting decides if skips and pauses made in the spotify app are adopted by the jam
+#[component]
+pub fn JamSettings(
+    #[prop(into)] jam: Signal<Option<Jam>>,
+    #[prop(into)] visible: Signal<bool>,
+    save: Callback<(String, u8, u32, SongLimit, ExternalChanges)>,
+    close: Callback<()>,
+) -> impl IntoView {
+    let (name, set_name) = signal(String::new());
+    let (max_song_count, set_max_song_count) = signal::<u8>(1);
+    let (no_repeat_minutes, set_no_repeat_minutes) = signal::<u32>(0);
+    let (song_limit, set_song_limit) = signal(SongLimit::default());
+    let (external_changes, set_external_changes) = signal(ExternalChanges::default());
+
+    Effect::new(move |_| {
+        if visible.get()
+            && let Some(jam) = jam.get()
+        {
+            set_name.set(jam.name);
+            set_max_song_count.set(jam.max_song_count);
+            set_no_repeat_minutes.set(jam.no_repeat_minutes);
+            set_song_limit.set(jam.song_limit);
+            set_external_changes.set(jam.external_changes);
+        }
+    });
+
+    view! {
+        <Modal visible>
+            <div class="jam-settings">
+                <div class="jam-name">
+                    <label for="settings-jam-name">"Jam Name"</label>
+                    <input
+                        type="text"
+                        prop:value=name
+                        on:input=move |ev| set_name.set(event_target_value(&ev))
+                        placeholder="ex. My Jam"
+                        id="settings-jam-name"
+                        maxlength=30
+                    />
+                </div>
+                <div class="song-limit">
+                    <label for="settings-jam-song-limit">"Song Limit"</label>
+                    <select
+                        id="settings-jam-song-limit"
+                        on:change=move |ev| {
+                            set_song_limit.set(SongLimit::from_db_str(&event_target_value(&ev)))
+                        }
+                    >
+                        {SongLimit::all()
+                            .into_iter()
+                            .map(|limit| {
+                                view! {
+                                    <option
+                                        value=limit.as_db_str()
+                                        prop:selected=move || song_limit.get() == limit
+                                    >
+                                        {limit.label()}
+                                    </option>
+                                }
+                            })
+                            .collect_view()}
+                    </select>
+                </div>
+                <div class="number-of-songs">
+                    <label for="settings-jam-max-songs">"Max Songs"</label>
+                    <input
+                        type="number"
+                        prop:value=max_song_count
+                        on:input=move |ev| {
+                            set_max_song_count.set(event_target_value(&ev).parse().unwrap_or(0))
+                        }
+                        placeholder="ex. 10"
+                        id="settings-jam-max-songs"
+                    />
+                </div>
+                <div class="no-repeat">
+                    <label for="settings-jam-no-repeat">"No Repeat (minutes)"</label>
+                    <input
+                        type="number"
+                        prop:value=no_repeat_minutes
+                        on:input=move |ev| {
+                            set_no_repeat_minutes.set(event_target_value(&ev).parse().unwrap_or(0))
+                        }
+                        placeholder="ex. 60"
+                        id="settings-jam-no-repeat"
+                    />
+                </div>
+                <div class="external-changes">
+                    <label for="settings-jam-external-changes">"Changes in the Spotify App"</label>
+                    <select
+                        id="settings-jam-external-changes"
+                        on:change=move |ev| {
+                            set_external_changes
+                                .set(ExternalChanges::from_db_str(&event_target_value(&ev)))
+                        }
+                    >
+                        {ExternalChanges::all()
+                            .into_iter()
+                            .map(|policy| {
+                                view! {
+                                    <option
+                                        value=policy.as_db_str()
+                                        prop:selected=move || external_changes.get() == policy
+                                    >
+                                        {policy.label()}
+                                    </option>
+                                }
+                            })
+                            .collect_view()}
+                    </select>
+                </div>
+                <div class="buttons">
+                    <button
+                        class="button"
+                        on:click=move |_| {
+                            save.run((
+                                name.get_untracked(),
+                                max_song_count.get_untracked(),
+                                no_repeat_minutes.get_untracked(),
+                                song_limit.get_untracked(),
+                                external_changes.get_untracked(),
+                            ));
+                            close.run(());
+                        }
+                    >
+                        "Save"
+                    </button>
+                    <button class="button" on:click=move |_| close.run(())>
+                        "Close"
+                    </button>
+                </div>
+            </div>
+        </Modal>
+    }
+}
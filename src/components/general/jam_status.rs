@@ -0,0 +1,80 @@
+use crate::model::{Jam, JamState};
+use leptos::{either::EitherOf4, prelude::*};
+
+/// Tells everyone when a scheduled jam starts or that the jam is paused,
+/// the host and co-hosts can open it for requests, start it early or resume it,
+/// it also tells everyone when spotify can't be reached, so nobody wonders why nothing plays
+#[component]
+pub fn JamStatus(
+    #[prop(into)] jam: Signal<Option<Jam>>,
+    #[prop(optional)] set_state: Option<Callback<JamState>>,
+) -> impl IntoView {
+    let state = Memo::new(move |_| jam.with(|jam| jam.as_ref().map(|jam| jam.state)));
+
+    // formatted in the browser, so it is in the local time of the viewer
+    let (starts_at, set_starts_at) = signal(String::new());
+    Effect::new(move |_| {
+        if let Some(starts_at) = jam.with(|jam| jam.as_ref().and_then(|jam| jam.starts_at)) {
+            use wasm_bindgen::JsValue;
+            let date = js_sys::Date::new(&JsValue::from_f64(starts_at as f64 * 1000.0));
+            set_starts_at.set(date.to_locale_string("default", &JsValue::UNDEFINED).into());
+        }
+    });
+
+    let button = move |label: &'static str, next: JamState| {
+        set_state.map(|set_state| {
+            view! {
+                <button class="button" on:click=move |_| set_state.run(next)>
+                    {label}
+                </button>
+            }
+        })
+    };
+
+    let spotify_unavailable = Memo::new(move |_| {
+        jam.with(|jam| jam.as_ref().is_some_and(|jam| jam.spotify_unavailable_until.is_some()))
+    });
+    let spotify_notice = move || {
+        spotify_unavailable.get().then(|| {
+            view! {
+                <div class="jam-status spotify-unavailable">
+                    <div class="text">
+                        "Spotify can't be reached right now, you can still add and vote for songs, playback continues once it is back"
+                    </div>
+                </div>
+            }
+        })
+    };
+
+    let status = move || match state.get() {
+        Some(JamState::Scheduled) => EitherOf4::A(view! {
+            <div class="jam-status">
+                <div class="text">
+                    {move || format!("The jam starts at {}, requests are not open yet", starts_at.get())}
+                </div>
+                {button("Open for requests", JamState::Open)}
+                {button("Start now", JamState::Playing)}
+            </div>
+        }),
+        Some(JamState::Open) => EitherOf4::B(view! {
+            <div class="jam-status">
+                <div class="text">
+                    {move || format!("The jam starts at {}, add your songs now!", starts_at.get())}
+                </div>
+                {button("Start now", JamState::Playing)}
+            </div>
+        }),
+        Some(JamState::Paused) => EitherOf4::C(view! {
+            <div class="jam-status">
+                <div class="text">"The jam is paused"</div>
+                {button("Resume", JamState::Playing)}
+            </div>
+        }),
+        _ => EitherOf4::D(()),
+    };
+
+    view! {
+        {spotify_notice}
+        {status}
+    }
+}
@@ -1,4 +1,6 @@
 pub mod create;
+pub mod jam_settings;
+pub mod jam_status;
 pub mod join;
 pub mod modal;
 pub mod share;
@@ -10,6 +12,10 @@ pub mod player;
 #[allow(unused_imports)]
 pub use create::*;
 #[allow(unused_imports)]
+pub use jam_settings::*;
+#[allow(unused_imports)]
+pub use jam_status::*;
+#[allow(unused_imports)]
 pub use join::*;
 #[allow(unused_imports)]
 pub use modal::*;
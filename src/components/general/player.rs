@@ -6,10 +6,19 @@ use leptos::{
     *,
 };
 
+/// how often the position is moved forward between updates, in milliseconds
+const INTERPOLATION_INTERVAL: u64 = 250;
+
 #[component]
 pub fn Player(
     #[prop(into)] position: Signal<f32>,
-    #[prop(into)] current_song: ReadSignal<Option<Song>>,
+    #[prop(into)] current_song: Signal<Option<Song>>,
+    /// the position is moved forward between updates, unless the song is paused
+    #[prop(optional, into)]
+    paused: Option<Signal<bool>>,
+    /// called with the percentage the progress bar was clicked at, the bar can't be clicked without it
+    #[prop(optional, into)]
+    seek: Option<Callback<f32>>,
     #[prop(optional)] children: Option<Children>,
 ) -> impl IntoView {
     Effect::new(move |_| {
@@ -21,6 +30,28 @@ pub fn Player(
     });
 
     let song_length = move || current_song.get().map(|s| s.duration).unwrap_or_default();
+
+    // the server only sends the position every few seconds, so it is interpolated in between
+    let (elapsed, set_elapsed) = signal(0u32);
+    Effect::new(move |_| {
+        position.track();
+        set_elapsed.set(0);
+    });
+    leptos_use::use_interval_fn(
+        move || {
+            if !paused.map(|paused| paused.get_untracked()).unwrap_or(false) {
+                set_elapsed.update(|elapsed| *elapsed += INTERPOLATION_INTERVAL as u32);
+            }
+        },
+        INTERPOLATION_INTERVAL,
+    );
+    let position = Memo::new(move |_| {
+        let song_length = song_length();
+        if song_length == 0 {
+            return position.get();
+        }
+        (position.get() + elapsed.get() as f32 / song_length as f32).min(1.0)
+    });
     let (title_overflow, set_title_overflow) = signal(false);
     let (artist_overflow, set_artist_overflow) = signal(false);
 
@@ -81,7 +112,25 @@ pub fn Player(
             </div>
 
             <div class="progress">
-                <div class="bar">
+                <div
+                    class="bar"
+                    class:seekable=seek.is_some()
+                    on:click=move |ev| {
+                        use wasm_bindgen::JsCast;
+                        let Some(seek) = seek else {
+                            return;
+                        };
+                        let bar = match ev.current_target().map(|target| target.dyn_into::<web_sys::Element>()) {
+                            Some(Ok(bar)) => bar,
+                            _ => return,
+                        };
+                        let rect = bar.get_bounding_client_rect();
+                        if rect.width() > 0.0 {
+                            let percentage = (ev.client_x() as f64 - rect.left()) / rect.width();
+                            seek.run(percentage.clamp(0.0, 1.0) as f32);
+                        }
+                    }
+                >
                     <div
                         class="position"
                         style:width=move || format!("{}%", position.get() * 100.0)
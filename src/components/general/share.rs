@@ -1,4 +1,4 @@
-use leptos::{logging::*, prelude::*, *};
+use leptos::{either::Either, logging::*, prelude::*, *};
 use qrcode::render::svg;
 use qrcode::{EcLevel, QrCode, Version};
 
@@ -20,7 +20,10 @@ async fn save_to_clipboard(text: &str) {
 }
 
 #[component]
-pub fn Share(#[prop(into)] jam_id: Signal<String>) -> impl IntoView {
+pub fn Share(
+    #[prop(into)] jam_id: Signal<String>,
+    #[prop(optional)] children: Option<Children>,
+) -> impl IntoView {
     let (base_url, set_base_url) = signal(String::new());
 
     let (clone_jam_id, set_jam_id) = signal(String::new());
@@ -88,6 +91,11 @@ pub fn Share(#[prop(into)] jam_id: Signal<String>) -> impl IntoView {
 
                 "COPY"
             </button>
+            {if let Some(children) = children {
+                Either::Left(children())
+            } else {
+                Either::Right(())
+            }}
         </div>
     }
 }
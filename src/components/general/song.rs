@@ -14,6 +14,8 @@ pub enum SongAction {
         add_vote: Callback<String>,
         remove_vote: Callback<String>,
         vote: Signal<Vote>,
+        /// if some, the song has a button to boost it with credits
+        boost: Option<Callback<String>>,
     },
     Remove {
         remove: Callback<String>,
@@ -34,6 +36,91 @@ impl SongAction {
     }
 }
 
+/// the spotify id of the song whose preview is playing, so starting a preview stops the other ones
+#[derive(Clone, Copy, Debug)]
+pub struct PlayingPreview(pub RwSignal<Option<String>>);
+
+impl PlayingPreview {
+    pub fn new() -> Self {
+        Self(RwSignal::new(None))
+    }
+}
+
+impl Default for PlayingPreview {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// a play button over the album cover that plays the 30 second preview from spotify
+#[component]
+fn SongPreview(spotify_id: String, preview_url: String) -> impl IntoView {
+    let audio_ref: NodeRef<html::Audio> = NodeRef::new();
+    let playing_preview = use_context::<PlayingPreview>().unwrap_or_default();
+
+    let is_playing = {
+        let spotify_id = spotify_id.clone();
+        Memo::new(move |_| playing_preview.0.get().as_deref() == Some(spotify_id.as_str()))
+    };
+
+    // another preview was started, or this one was stopped
+    Effect::new(move |_| {
+        if !is_playing.get()
+            && let Some(audio) = audio_ref.get()
+        {
+            if let Err(e) = audio.pause() {
+                error!("could not pause preview: {:?}", e);
+            }
+            audio.set_current_time(0.0);
+        }
+    });
+
+    on_cleanup(move || {
+        if let Some(Some(audio)) = audio_ref.try_get_untracked() {
+            let _ = audio.pause();
+        }
+    });
+
+    let toggle = move |ev: ev::MouseEvent| {
+        ev.stop_propagation();
+        if is_playing.get_untracked() {
+            playing_preview.0.set(None);
+            return;
+        }
+        playing_preview.0.set(Some(spotify_id.clone()));
+        if let Some(audio) = audio_ref.get_untracked()
+            && let Err(e) = audio.play()
+        {
+            error!("could not play preview: {:?}", e);
+        }
+    };
+
+    let icon = move || {
+        if is_playing.get() {
+            icondata::FaPauseSolid
+        } else {
+            icondata::BsPlayFill
+        }
+    };
+
+    view! {
+        <button
+            class="preview"
+            class:playing=is_playing
+            title=move || if is_playing.get() { "stop the preview" } else { "play a preview" }
+            on:click=toggle
+        >
+            <svg viewBox=move || icon().view_box inner_html=move || icon().data></svg>
+        </button>
+        <audio
+            node_ref=audio_ref
+            src=preview_url
+            preload="none"
+            on:ended=move |_| playing_preview.0.set(None)
+        ></audio>
+    }
+}
+
 #[component]
 pub fn Song(#[prop(optional_no_strip)] song: Option<Song>, song_type: SongAction) -> impl IntoView {
     let loaded = move |song: Song| {
@@ -41,6 +128,8 @@ pub fn Song(#[prop(optional_no_strip)] song: Option<Song>, song_type: SongAction
         let title_ref: NodeRef<html::Div> = NodeRef::new();
         let artist_ref: NodeRef<html::Div> = NodeRef::new();
 
+        let spotify_url = song.spotify_url();
+
         let mut width: u16 = 180;
         if song_type.is_add() {
             width = 150;
@@ -79,7 +168,7 @@ pub fn Song(#[prop(optional_no_strip)] song: Option<Song>, song_type: SongAction
                     let song_id = song.id.clone().unwrap_or_default();
                     move |_| {
                         match song_type {
-                            SongAction::Vote { add_vote, remove_vote, vote } => {
+                            SongAction::Vote { add_vote, remove_vote, vote, .. } => {
                                 if let Some(vote) = vote.get().have_you_voted {
                                     if vote {
                                         log!("Removing vote");
@@ -114,10 +203,25 @@ pub fn Song(#[prop(optional_no_strip)] song: Option<Song>, song_type: SongAction
                 }}
 
                 <div class="info" id="info">
-                    <img
-                        src=song.image_url
-                        alt=format!("This is the album cover of {}", song.name)
-                    />
+                    <div class="cover">
+                        <img
+                            src=song.image_url.clone()
+                            alt=format!("This is the album cover of {}", song.name)
+                        />
+                        {song
+                            .details
+                            .preview_url
+                            .clone()
+                            .filter(|_| !song_type.is_remove())
+                            .map(|preview_url| {
+                                view! {
+                                    <SongPreview
+                                        spotify_id=song.spotify_id.clone()
+                                        preview_url
+                                    />
+                                }
+                            })}
+                    </div>
                     <div
                         class="info-text"
                         id="info-txt"
@@ -165,14 +269,61 @@ pub fn Song(#[prop(optional_no_strip)] song: Option<Song>, song_type: SongAction
                             <span class="bullet-point">"•"</span>
                             <span class="song-duration">{millis_to_min_sec(song.duration)}</span>
                         </div>
+                        <div class="details">
+                            {song
+                                .details
+                                .explicit
+                                .then(|| view! { <span class="explicit" title="explicit">"E"</span> })}
+                            {song
+                                .details
+                                .release_year
+                                .map(|year| view! { <span class="release-year">{year}</span> })}
+                            {song
+                                .details
+                                .popularity
+                                .map(|popularity| {
+                                    view! {
+                                        <span class="popularity" title="popularity on spotify, 0 to 100">
+                                            {format!("pop. {}", popularity)}
+                                        </span>
+                                    }
+                                })}
+                            <a
+                                class="spotify-link"
+                                href=spotify_url
+                                target="_blank"
+                                rel="noopener noreferrer"
+                                on:click=|ev| ev.stop_propagation()
+                            >
+                                "Spotify"
+                            </a>
+                        </div>
                     </div>
                 </div>
 
                 <div class="action">
                     {match song_type {
-                        SongAction::Vote { vote, .. } => {
+                        SongAction::Vote { vote, boost, .. } => {
+                            let song_id = song.id.clone().unwrap_or_default();
                             EitherOf3::A(
-                                view! { <div class="votes">{move || vote.get().votes}</div> },
+                                view! {
+                                    <div class="votes">{move || vote.get().votes}</div>
+                                    {boost
+                                        .map(|boost| {
+                                            view! {
+                                                <button
+                                                    class="boost"
+                                                    title="boost this song with credits"
+                                                    on:click=move |ev| {
+                                                        ev.stop_propagation();
+                                                        boost.run(song_id.clone());
+                                                    }
+                                                >
+                                                    "+"
+                                                </button>
+                                            }
+                                        })}
+                                },
                             )
                         }
                         SongAction::Add(_) => {
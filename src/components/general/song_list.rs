@@ -8,6 +8,8 @@ pub enum SongListAction {
         add_vote: Callback<String>,
         remove_vote: Callback<String>,
         remove_song: Callback<String>,
+        /// if some, songs of others can be boosted with credits
+        boost: Option<Callback<String>>,
     },
     Remove(Callback<String>),
     Add(Callback<String>),
@@ -185,11 +187,12 @@ pub fn SongList(
                                                     log!("votes: {:#?}, song name:{}", votes.get(), name);
                                                 });
                                                 let song_action = match song_list_action {
-                                                    SongListAction::Vote { add_vote, remove_vote, .. } => {
+                                                    SongListAction::Vote { add_vote, remove_vote, boost, .. } => {
                                                         SongAction::Vote {
                                                             add_vote,
                                                             remove_vote,
                                                             vote: votes.into(),
+                                                            boost,
                                                         }
                                                     }
                                                     SongListAction::Remove(cb) => {
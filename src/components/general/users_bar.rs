@@ -1,5 +1,6 @@
+use crate::components::Modal;
 use crate::model::*;
-use icondata::IoClose;
+use icondata::{AiCrownFilled, IoClose, IoSettingsSharp};
 use leptos::{either::Either, logging::log, prelude::*};
 use std::rc::Rc;
 
@@ -7,8 +8,17 @@ use std::rc::Rc;
 pub fn UsersBar(
     #[prop(into)] users: Signal<Option<Vec<User>>>,
     #[prop(optional)] kick_user: Option<Callback<String>>,
+    /// if some, clicking on the crown of a user opens the co-host editor, this should only be given to the host
+    #[prop(optional)]
+    set_permissions: Option<Callback<(String, Permissions)>>,
+    /// if some, the co-host editor also has a button to hand over the jam to the user
+    #[prop(optional)]
+    hand_over: Option<Callback<String>>,
+    #[prop(optional)] open_settings: Option<Callback<()>>,
     close: Callback<()>,
 ) -> impl IntoView {
+    let (editing, set_editing) = signal(None::<User>);
+
     view! {
         <div class="bar">
             <button on:click=move |_| {
@@ -45,11 +55,12 @@ pub fn UsersBar(
                 }}
                 <For
                     each=move || users.get().unwrap_or_default()
-                    key=|user| user.id.clone()
+                    key=|user| (user.id.clone(), user.permissions)
                     children=move |user| {
-                        let user_id = Rc::new(user.id);
+                        let user_id = Rc::new(user.id.clone());
+                        let is_co_host = user.permissions.is_co_host();
                         view! {
-                            <div title=user.name.clone() class="user">
+                            <div title=user.name.clone() class="user" class:co-host=is_co_host>
                                 <img
                                     src=format!("/uploads/{}.webp", user_id)
                                     alt=format!(
@@ -77,6 +88,26 @@ pub fn UsersBar(
                                 } else {
                                     Either::Right(())
                                 }}
+                                {if is_co_host || set_permissions.is_some() {
+                                    Either::Left(
+                                        view! {
+                                            <svg
+                                                class="crown"
+                                                class:editable=set_permissions.is_some()
+                                                on:click=move |_| {
+                                                    if set_permissions.is_some() {
+                                                        set_editing.set(Some(user.clone()));
+                                                    }
+                                                }
+
+                                                viewBox=AiCrownFilled.view_box
+                                                inner_html=AiCrownFilled.data
+                                            ></svg>
+                                        },
+                                    )
+                                } else {
+                                    Either::Right(())
+                                }}
 
                             </div>
                         }
@@ -84,6 +115,123 @@ pub fn UsersBar(
                 />
 
             </div>
+            {if let Some(open_settings) = open_settings {
+                Either::Left(
+                    view! {
+                        <button class="settings" on:click=move |_| open_settings.run(())>
+                            <svg
+                                viewBox=IoSettingsSharp.view_box
+                                inner_html=IoSettingsSharp.data
+                            ></svg>
+                        </button>
+                    },
+                )
+            } else {
+                Either::Right(())
+            }}
+            {if let Some(set_permissions) = set_permissions {
+                Either::Left(
+                    view! {
+                        <Modal visible=Signal::derive(move || {
+                            editing.with(Option::is_some)
+                        })>
+                            {move || {
+                                editing
+                                    .get()
+                                    .map(|user| {
+                                        view! {
+                                            <CoHostEditor
+                                                user
+                                                set_permissions
+                                                hand_over
+                                                close=Callback::new(move |_| set_editing.set(None))
+                                            />
+                                        }
+                                    })
+                            }}
+                        </Modal>
+                    },
+                )
+            } else {
+                Either::Right(())
+            }}
+        </div>
+    }
+}
+
+#[component]
+fn CoHostEditor(
+    user: User,
+    set_permissions: Callback<(String, Permissions)>,
+    hand_over: Option<Callback<String>>,
+    close: Callback<()>,
+) -> impl IntoView {
+    let (permissions, set_local_permissions) = signal(user.permissions);
+
+    let checkbox = move |label: &'static str,
+                         get: fn(&Permissions) -> bool,
+                         set: fn(&mut Permissions, bool)| {
+        view! {
+            <label>
+                <input
+                    type="checkbox"
+                    prop:checked=move || permissions.with(get)
+                    on:change=move |ev| {
+                        set_local_permissions
+                            .update(|permissions| set(permissions, event_target_checked(&ev)))
+                    }
+                />
+                {label}
+            </label>
+        }
+    };
+
+    view! {
+        <div class="co-host-editor">
+            <div class="title">{format!("Permissions of {}", user.name)}</div>
+            {checkbox("Kick users", |p| p.kick, |p, v| p.kick = v)}
+            {checkbox("Remove songs", |p| p.remove_songs, |p, v| p.remove_songs = v)}
+            {checkbox("Skip songs", |p| p.skip, |p, v| p.skip = v)}
+            {checkbox("Change settings", |p| p.change_settings, |p, v| p.change_settings = v)}
+            <div class="buttons">
+                <button
+                    class="button"
+                    on:click=move |_| set_local_permissions.set(Permissions::all())
+                >
+                    "All"
+                </button>
+                <button
+                    class="button"
+                    on:click={
+                        let user_id = user.id.clone();
+                        move |_| {
+                            set_permissions.run((user_id.clone(), permissions.get_untracked()));
+                            close.run(());
+                        }
+                    }
+                >
+                    "Save"
+                </button>
+                {hand_over
+                    .map(|hand_over| {
+                        let user_id = user.id.clone();
+                        view! {
+                            <button
+                                class="button"
+                                title="the user has to log in with spotify to accept"
+                                on:click=move |_| {
+                                    hand_over.run(user_id.clone());
+                                    close.run(());
+                                }
+                            >
+                                "Make host"
+                            </button>
+                        }
+                    })}
+                <button class="button" on:click=move |_| close.run(())>
+                    "Close"
+                </button>
+            </div>
         </div>
     }
 }
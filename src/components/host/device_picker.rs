@@ -0,0 +1,194 @@
+use crate::components::Modal;
+use crate::model::{PlaybackDevice, PlaybackDevices};
+use leptos::{either::Either, logging::error, prelude::*, task::spawn_local};
+
+/// Lets the host choose where the jam plays, the browser or one of their spotify connect devices,
+/// like a speaker, a tv or the desktop app, the server remembers the choice
+#[component]
+pub fn DevicePicker(
+    #[prop(into)] host_id: Signal<Option<String>>,
+    #[prop(into)] jam_id: Signal<Option<String>>,
+    /// the device of the player in this browser, none until it is ready
+    #[prop(into)]
+    browser_device_id: Signal<Option<String>>,
+) -> impl IntoView {
+    let (visible, set_visible) = signal(false);
+    let (error_message, set_error_message) = signal(String::new());
+
+    // loaded every time the picker opens, devices come and go
+    let devices = LocalResource::new(move || async move {
+        if !visible.get() {
+            return Ok(PlaybackDevices::default());
+        }
+        match (host_id.get(), jam_id.get()) {
+            (Some(host_id), Some(jam_id)) => get_playback_devices(host_id, jam_id).await,
+            _ => Ok(PlaybackDevices::default()),
+        }
+    });
+
+    let choose = move |device_id: String, is_browser: bool| {
+        spawn_local(async move {
+            let (Some(host_id), Some(jam_id)) = (host_id.get_untracked(), jam_id.get_untracked())
+            else {
+                return;
+            };
+            match choose_playback_device(host_id, jam_id, device_id, is_browser).await {
+                Ok(()) => set_visible.set(false),
+                Err(e) => {
+                    error!("Error choosing device: {:?}", e);
+                    set_error_message.set(format!("Could not play on that device: {}", e));
+                }
+            }
+        })
+    };
+
+    let device_list = move || {
+        let result = devices.get().map(|devices| devices.take());
+        match result {
+            Some(Ok(devices)) => {
+                let browser_device_id = browser_device_id.get();
+                let plays_in_browser = devices.selected.is_none();
+                let browser = browser_device_id.clone().map(|browser_device_id| {
+                    view! {
+                        <button
+                            class="device"
+                            class:selected=plays_in_browser
+                            on:click=move |_| choose(browser_device_id.clone(), true)
+                        >
+                            <div class="name">"This browser"</div>
+                            <div class="kind">"Web player"</div>
+                        </button>
+                    }
+                });
+                let others = devices
+                    .devices
+                    .into_iter()
+                    .filter(|device| Some(&device.id) != browser_device_id.as_ref())
+                    .map(|device: PlaybackDevice| {
+                        let selected = devices.selected.as_ref() == Some(&device.id);
+                        let id = device.id.clone();
+                        view! {
+                            <button
+                                class="device"
+                                class:selected=selected
+                                on:click=move |_| choose(id.clone(), false)
+                            >
+                                <div class="name">{device.name}</div>
+                                <div class="kind">
+                                    {device.kind}
+                                    {device.is_active.then_some(", playing")}
+                                </div>
+                            </button>
+                        }
+                    })
+                    .collect_view();
+                Either::Left(view! { {browser} {others} })
+            }
+            Some(Err(e)) => Either::Right(format!("Could not load the devices: {}", e)),
+            None => Either::Right("Loading devices...".to_string()),
+        }
+    };
+
+    view! {
+        <button on:click=move |_| set_visible.set(true) class="devices" title="devices">
+            <svg
+                viewBox=icondata::BsSpeakerFill.view_box
+                inner_html=icondata::BsSpeakerFill.data
+            ></svg>
+        </button>
+        <Modal visible>
+            <div class="device-picker">
+                <div class="title">"Play the jam on"</div>
+                <div class="list">{device_list}</div>
+                {move || error_message.get()}
+            </div>
+            <button on:click=move |_| {
+                set_error_message.set(String::new());
+                set_visible.set(false);
+            }>"close"</button>
+        </Modal>
+    }
+}
+
+/// the jam id, if the id belongs to the host of the jam
+#[cfg(feature = "ssr")]
+async fn host_jam_id(
+    host_id: &str,
+    jam_id: &str,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+) -> Result<String, ServerFnError<String>> {
+    match crate::model::check_id_type(host_id, Some(jam_id), transaction).await {
+        Ok(id) if id.is_host() => Ok(id.jam_id),
+        Ok(_) => {
+            leptos_axum::redirect("/");
+            Err(ServerFnError::Request(
+                "the id was found, but it belongs to a user".to_string(),
+            ))
+        }
+        Err(e) => Err(ServerFnError::ServerError(e.to_string())),
+    }
+}
+
+#[server]
+async fn get_playback_devices(
+    host_id: String,
+    jam_id: String,
+) -> Result<PlaybackDevices, ServerFnError<String>> {
+    use crate::model::*;
+    let app_state = expect_context::<AppState>();
+    let mut transaction =
+        app_state.db.pool.begin().await.map_err(|e| {
+            ServerFnError::ServerError(format!("error starting transaction: {}", e))
+        })?;
+    let jam_id = host_jam_id(&host_id, &jam_id, &mut transaction).await?;
+
+    let devices = crate::model::get_playback_devices(
+        &jam_id,
+        &mut transaction,
+        app_state.spotify_credentials,
+    )
+    .await
+    .map_err(|e| ServerFnError::ServerError(e.into()))?;
+
+    transaction
+        .commit()
+        .await
+        .map_err(|e| ServerFnError::ServerError(format!("error committing transaction: {}", e)))?;
+    Ok(devices)
+}
+
+/// the browser is remembered as no device, because the id of its player changes with every page load
+#[server]
+async fn choose_playback_device(
+    host_id: String,
+    jam_id: String,
+    device_id: String,
+    is_browser: bool,
+) -> Result<(), ServerFnError<String>> {
+    use crate::model::*;
+    let app_state = expect_context::<AppState>();
+    let mut transaction =
+        app_state.db.pool.begin().await.map_err(|e| {
+            ServerFnError::ServerError(format!("error starting transaction: {}", e))
+        })?;
+    let jam_id = host_jam_id(&host_id, &jam_id, &mut transaction).await?;
+    let credentials = app_state.spotify_credentials;
+
+    let res = if is_browser {
+        match set_playback_device(None, &jam_id, &mut transaction, credentials.clone()).await {
+            Ok(()) => {
+                switch_playback_to_device(&device_id, &jam_id, &mut transaction, credentials).await
+            }
+            Err(e) => Err(e),
+        }
+    } else {
+        set_playback_device(Some(&device_id), &jam_id, &mut transaction, credentials).await
+    };
+    res.map_err(|e| ServerFnError::ServerError(e.into()))?;
+
+    transaction
+        .commit()
+        .await
+        .map_err(|e| ServerFnError::ServerError(format!("error committing transaction: {}", e)))?;
+    Ok(())
+}
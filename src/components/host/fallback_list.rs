@@ -0,0 +1,141 @@
+use crate::components::{Search, Song, SongAction};
+use crate::model::{
+    Fallback, Vote,
+    real_time::{SearchQuery, SearchResult},
+};
+use leptos::prelude::*;
+
+/// The songs that are played when nobody queued a song, the host can import a spotify playlist,
+/// add tracks through the search, remove and reorder them and choose to shuffle them
+#[component]
+pub fn FallbackList(
+    #[prop(into)] fallback: Signal<Option<Fallback>>,
+    #[prop(into)] search_result: Signal<Option<SearchResult>>,
+    search: Callback<(SearchQuery, u32, String)>,
+    import_playlist: Callback<String>,
+    clear: Callback<()>,
+    add_track: Callback<String>,
+    remove_track: Callback<String>,
+    /// the id of the track and the position it is moved to
+    move_track: Callback<(String, u32)>,
+    set_shuffle: Callback<bool>,
+) -> impl IntoView {
+    let (playlist, set_playlist) = signal(String::new());
+    Effect::new(move |_| {
+        if let Some(playlist_id) = fallback.with(|f| f.as_ref().and_then(|f| f.playlist_id.clone()))
+        {
+            set_playlist.set(format!("https://open.spotify.com/playlist/{}", playlist_id));
+        }
+    });
+
+    let shuffle = move || fallback.with(|f| f.as_ref().map(|f| f.shuffle).unwrap_or(false));
+    let tracks = move || {
+        fallback
+            .get()
+            .map(|f| {
+                let track_count = f.tracks.len();
+                f.tracks
+                    .into_iter()
+                    .enumerate()
+                    .map(|(position, track)| {
+                        let played = f.played.contains(&track.spotify_id);
+                        (position, track_count, played, track)
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default()
+    };
+
+    view! {
+        <div class="fallback-list">
+            <div class="header">
+                <div class="title">"Fallback"</div>
+                <label class="shuffle">
+                    <input
+                        type="checkbox"
+                        prop:checked=shuffle
+                        on:change=move |ev| set_shuffle.run(event_target_checked(&ev))
+                    />
+                    "Shuffle"
+                </label>
+            </div>
+            <div class="playlist">
+                <input
+                    type="text"
+                    prop:value=playlist
+                    on:input=move |ev| set_playlist.set(event_target_value(&ev))
+                    placeholder="link to a spotify playlist"
+                />
+                <button
+                    class="button"
+                    on:click=move |_| import_playlist.run(playlist.get_untracked())
+                >
+                    "Import"
+                </button>
+                <button
+                    class="button"
+                    on:click=move |_| {
+                        set_playlist.set(String::new());
+                        clear.run(());
+                    }
+                >
+                    "Clear"
+                </button>
+            </div>
+            <Search
+                search_result
+                search
+                add_song=add_track
+                loaded=Signal::derive(move || fallback.with(Option::is_some))
+            />
+            <div class="tracks">
+                <For
+                    each=tracks
+                    key=|(position, _, played, track)| {
+                        (track.id.clone(), *position, *played)
+                    }
+                    children=move |(position, track_count, played, track)| {
+                        let track_id = track.id.clone().unwrap_or_default();
+                        let move_up = {
+                            let track_id = track_id.clone();
+                            move |_| move_track.run((track_id.clone(), position as u32 - 1))
+                        };
+                        let move_down = move |_| {
+                            move_track.run((track_id.clone(), position as u32 + 1))
+                        };
+                        view! {
+                            <div class="track" class:played=played>
+                                <Song
+                                    song=Some(track)
+                                    song_type=SongAction::Remove {
+                                        remove: remove_track,
+                                        vote: Signal::stored(Vote {
+                                            votes: 0,
+                                            have_you_voted: None,
+                                        }),
+                                    }
+                                />
+                                <div class="move">
+                                    <button
+                                        on:click=move_up
+                                        disabled=position == 0
+                                        title="move up"
+                                    >
+                                        "▲"
+                                    </button>
+                                    <button
+                                        on:click=move_down
+                                        disabled=position + 1 == track_count
+                                        title="move down"
+                                    >
+                                        "▼"
+                                    </button>
+                                </div>
+                            </div>
+                        }
+                    }
+                />
+            </div>
+        </div>
+    }
+}
@@ -1,2 +1,10 @@
 mod player;
-pub use player::*;
\ No newline at end of file
+pub use player::*;
+mod fallback_list;
+pub use fallback_list::*;
+mod device_picker;
+pub use device_picker::*;
+mod remote_controls;
+pub use remote_controls::*;
+mod volume_control;
+pub use volume_control::*;
@@ -1,5 +1,6 @@
 use std::time::Duration;
 
+use super::{DevicePicker, VolumeControl};
 use crate::components::general::{self, modal::*};
 use leptos::{
     either::*,
@@ -13,16 +14,35 @@ use task::spawn_local;
 
 use crate::model;
 
+/// how often the position of the player is sent to the server, in milliseconds
+const POSITION_REPORT_INTERVAL: u64 = 5000;
+
 #[component]
 pub fn Player(
     #[prop(into)] host_id: Signal<Option<String>>,
-    #[prop(into)] set_song_position: Callback<f32>,
+    #[prop(into)] jam_id: Signal<Option<String>>,
+    /// the percentage and the spotify id of the song the player is at
+    #[prop(into)] set_song_position: Callback<(f32, String)>,
+    #[prop(into)] skip: Callback<()>,
+    /// pausing goes through the server, so the whole jam is paused, not just the player
+    #[prop(into)] set_paused: Callback<bool>,
+    /// the position in the current song in milliseconds, seeking goes through the server, so the users follow
+    #[prop(into)]
+    seek: Callback<u32>,
+    /// from 0 to 100
+    #[prop(into)]
+    volume: Signal<u8>,
+    #[prop(into)] set_volume: Callback<u8>,
 ) -> impl IntoView {
     let (error_message, set_error_message) = signal(String::new());
+    // spotify rejected the login of the host, nothing can be played until they log in again
+    let (login_expired, set_login_expired) = signal(false);
 
     let set_global_song_position = set_song_position;
 
     let (player_is_connected, set_player_is_connected) = signal(false);
+    // the id of the player in this browser, so it can be told apart from the other devices of the host
+    let (browser_device_id, set_browser_device_id) = signal(None::<String>);
 
     let (current_song, set_current_song) = signal(None::<model::Song>);
     let (playing, set_playing) = signal(false);
@@ -43,14 +63,17 @@ pub fn Player(
                 votes: 0,
                 have_you_voted: None,
             },
+            details: Default::default(),
         }));
     };
 
     let switch_device = move |device_id: String| {
         spawn_local(async move {
             if let Some(host_id) = host_id.try_get_untracked() {
-                if let Some(host_id) = host_id {
-                    if let Err(e) = change_playback_device(device_id, host_id).await {
+                if let Some(host_id) = host_id
+                    && let Some(jam_id) = jam_id.get_untracked()
+                {
+                    if let Err(e) = change_playback_device(device_id, host_id, jam_id).await {
                         set_error_message.set(format!("Error switching device: {:?}", e));
                     }
                 } else {
@@ -83,8 +106,10 @@ pub fn Player(
             let mut res = None;
             while token.is_err() {
                 token = {
-                    if let Some(host_id) = host_id.get_untracked() {
-                        get_access_token(host_id).await
+                    if let Some(host_id) = host_id.get_untracked()
+                        && let Some(jam_id) = jam_id.get_untracked()
+                    {
+                        get_access_token(host_id, jam_id).await
                     } else {
                         use leptos_router::NavigateOptions;
                         use_navigate()("/", NavigateOptions::default());
@@ -98,17 +123,17 @@ pub fn Player(
                         error!("Error getting token: {:?}", e);
                         sleep(Duration::from_secs(2)).await;
                     }
-                    Ok(token) => {
+                    Ok(None) => {
+                        set_login_expired.set(true);
+                        break;
+                    }
+                    Ok(Some(token)) => {
                         res = Some(token.clone());
                         break;
                     }
                 }
             }
-            if let Some(token) = res {
-                token
-            } else {
-                panic!("token error");
-            }
+            res
         }
     };
 
@@ -119,13 +144,16 @@ pub fn Player(
             if sp::player_ready() && !host_id.with(Option::is_some) {
                 return;
             }
-            let token = get_token().await;
+            let token = match get_token().await {
+                Some(token) => token,
+                None => return,
+            };
             get_token_action.dispatch(());
 
             log!("initializing player with token: {:?}", token);
             sp::init(
                 move || {
-                    let t = match get_token_action.value().get_untracked() {
+                    let t = match get_token_action.value().get_untracked().flatten() {
                         Some(t) => t,
                         None => token.clone(),
                     };
@@ -136,6 +164,7 @@ pub fn Player(
                         set_error_message.set(format!("Error adding listener: {:?}", e));
                     }
                     if let Err(e) = sp::add_listener!("ready", move |player: sp::Player| {
+                        set_browser_device_id.set(Some(player.device_id.clone()));
                         switch_device(player.device_id);
                     }) {
                         set_error_message.set(format!("Error adding listener: {:?}", e));
@@ -156,13 +185,7 @@ pub fn Player(
         log!("player is connected:{}", is_loaded.get());
     });
 
-    let toggle_play = move || {
-        spawn_local(async move {
-            if let Err(e) = sp::toggle_play().await {
-                set_error_message.set(format!("Error toggling play: {:?}", e));
-            }
-        })
-    };
+    let toggle_play = move || set_paused.run(playing.get_untracked());
 
     let calculate_percentage = move |current_time: i32| {
         current_time as f32
@@ -174,11 +197,20 @@ pub fn Player(
         spawn_local(async move {
             if is_loaded.get_untracked() {
                 if let Ok(Some(state)) = sp::get_current_state().await {
-                    let percentage = calculate_percentage(state.position);
-                    if !(position_percentage.get() > 0.99 && percentage > 0.99) {
-                        set_global_song_position.run(percentage);
-                        set_position_percentage.set(percentage);
-                    }
+                    set_position_percentage.set(calculate_percentage(state.position));
+                }
+            }
+        })
+    };
+    // the server keeps the time itself, the reports only correct it if it drifted off
+    let report_position = move || {
+        spawn_local(async move {
+            if is_loaded.get_untracked() {
+                if let Ok(Some(state)) = sp::get_current_state().await {
+                    set_global_song_position.run((
+                        calculate_percentage(state.position),
+                        state.track_window.current_track.id,
+                    ));
                 }
             }
         })
@@ -191,6 +223,12 @@ pub fn Player(
                 },
                 10,
             );
+            leptos_use::use_interval_fn(
+                move || {
+                    report_position();
+                },
+                POSITION_REPORT_INTERVAL,
+            );
         }
     });
 
@@ -211,7 +249,26 @@ pub fn Player(
                 set_error_message.set("".into());
             }>"close"</button>
         </Modal>
-        <general::Player current_song position=position_percentage>
+        <Modal visible=login_expired>
+            "Spotify doesn't accept your login anymore, log in again to keep the music playing, your jams are still there"
+            <button on:click=move |_| {
+                spawn_local(async move {
+                    if let Err(e) = general::create::redirect_to_spotify_oauth().await {
+                        error!("Error redirecting to Spotify OAuth: {}", e);
+                    }
+                });
+            }>"Log in with Spotify"</button>
+        </Modal>
+        <general::Player
+            current_song
+            position=position_percentage
+            paused=Signal::derive(move || !playing.get())
+            seek=Callback::new(move |percentage: f32| {
+                let duration = current_song
+                    .with_untracked(|song| song.as_ref().map(|song| song.duration).unwrap_or(0));
+                seek.run((percentage * duration as f32) as u32);
+            })
+        >
             <button
                 on:click=move |_| {
                     toggle_play();
@@ -250,6 +307,14 @@ pub fn Player(
                 }}
 
             </button>
+            <button on:click=move |_| skip.run(()) class="skip" title="skip">
+                <svg
+                    viewBox=icondata::BsSkipEndFill.view_box
+                    inner_html=icondata::BsSkipEndFill.data
+                ></svg>
+            </button>
+            <DevicePicker host_id jam_id browser_device_id />
+            <VolumeControl volume set_volume />
         </general::Player>
     }
 }
@@ -258,6 +323,7 @@ pub fn Player(
 async fn change_playback_device(
     device_id: String,
     host_id: String,
+    jam_id: String,
 ) -> Result<(), ServerFnError<String>> {
     use crate::model::*;
     let app_state = expect_context::<AppState>();
@@ -267,7 +333,7 @@ async fn change_playback_device(
         })?;
     let credentials = app_state.spotify_credentials;
 
-    let jam_id = match model::check_id_type(&host_id, &mut transaction).await {
+    let jam_id = match model::check_id_type(&host_id, Some(&jam_id), &mut transaction).await {
         Ok(id) => match id.id {
             IdType::Host(_) => id.jam_id,
             _ => {
@@ -280,12 +346,37 @@ async fn change_playback_device(
         Err(e) => return Err(ServerFnError::ServerError(e.to_string())),
     };
 
-    if let Err(e) =
-        model::switch_playback_to_device(&device_id, &jam_id, &mut transaction, credentials).await
+    // the browser doesn't take the playback from a device the host chose
+    match model::get_playback_device(&jam_id, &mut *transaction).await {
+        Ok(None) => (),
+        Ok(Some(_)) => return Ok(()),
+        Err(e) => return Err(ServerFnError::ServerError(e.to_string())),
+    }
+
+    if let Err(e) = model::switch_playback_to_device(
+        &device_id,
+        &jam_id,
+        &mut transaction,
+        credentials.clone(),
+    )
+    .await
     {
         return Err(ServerFnError::ServerError(e.to_string()));
     };
 
+    // a new player starts at full volume, so it gets the volume the host set before
+    match model::get_volume(&jam_id, &mut *transaction).await {
+        Ok(100) => (),
+        Ok(percent) => {
+            if let Err(e) =
+                model::set_playback_volume(percent, &jam_id, &mut transaction, credentials).await
+            {
+                return Err(ServerFnError::ServerError(e.to_string()));
+            }
+        }
+        Err(e) => return Err(ServerFnError::ServerError(e.to_string())),
+    }
+
     transaction
         .commit()
         .await
@@ -294,8 +385,12 @@ async fn change_playback_device(
     Ok(())
 }
 
+/// none if the host has to log in with spotify again
 #[server]
-async fn get_access_token(host_id: String) -> Result<rspotify::Token, ServerFnError<String>> {
+async fn get_access_token(
+    host_id: String,
+    jam_id: String,
+) -> Result<Option<rspotify::Token>, ServerFnError<String>> {
     use crate::model::*;
 
     let app_state = expect_context::<AppState>();
@@ -305,7 +400,7 @@ async fn get_access_token(host_id: String) -> Result<rspotify::Token, ServerFnEr
         })?;
     let credentials = app_state.spotify_credentials;
 
-    let id = check_id_type(&host_id, &mut transaction).await;
+    let id = check_id_type(&host_id, Some(&jam_id), &mut transaction).await;
     let id = match id {
         Ok(id) => id,
         Err(e) => return Err(ServerFnError::ServerError(e.to_string())),
@@ -319,8 +414,10 @@ async fn get_access_token(host_id: String) -> Result<rspotify::Token, ServerFnEr
         ));
     };
 
+    // committed either way, so an expired login is remembered
     let token = match crate::model::get_access_token(&mut transaction, &jam_id, credentials).await {
-        Ok(token) => token,
+        Ok(token) => Some(token),
+        Err(Error::SpotifyLoginExpired(_)) => None,
         Err(e) => return Err(ServerFnError::ServerError(e.into())),
     };
 
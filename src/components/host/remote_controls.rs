@@ -0,0 +1,75 @@
+use super::{DevicePicker, VolumeControl};
+use crate::components::general;
+use crate::model::Song;
+use leptos::{either::Either, prelude::*};
+
+/// The controls of the host player without the player itself, for the remote page,
+/// the song and the position come from the server, the music plays on the output page or another device
+#[component]
+pub fn RemoteControls(
+    #[prop(into)] host_id: Signal<Option<String>>,
+    #[prop(into)] jam_id: Signal<Option<String>>,
+    current_song: ReadSignal<Option<Song>>,
+    #[prop(into)] position: Signal<f32>,
+    #[prop(into)] paused: Signal<bool>,
+    #[prop(into)] skip: Callback<()>,
+    /// pausing goes through the server, so the whole jam is paused
+    #[prop(into)]
+    set_paused: Callback<bool>,
+    /// the position in the current song in milliseconds
+    #[prop(into)]
+    seek: Callback<u32>,
+    /// from 0 to 100
+    #[prop(into)]
+    volume: Signal<u8>,
+    #[prop(into)] set_volume: Callback<u8>,
+) -> impl IntoView {
+    let seek = Callback::new(move |percentage: f32| {
+        let duration = current_song
+            .with_untracked(|song| song.as_ref().map(|song| song.duration).unwrap_or(0));
+        seek.run((percentage * duration as f32) as u32);
+    });
+
+    view! {
+        <general::Player current_song position paused seek>
+            <button
+                on:click=move |_| set_paused.run(!paused.get_untracked())
+                class="play-pause"
+                title=move || if paused.get() { "play" } else { "pause" }
+            >
+                {move || {
+                    if paused.get() {
+                        Either::Left(
+                            view! {
+                                <svg
+                                    viewBox=icondata::BsPlayFill.view_box
+                                    inner_html=icondata::BsPlayFill.data
+                                    class="play"
+                                ></svg>
+                            },
+                        )
+                    } else {
+                        Either::Right(
+                            view! {
+                                <svg
+                                    viewBox=icondata::FaPauseSolid.view_box
+                                    inner_html=icondata::FaPauseSolid.data
+                                    class="pause"
+                                ></svg>
+                            },
+                        )
+                    }
+                }}
+            </button>
+            <button on:click=move |_| skip.run(()) class="skip" title="skip">
+                <svg
+                    viewBox=icondata::BsSkipEndFill.view_box
+                    inner_html=icondata::BsSkipEndFill.data
+                ></svg>
+            </button>
+            // there is no player in this browser, so it can't be picked
+            <DevicePicker host_id jam_id browser_device_id=Signal::derive(|| None) />
+            <VolumeControl volume set_volume />
+        </general::Player>
+    }
+}
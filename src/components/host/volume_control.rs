@@ -0,0 +1,33 @@
+use leptos::prelude::*;
+
+/// A slider for the volume of the player of the host, the volume is set through the server,
+/// so it works the same for the browser player and for spotify connect devices
+#[component]
+pub fn VolumeControl(
+    /// from 0 to 100
+    #[prop(into)]
+    volume: Signal<u8>,
+    #[prop(into)] set_volume: Callback<u8>,
+) -> impl IntoView {
+    view! {
+        <div class="volume">
+            <svg
+                viewBox=icondata::BsVolumeUpFill.view_box
+                inner_html=icondata::BsVolumeUpFill.data
+            ></svg>
+            // only sent once the slider is let go, every step would be a call to spotify
+            <input
+                type="range"
+                min=0
+                max=100
+                title="volume"
+                prop:value=move || volume.get()
+                on:change=move |ev| {
+                    if let Ok(percent) = event_target_value(&ev).parse::<u8>() {
+                        set_volume.run(percent.min(100));
+                    }
+                }
+            />
+        </div>
+    }
+}
@@ -0,0 +1,59 @@
+use crate::components::general::millis_to_min_sec;
+use crate::model::Credits;
+use leptos::prelude::*;
+
+/// how often the countdown to the next credit is updated, in milliseconds
+const TICK_INTERVAL: u64 = 1000;
+
+/// Shows a user how many credits they have, when they get the next one and what things cost
+#[component]
+pub fn CreditsBalance(#[prop(into)] credits: Signal<Option<Credits>>) -> impl IntoView {
+    // only set in the browser, the server renders the balance as it was sent
+    let (now, set_now) = signal(0_i64);
+    Effect::new(move |_| {
+        set_now.set(js_sys::Date::now() as i64);
+        leptos_use::use_interval_fn(
+            move || set_now.set(js_sys::Date::now() as i64),
+            TICK_INTERVAL,
+        );
+    });
+
+    let balance = move || credits.get().map(|credits| credits.balance_at(now.get()));
+    let next_credit_in = move || {
+        let credits = credits.get()?;
+        let now = now.get();
+        if now == 0 || credits.balance_at(now) >= Credits::MAX {
+            return None;
+        }
+        let next_credit_at = credits.next_credit_at?;
+        // the credits earned since the update was sent are counted in the browser
+        let remaining = if now < next_credit_at {
+            next_credit_at - now
+        } else {
+            Credits::INTERVAL - (now - next_credit_at) % Credits::INTERVAL
+        };
+        Some(millis_to_min_sec(remaining as u32))
+    };
+
+    move || {
+        balance().map(|balance| {
+            view! {
+                <div class="credits-balance">
+                    <div class="balance">{format!("{} / {} credits", balance, Credits::MAX)}</div>
+                    {move || {
+                        next_credit_in()
+                            .map(|next| view! { <div class="next">{format!("+1 in {}", next)}</div> })
+                    }}
+                    <div class="costs">
+                        {format!(
+                            "adding a song costs {}, boosting a song costs {}, songs you voted for earn you {} when they play",
+                            Credits::SONG_COST,
+                            Credits::BOOST_COST,
+                            Credits::VOTE_REWARD,
+                        )}
+                    </div>
+                </div>
+            }
+        })
+    }
+}
@@ -1,7 +1,10 @@
 pub mod create_user;
+pub mod credits_balance;
 pub mod search;
 
 #[allow(unused_imports)]
 pub use create_user::*;
 #[allow(unused_imports)]
+pub use credits_balance::*;
+#[allow(unused_imports)]
 pub use search::*;
@@ -1,37 +1,140 @@
 use crate::components::{Song, SongAction};
-use crate::model::{types::real_time::SearchResult, *};
+use crate::model::{
+    types::real_time::{
+        LibrarySource, SearchCollection, SearchKind, SearchQuery, SearchResult, SpotifyLink,
+    },
+    *,
+};
 use icondata::AiSearchOutlined;
-use leptos::{either::Either, prelude::*};
+use leptos::{either::Either, html, prelude::*};
 
+/// how close to the end of the results the next page is loaded, in pixels
+const LOAD_MORE_THRESHOLD: i32 = 200;
+
+/// songs can be added directly, albums, artists and playlists are opened to pick songs,
+/// spotify links can be pasted instead of searching, guests who logged in with spotify can browse their library,
+/// the search callback gets the query, the offset of the page and the id of the search
 #[component]
 pub fn Search(
     #[prop(into)] search_result: Signal<Option<SearchResult>>,
-    search: Callback<(String, String)>,
+    search: Callback<(SearchQuery, u32, String)>,
     add_song: Callback<String>,
     #[prop(into)] loaded: Signal<bool>,
+    /// how many more songs can be added, shown while picking songs from an album, artist or playlist,
+    /// none if there is no limit
+    #[prop(optional, into)]
+    songs_left: Option<Signal<Option<u32>>>,
+    /// some if the library tab is shown, true if the user logged in with spotify
+    #[prop(optional, into)]
+    spotify_connected: Option<Signal<bool>>,
+    #[prop(optional)] connect_spotify: Option<Callback<()>>,
+    #[prop(optional)] disconnect_spotify: Option<Callback<()>>,
 ) -> impl IntoView {
     let (current_result, set_current_result) = signal::<Vec<Song>>(Vec::new());
-    let add_song = Callback::new(move |id| {
+    let (collections, set_collections) = signal::<Vec<SearchCollection>>(Vec::new());
+    let (text, set_text) = signal(String::new());
+    let (kind, set_kind) = signal(SearchKind::default());
+    let (opened, set_opened) = signal(None::<SearchCollection>);
+    let (library, set_library) = signal(None::<LibrarySource>);
+    let (next_offset, set_next_offset) = signal(None::<u32>);
+    let (loading_more, set_loading_more) = signal(false);
+    let results_ref: NodeRef<html::Div> = NodeRef::new();
+
+    // results of older searches are ignored, they can arrive after the newer ones
+    let search_count = StoredValue::new(0_u32);
+    let query = move || match (opened.get_untracked(), library.get_untracked()) {
+        (Some(collection), _) => SearchQuery::Collection(collection),
+        (None, Some(source)) => SearchQuery::Library(source),
+        (None, None) => SearchQuery::Text {
+            query: text.get_untracked(),
+            kind: kind.get_untracked(),
+        },
+    };
+    let new_search = move || {
+        search_count.update_value(|count| *count += 1);
+        set_next_offset.set(None);
+        set_loading_more.set(false);
+        if let Some(results) = results_ref.get_untracked() {
+            results.set_scroll_top(0);
+        }
+        search.run((query(), 0, search_count.get_value().to_string()));
+    };
+
+    let link = Memo::new(move |_| SpotifyLink::parse(&text.get()));
+    // the songs of an album, artist, playlist or the library stay after adding one, so more can be picked
+    let picking = Memo::new(move |_| {
+        opened.get().is_some()
+            || library.get().is_some()
+            || link.get().is_some_and(|link| link.kind != SearchKind::Track)
+    });
+    let has_library = spotify_connected.is_some();
+    let is_spotify_connected =
+        move || spotify_connected.map(|connected| connected.get()).unwrap_or(false);
+    let clear_results = move || {
+        set_opened.set(None);
         set_current_result.set(vec![]);
+        set_collections.set(vec![]);
+    };
+    let songs_left = move || songs_left.and_then(|songs_left| songs_left.get());
+
+    let add_song = Callback::new(move |id: String| {
+        if picking.get_untracked() {
+            set_current_result.update(|songs| songs.retain(|song| song.spotify_id != id));
+        } else {
+            set_current_result.set(vec![]);
+        }
         add_song.run(id);
     });
 
     Effect::new(move |_| {
         if let Some(search_result) = search_result.get() {
-            set_current_result.set(search_result.songs.clone());
+            if search_result.search_id != search_count.get_value().to_string() {
+                return;
+            }
+            if search_result.offset == 0 {
+                set_current_result.set(search_result.songs);
+                set_collections.set(search_result.collections);
+            } else {
+                set_current_result.update(|songs| songs.extend(search_result.songs));
+                set_collections.update(|collections| collections.extend(search_result.collections));
+            }
+            set_next_offset.set(search_result.next_offset);
+            set_loading_more.set(false);
         }
     });
 
+    let load_more = move |_| {
+        let Some(results) = results_ref.get_untracked() else {
+            return;
+        };
+        let near_end = results.scroll_top() + results.client_height()
+            >= results.scroll_height() - LOAD_MORE_THRESHOLD;
+        if !near_end || loading_more.get_untracked() {
+            return;
+        }
+        if let Some(offset) = next_offset.get_untracked() {
+            set_loading_more.set(true);
+            search.run((query(), offset, search_count.get_value().to_string()));
+        }
+    };
+
     view! {
         <div class="search">
             <div class="search-input">
                 <input
                     type="text"
-                    placeholder="Search for a song"
+                    placeholder=move || match kind.get() {
+                        SearchKind::Track => "Search for a song or paste a link",
+                        SearchKind::Album => "Search for an album",
+                        SearchKind::Artist => "Search for an artist",
+                        SearchKind::Playlist => "Search for a playlist",
+                    }
                     on:input=move |ev| {
+                        set_text.set(event_target_value(&ev));
+                        set_opened.set(None);
+                        set_library.set(None);
                         if loaded.get_untracked() {
-                            let id = "";
-                            search.run((event_target_value(&ev), id.to_string()));
+                            new_search();
                         }
                     }
                 />
@@ -93,7 +196,171 @@ pub fn Search(
 
                 </button>
             </div>
-            <div class="search-result">
+            <div class="search-kinds">
+                {SearchKind::all()
+                    .into_iter()
+                    .map(|search_kind| {
+                        view! {
+                            <button
+                                class:selected=move || {
+                                    kind.get() == search_kind && library.get().is_none()
+                                }
+                                on:click=move |_| {
+                                    set_kind.set(search_kind);
+                                    set_library.set(None);
+                                    clear_results();
+                                    if loaded.get_untracked() {
+                                        new_search();
+                                    }
+                                }
+                            >
+                                {search_kind.label()}
+                            </button>
+                        }
+                    })
+                    .collect_view()}
+                {has_library
+                    .then(|| {
+                        view! {
+                            <button
+                                class:selected=move || library.get().is_some()
+                                on:click=move |_| {
+                                    set_library.set(Some(LibrarySource::default()));
+                                    clear_results();
+                                    if loaded.get_untracked() && is_spotify_connected() {
+                                        new_search();
+                                    }
+                                }
+                            >
+                                "Your Library"
+                            </button>
+                        }
+                    })}
+            </div>
+            {move || {
+                library
+                    .get()
+                    .is_some()
+                    .then(|| {
+                        if is_spotify_connected() {
+                            Either::Left(
+                                view! {
+                                    <div class="search-kinds library-sources">
+                                        {LibrarySource::all()
+                                            .into_iter()
+                                            .map(|source| {
+                                                view! {
+                                                    <button
+                                                        class:selected=move || library.get() == Some(source)
+                                                        on:click=move |_| {
+                                                            set_library.set(Some(source));
+                                                            clear_results();
+                                                            if loaded.get_untracked() {
+                                                                new_search();
+                                                            }
+                                                        }
+                                                    >
+                                                        {source.label()}
+                                                    </button>
+                                                }
+                                            })
+                                            .collect_view()}
+                                        <button
+                                            class="disconnect"
+                                            on:click=move |_| {
+                                                clear_results();
+                                                if let Some(disconnect) = disconnect_spotify {
+                                                    disconnect.run(());
+                                                }
+                                            }
+                                        >
+                                            "Log out"
+                                        </button>
+                                    </div>
+                                },
+                            )
+                        } else {
+                            Either::Right(
+                                view! {
+                                    <div class="connect-spotify">
+                                        <span>
+                                            "Log in with Spotify to add songs from your own library"
+                                        </span>
+                                        <button on:click=move |_| {
+                                            if let Some(connect) = connect_spotify {
+                                                connect.run(());
+                                            }
+                                        }>"Log in with Spotify"</button>
+                                    </div>
+                                },
+                            )
+                        }
+                    })
+            }}
+            {move || {
+                let title = match (opened.get(), link.get()) {
+                    (Some(collection), _) => Some(collection.name),
+                    (None, Some(link)) => match link.kind {
+                        SearchKind::Track => None,
+                        SearchKind::Album => Some("Songs from the pasted album".to_string()),
+                        SearchKind::Artist => Some("Top songs of the pasted artist".to_string()),
+                        SearchKind::Playlist => Some("Songs from the pasted playlist".to_string()),
+                    },
+                    _ => None,
+                };
+                title
+                    .map(|title| {
+                        view! {
+                            <div class="opened-collection">
+                                {opened
+                                    .get()
+                                    .is_some()
+                                    .then(|| {
+                                        view! {
+                                            <button on:click=move |_| {
+                                                set_opened.set(None);
+                                                new_search();
+                                            }>"Back"</button>
+                                        }
+                                    })}
+                                <span>{title}</span>
+                                {move || {
+                                    songs_left()
+                                        .map(|left| {
+                                            view! {
+                                                <span class="songs-left">
+                                                    {format!("{} left", left)}
+                                                </span>
+                                            }
+                                        })
+                                }}
+                            </div>
+                        }
+                    })
+            }}
+            <div
+                class="search-result"
+                class:limit-reached=move || picking.get() && songs_left() == Some(0)
+                node_ref=results_ref
+                on:scroll=load_more
+            >
+                <For
+                    each=move || collections.get().into_iter()
+                    key=|collection| collection.spotify_id.clone()
+                    children=move |collection| {
+                        view! {
+                            <Collection
+                                collection
+                                open=Callback::new(move |collection| {
+                                    set_current_result.set(vec![]);
+                                    set_collections.set(vec![]);
+                                    set_opened.set(Some(collection));
+                                    new_search();
+                                })
+                            />
+                        }
+                    }
+                />
                 <For
                     each=move || current_result.get().into_iter()
                     key=|song| song.spotify_id.clone()
@@ -108,3 +375,28 @@ pub fn Search(
         </div>
     }
 }
+
+/// an album, artist or playlist in the search results
+#[component]
+fn Collection(collection: SearchCollection, open: Callback<SearchCollection>) -> impl IntoView {
+    view! {
+        <div
+            class="collection"
+            class:artist=collection.kind == SearchKind::Artist
+            title=collection.name.clone()
+            on:click={
+                let collection = collection.clone();
+                move |_| open.run(collection.clone())
+            }
+        >
+            <img
+                src=collection.image_url.clone()
+                alt=format!("This is the cover of {}", collection.name)
+            />
+            <div class="collection-text">
+                <div class="name">{collection.name.clone()}</div>
+                <div class="subtitle">{collection.subtitle.clone()}</div>
+            </div>
+        </div>
+    }
+}
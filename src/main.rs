@@ -5,16 +5,32 @@ async fn main() {
     use leptos::prelude::*;
     use leptos_axum::generate_route_list;
     use music_jam::router;
-    use music_jam::{app::*, model::types::AppState};
+    use music_jam::{
+        app::*,
+        model::types::{AppState, SpotifyUrls},
+    };
     println!("Starting server...");
     if dotenvy::dotenv().is_err() {
         eprintln!("didn't find env file")
     };
 
-    let spotify_id = std::env::var("SPOTIFY_ID").expect("SPOTIFY_ID must be set");
-    let spotify_secret = std::env::var("SPOTIFY_SECRET").expect("SPOTIFY_SECRET must be set");
     let db_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
     let site_url = std::env::var("SITE_URL").expect("SITE_URL must be set");
+    // points the app at the mock spotify server instead of spotify, for development and tests
+    let spotify_urls = std::env::var("SPOTIFY_MOCK_URL")
+        .ok()
+        .map(|url| SpotifyUrls::mock(&url));
+    if let Some(urls) = &spotify_urls {
+        println!("Using the mock spotify server at: {}", urls.accounts_url);
+    }
+    // the mock server accepts any app, so they don't have to be set for it
+    let spotify_var = |name: &str| match std::env::var(name) {
+        Ok(value) => value,
+        Err(_) if spotify_urls.is_some() => "mock".to_string(),
+        Err(_) => panic!("{} must be set", name),
+    };
+    let spotify_id = spotify_var("SPOTIFY_ID");
+    let spotify_secret = spotify_var("SPOTIFY_SECRET");
 
     println!("Loading configuration...");
     let conf = get_configuration(None).unwrap();
@@ -33,11 +49,20 @@ async fn main() {
         spotify_secret,
         db_url,
         site_url,
+        spotify_urls,
     )
     .await
     .unwrap();
     println!("State loaded...");
 
+    if let Err(e) = state
+        .jam_clocks
+        .start_all(&state.db.pool, state.spotify_credentials.clone())
+        .await
+    {
+        eprintln!("could not start the clocks of the jams: {:?}", e);
+    }
+
     println!("creating router...");
     // build our application with a route
     let app = router::new(routes, state, leptos_options.clone());
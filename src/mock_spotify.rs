@@ -0,0 +1,902 @@
+//! A stand-in for the spotify accounts and web api, so the app can run without a spotify app or a premium account.
+//! It only implements what the app uses: the login, tokens, searching, tracks, albums, artists, playlists,
+//! recommendations, the top tracks, the saved tracks and the playlists of the user and a fake player,
+//! start it with `cargo run --bin mock_spotify --features ssr` and set `SPOTIFY_MOCK_URL` to its url.
+//! Everything else, like audio features or browsing categories, answers with a 404.
+//! The web playback sdk in the browser of the host still needs spotify, everything on the server works without it.
+use axum::{
+    Json, Router,
+    extract::{Path, Query, State},
+    http::{StatusCode, header},
+    response::{IntoResponse, Redirect, Response},
+    routing::{get, post, put},
+};
+use serde_json::{Value, json};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const ARTISTS: [&str; 4] = [
+    "The Mockingbirds",
+    "Stub & Fixture",
+    "Null Pointer",
+    "DJ Localhost",
+];
+const ALBUMS: [&str; 4] = ["Fake It", "Offline Hits", "Unit Tested", "127.0.0.1"];
+/// every playlist has every nth song of the catalog, the first one has all of them
+const PLAYLISTS: [&str; 3] = ["Mock Mix", "Late Night Stubs", "Greatest Fixtures"];
+const TRACKS: [&str; 12] = [
+    "Never Gonna Mock You Up",
+    "Return 200",
+    "Bohemian Rebase",
+    "Sweet Child O' Mine Thread",
+    "Hotel Localhost",
+    "Smells Like Clean Build",
+    "Stairway to Production",
+    "Another Byte the Dust",
+    "Billie Jeans Pattern",
+    "Don't Stop Compiling",
+    "Wonderwall Clock",
+    "Yesterday's Cache",
+];
+
+#[derive(Debug, Clone)]
+struct Track {
+    id: String,
+    name: &'static str,
+    artist: usize,
+    album: usize,
+    duration: Duration,
+    explicit: bool,
+}
+
+/// what the fake device of the host plays
+#[derive(Debug, Default)]
+struct Player {
+    current: Option<String>,
+    queue: VecDeque<String>,
+    /// when the current song would have started, if it played without pauses
+    started_at: Option<Instant>,
+    /// where the song was paused, none if it is playing
+    paused_at: Option<Duration>,
+}
+
+#[derive(Clone)]
+struct MockState {
+    base_url: String,
+    tracks: Arc<Vec<Track>>,
+    player: Arc<Mutex<Player>>,
+}
+
+fn artist_id(artist: usize) -> String {
+    format!("mockartist{:012}", artist)
+}
+
+fn album_id(album: usize) -> String {
+    format!("mockalbum{:013}", album)
+}
+
+fn playlist_id(playlist: usize) -> String {
+    format!("mockplaylist{:010}", playlist)
+}
+
+fn catalog() -> Vec<Track> {
+    TRACKS
+        .iter()
+        .enumerate()
+        .map(|(i, name)| Track {
+            id: format!("mocktrack{:013}", i),
+            name,
+            artist: i % ARTISTS.len(),
+            album: i % ALBUMS.len(),
+            duration: Duration::from_secs(150 + 15 * i as u64),
+            explicit: i % 5 == 4,
+        })
+        .collect()
+}
+
+/// the mock spotify server, the base url is where it is reachable, the covers link to it
+pub fn router(base_url: &str) -> Router {
+    let state = MockState {
+        base_url: base_url.trim_end_matches('/').to_string(),
+        tracks: Arc::new(catalog()),
+        player: Arc::new(Mutex::new(Player::default())),
+    };
+    Router::new()
+        .route("/authorize", get(authorize))
+        .route("/api/token", post(token))
+        .route("/images/:id", get(cover))
+        .route("/v1/me", get(me))
+        .route("/v1/search", get(search))
+        .route("/v1/tracks", get(tracks))
+        .route("/v1/tracks/:id", get(track))
+        .route("/v1/artists/:id/top-tracks", get(artist_top_tracks))
+        .route("/v1/albums/:id/tracks", get(album_tracks))
+        .route("/v1/playlists/:id/tracks", get(playlist_items))
+        .route("/v1/recommendations", get(recommendations))
+        .route("/v1/me/top/tracks", get(top_tracks))
+        .route("/v1/me/tracks", get(saved_tracks))
+        .route("/v1/me/playlists", get(user_playlists))
+        .route("/v1/me/player", put(transfer))
+        .route("/v1/me/player/devices", get(devices))
+        .route("/v1/me/player/currently-playing", get(currently_playing))
+        .route("/v1/me/player/play", put(play))
+        .route("/v1/me/player/pause", put(pause))
+        .route("/v1/me/player/next", post(next))
+        .route("/v1/me/player/seek", put(seek))
+        .route("/v1/me/player/volume", put(volume))
+        .route("/v1/me/player/queue", post(queue))
+        .fallback(not_implemented)
+        .with_state(state)
+}
+
+/// serves the mock spotify server until the process is stopped
+pub async fn serve(addr: &str) -> std::io::Result<()> {
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    let base_url = format!("http://{}", listener.local_addr()?);
+    println!("mock spotify server listening on {}", base_url);
+    println!("start the app with SPOTIFY_MOCK_URL={} to use it", base_url);
+    axum::serve(listener, router(&base_url)).await
+}
+
+fn error(status: StatusCode, message: &str) -> Response {
+    (
+        status,
+        Json(json!({ "error": { "status": status.as_u16(), "message": message } })),
+    )
+        .into_response()
+}
+
+async fn not_implemented() -> Response {
+    error(
+        StatusCode::NOT_FOUND,
+        "this endpoint is not implemented by the mock spotify server",
+    )
+}
+
+/// skips the login, spotify would ask the user first
+async fn authorize(Query(query): Query<HashMap<String, String>>) -> Response {
+    let redirect_uri = match query.get("redirect_uri") {
+        Some(redirect_uri) => redirect_uri,
+        None => return error(StatusCode::BAD_REQUEST, "redirect_uri is missing"),
+    };
+    let state = query.get("state").cloned().unwrap_or_default();
+    Redirect::to(&format!(
+        "{}?code=mock-code-{}&state={}",
+        redirect_uri,
+        cuid2::create_id(),
+        state
+    ))
+    .into_response()
+}
+
+/// hands out a token for a code and for a refresh token alike, a refresh token of "revoked" is rejected,
+/// to try what happens when a user revokes the access
+async fn token(axum::Form(form): axum::Form<HashMap<String, String>>) -> Response {
+    match form.get("grant_type").map(String::as_str) {
+        Some("authorization_code") => (),
+        Some("refresh_token")
+            if form.get("refresh_token").map(String::as_str) == Some("revoked") =>
+        {
+            return error(StatusCode::BAD_REQUEST, "invalid_grant");
+        }
+        Some("refresh_token") => (),
+        _ => return error(StatusCode::BAD_REQUEST, "unsupported_grant_type"),
+    }
+    Json(json!({
+        "access_token": format!("mock-access-{}", cuid2::create_id()),
+        "token_type": "Bearer",
+        "scope": "user-read-playback-state user-modify-playback-state user-read-currently-playing streaming user-read-private user-read-email user-read-recently-played user-top-read user-library-read playlist-read-private playlist-read-collaborative",
+        "expires_in": 3600,
+        "refresh_token": format!("mock-refresh-{}", cuid2::create_id()),
+    }))
+    .into_response()
+}
+
+/// a plain colored square, so the covers show up without the internet
+async fn cover(Path(id): Path<String>) -> Response {
+    let hue = id
+        .bytes()
+        .fold(0u32, |hash, b| hash.wrapping_mul(31).wrapping_add(b as u32))
+        % 360;
+    let svg = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="640" height="640"><rect width="640" height="640" fill="hsl({}, 60%, 45%)"/></svg>"#,
+        hue
+    );
+    ([(header::CONTENT_TYPE, "image/svg+xml")], svg).into_response()
+}
+
+async fn me() -> Json<Value> {
+    Json(json!({
+        "id": "mockhost",
+        "display_name": "Mock Host",
+        "email": "host@example.com",
+        "country": "US",
+        "product": "premium",
+        "external_urls": {},
+        "followers": { "href": null, "total": 0 },
+        "href": null,
+        "images": [],
+        "type": "user",
+        "uri": "spotify:user:mockhost",
+    }))
+}
+
+fn artist_json(artist: usize) -> Value {
+    json!({
+        "external_urls": {},
+        "href": null,
+        "id": artist_id(artist),
+        "name": ARTISTS[artist],
+        "type": "artist",
+        "uri": format!("spotify:artist:{}", artist_id(artist)),
+    })
+}
+
+fn image_json(state: &MockState, id: &str) -> Value {
+    json!({
+        "height": 640,
+        "url": format!("{}/images/{}", state.base_url, id),
+        "width": 640,
+    })
+}
+
+/// every album is by the artist with the same number
+fn album_json(state: &MockState, album: usize) -> Value {
+    json!({
+        "album_type": "album",
+        "artists": [artist_json(album % ARTISTS.len())],
+        "available_markets": [],
+        "external_urls": {},
+        "href": null,
+        "id": album_id(album),
+        "images": [image_json(state, &album_id(album))],
+        "name": ALBUMS[album],
+        "release_date": format!("{}-01-01", 2000 + album * 5),
+        "release_date_precision": "day",
+        "type": "album",
+        "uri": format!("spotify:album:{}", album_id(album)),
+    })
+}
+
+fn track_json(state: &MockState, track: &Track) -> Value {
+    json!({
+        "album": album_json(state, track.album),
+        "artists": [artist_json(track.artist)],
+        "available_markets": [],
+        "disc_number": 1,
+        "duration_ms": track.duration.as_millis() as u64,
+        "explicit": track.explicit,
+        "external_ids": {},
+        "external_urls": {},
+        "href": null,
+        "id": track.id,
+        "is_local": false,
+        "is_playable": true,
+        "name": track.name,
+        "popularity": 50,
+        "preview_url": null,
+        "track_number": 1,
+        "type": "track",
+        "uri": format!("spotify:track:{}", track.id),
+    })
+}
+
+fn full_artist_json(state: &MockState, artist: usize) -> Value {
+    json!({
+        "external_urls": {},
+        "followers": { "href": null, "total": 1000 * (artist + 1) },
+        "genres": [],
+        "href": format!("{}/v1/artists/{}", state.base_url, artist_id(artist)),
+        "id": artist_id(artist),
+        "images": [image_json(state, &artist_id(artist))],
+        "name": ARTISTS[artist],
+        "popularity": 50,
+        "type": "artist",
+        "uri": format!("spotify:artist:{}", artist_id(artist)),
+    })
+}
+
+fn playlist_json(state: &MockState, playlist: usize) -> Value {
+    json!({
+        "collaborative": false,
+        "description": null,
+        "external_urls": {},
+        "href": format!("{}/v1/playlists/{}", state.base_url, playlist_id(playlist)),
+        "id": playlist_id(playlist),
+        "images": [image_json(state, &playlist_id(playlist))],
+        "name": PLAYLISTS[playlist],
+        "owner": {
+            "display_name": "Mock Host",
+            "external_urls": {},
+            "href": format!("{}/v1/users/mockhost", state.base_url),
+            "id": "mockhost",
+            "images": [],
+            "type": "user",
+            "uri": "spotify:user:mockhost",
+        },
+        "public": true,
+        "snapshot_id": "mocksnapshot",
+        "tracks": {
+            "href": format!("{}/v1/playlists/{}/tracks", state.base_url, playlist_id(playlist)),
+            "total": playlist_tracks(state, playlist).len(),
+        },
+        "type": "playlist",
+        "uri": format!("spotify:playlist:{}", playlist_id(playlist)),
+    })
+}
+
+fn playlist_tracks(state: &MockState, playlist: usize) -> Vec<&Track> {
+    state.tracks.iter().step_by(playlist + 1).collect()
+}
+
+fn page_json(items: Vec<Value>, offset: usize, limit: usize, total: usize, url: &str) -> Value {
+    let next = (offset + items.len() < total)
+        .then(|| format!("{}?offset={}&limit={}", url, offset + items.len(), limit));
+    json!({
+        "href": url,
+        "items": items,
+        "limit": limit,
+        "next": next,
+        "offset": offset,
+        "previous": null,
+        "total": total,
+    })
+}
+
+fn paging(query: &HashMap<String, String>) -> (usize, usize) {
+    let offset = query
+        .get("offset")
+        .and_then(|o| o.parse().ok())
+        .unwrap_or(0);
+    let limit = query
+        .get("limit")
+        .and_then(|l| l.parse().ok())
+        .unwrap_or(20);
+    (offset, limit)
+}
+
+/// the id from a plain id or a spotify uri
+fn plain_id(id: &str) -> &str {
+    id.rsplit(':').next().unwrap_or(id)
+}
+
+/// finds songs, albums, artists and playlists whose name contains the query, songs are also found by their artist and album
+async fn search(
+    State(state): State<MockState>,
+    Query(query): Query<HashMap<String, String>>,
+) -> Response {
+    let (offset, limit) = paging(&query);
+    let url = format!("{}/v1/search", state.base_url);
+    let text = query.get("q").map(|q| q.to_lowercase()).unwrap_or_default();
+    let matches = |name: &str| name.to_lowercase().contains(&text);
+    let (key, found) = match query.get("type").map(String::as_str).unwrap_or("track") {
+        "track" => (
+            "tracks",
+            state
+                .tracks
+                .iter()
+                .filter(|track| {
+                    matches(track.name)
+                        || matches(ARTISTS[track.artist])
+                        || matches(ALBUMS[track.album])
+                })
+                .map(|track| track_json(&state, track))
+                .collect::<Vec<_>>(),
+        ),
+        "album" => (
+            "albums",
+            (0..ALBUMS.len())
+                .filter(|album| matches(ALBUMS[*album]))
+                .map(|album| album_json(&state, album))
+                .collect(),
+        ),
+        "artist" => (
+            "artists",
+            (0..ARTISTS.len())
+                .filter(|artist| matches(ARTISTS[*artist]))
+                .map(|artist| full_artist_json(&state, artist))
+                .collect(),
+        ),
+        "playlist" => (
+            "playlists",
+            (0..PLAYLISTS.len())
+                .filter(|playlist| matches(PLAYLISTS[*playlist]))
+                .map(|playlist| playlist_json(&state, playlist))
+                .collect(),
+        ),
+        _ => return error(StatusCode::BAD_REQUEST, "unsupported search type"),
+    };
+    let total = found.len();
+    let items = found.into_iter().skip(offset).take(limit).collect();
+    Json(json!({ key: page_json(items, offset, limit, total, &url) })).into_response()
+}
+
+fn find<'a>(state: &'a MockState, id: &str) -> Option<&'a Track> {
+    state.tracks.iter().find(|track| track.id == plain_id(id))
+}
+
+async fn track(State(state): State<MockState>, Path(id): Path<String>) -> Response {
+    match find(&state, &id) {
+        Some(track) => Json(track_json(&state, track)).into_response(),
+        None => error(StatusCode::NOT_FOUND, "non existing id"),
+    }
+}
+
+async fn tracks(
+    State(state): State<MockState>,
+    Query(query): Query<HashMap<String, String>>,
+) -> Json<Value> {
+    let tracks = query
+        .get("ids")
+        .map(|ids| {
+            ids.split(',')
+                .map(|id| match find(&state, id) {
+                    Some(track) => track_json(&state, track),
+                    None => Value::Null,
+                })
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    Json(json!({ "tracks": tracks }))
+}
+
+async fn artist_top_tracks(State(state): State<MockState>, Path(id): Path<String>) -> Json<Value> {
+    let tracks = state
+        .tracks
+        .iter()
+        .filter(|track| artist_id(track.artist) == plain_id(&id))
+        .map(|track| track_json(&state, track))
+        .collect::<Vec<_>>();
+    Json(json!({ "tracks": tracks }))
+}
+
+async fn top_tracks(
+    State(state): State<MockState>,
+    Query(query): Query<HashMap<String, String>>,
+) -> Json<Value> {
+    let (offset, limit) = paging(&query);
+    let items = state
+        .tracks
+        .iter()
+        .skip(offset)
+        .take(limit)
+        .map(|track| track_json(&state, track))
+        .collect();
+    Json(page_json(
+        items,
+        offset,
+        limit,
+        state.tracks.len(),
+        &format!("{}/v1/me/top/tracks", state.base_url),
+    ))
+}
+
+async fn album_tracks(
+    State(state): State<MockState>,
+    Path(id): Path<String>,
+    Query(query): Query<HashMap<String, String>>,
+) -> Response {
+    let album = match (0..ALBUMS.len()).find(|album| album_id(*album) == plain_id(&id)) {
+        Some(album) => album,
+        None => return error(StatusCode::NOT_FOUND, "non existing id"),
+    };
+    let (offset, limit) = paging(&query);
+    let tracks = state
+        .tracks
+        .iter()
+        .filter(|track| track.album == album)
+        .collect::<Vec<_>>();
+    let items = tracks
+        .iter()
+        .skip(offset)
+        .take(limit)
+        .map(|track| track_json(&state, track))
+        .collect();
+    Json(page_json(
+        items,
+        offset,
+        limit,
+        tracks.len(),
+        &format!("{}/v1/albums/{}/tracks", state.base_url, album_id(album)),
+    ))
+    .into_response()
+}
+
+async fn playlist_items(
+    State(state): State<MockState>,
+    Path(id): Path<String>,
+    Query(query): Query<HashMap<String, String>>,
+) -> Response {
+    let playlist =
+        match (0..PLAYLISTS.len()).find(|playlist| playlist_id(*playlist) == plain_id(&id)) {
+            Some(playlist) => playlist,
+            None => return error(StatusCode::NOT_FOUND, "non existing id"),
+        };
+    let (offset, limit) = paging(&query);
+    let tracks = playlist_tracks(&state, playlist);
+    let items = tracks
+        .iter()
+        .skip(offset)
+        .take(limit)
+        .map(|track| {
+            json!({
+                "added_at": "2024-01-01T00:00:00Z",
+                "added_by": null,
+                "is_local": false,
+                "track": track_json(&state, track),
+            })
+        })
+        .collect();
+    Json(page_json(
+        items,
+        offset,
+        limit,
+        tracks.len(),
+        &format!(
+            "{}/v1/playlists/{}/tracks",
+            state.base_url,
+            playlist_id(playlist)
+        ),
+    ))
+    .into_response()
+}
+
+/// the songs of the artists of the seeds first, then the rest of the catalog, never the seeds themselves,
+/// the seeds of the answer are left empty, the app doesn't read them
+async fn recommendations(
+    State(state): State<MockState>,
+    Query(query): Query<HashMap<String, String>>,
+) -> Response {
+    let seeds = query
+        .get("seed_tracks")
+        .map(|seeds| seeds.split(',').map(plain_id).collect::<Vec<_>>())
+        .unwrap_or_default();
+    if seeds.is_empty() {
+        return error(StatusCode::BAD_REQUEST, "at least one seed is needed");
+    }
+    let limit = query
+        .get("limit")
+        .and_then(|l| l.parse().ok())
+        .unwrap_or(20);
+    let seed_artists = seeds
+        .iter()
+        .filter_map(|seed| find(&state, seed))
+        .map(|track| track.artist)
+        .collect::<Vec<_>>();
+    let mut tracks = state
+        .tracks
+        .iter()
+        .filter(|track| !seeds.contains(&track.id.as_str()))
+        .collect::<Vec<_>>();
+    tracks.sort_by_key(|track| !seed_artists.contains(&track.artist));
+    let tracks = tracks
+        .into_iter()
+        .take(limit)
+        .map(|track| track_json(&state, track))
+        .collect::<Vec<_>>();
+    Json(json!({ "seeds": [], "tracks": tracks })).into_response()
+}
+
+/// every song of the catalog is saved, the newest first
+async fn saved_tracks(
+    State(state): State<MockState>,
+    Query(query): Query<HashMap<String, String>>,
+) -> Json<Value> {
+    let (offset, limit) = paging(&query);
+    let items = state
+        .tracks
+        .iter()
+        .rev()
+        .skip(offset)
+        .take(limit)
+        .map(|track| {
+            json!({
+                "added_at": "2024-01-01T00:00:00Z",
+                "track": track_json(&state, track),
+            })
+        })
+        .collect();
+    Json(page_json(
+        items,
+        offset,
+        limit,
+        state.tracks.len(),
+        &format!("{}/v1/me/tracks", state.base_url),
+    ))
+}
+
+async fn user_playlists(
+    State(state): State<MockState>,
+    Query(query): Query<HashMap<String, String>>,
+) -> Json<Value> {
+    let (offset, limit) = paging(&query);
+    let items = (0..PLAYLISTS.len())
+        .skip(offset)
+        .take(limit)
+        .map(|playlist| playlist_json(&state, playlist))
+        .collect();
+    Json(page_json(
+        items,
+        offset,
+        limit,
+        PLAYLISTS.len(),
+        &format!("{}/v1/me/playlists", state.base_url),
+    ))
+}
+
+impl Player {
+    fn position(&self) -> Duration {
+        match (self.paused_at, self.started_at) {
+            (Some(paused_at), _) => paused_at,
+            (None, Some(started_at)) => started_at.elapsed(),
+            (None, None) => Duration::ZERO,
+        }
+    }
+
+    fn start(&mut self, id: Option<String>) {
+        self.current = id;
+        self.started_at = self.current.as_ref().map(|_| Instant::now());
+        self.paused_at = None;
+    }
+
+    /// moves on to the queued song once the current one is over, like a real device would
+    fn catch_up(&mut self, tracks: &[Track]) {
+        let duration = match self
+            .current
+            .as_ref()
+            .and_then(|id| tracks.iter().find(|track| &track.id == id))
+        {
+            Some(track) => track.duration,
+            None => return,
+        };
+        if self.paused_at.is_none() && self.position() >= duration {
+            let next = self.queue.pop_front();
+            self.start(next);
+        }
+    }
+}
+
+async fn currently_playing(State(state): State<MockState>) -> Response {
+    let mut player = state.player.lock().unwrap_or_else(|e| e.into_inner());
+    player.catch_up(&state.tracks);
+    let track = match player.current.as_ref().and_then(|id| find(&state, id)) {
+        Some(track) => track,
+        None => return StatusCode::NO_CONTENT.into_response(),
+    };
+    Json(json!({
+        "context": null,
+        "timestamp": chrono::Utc::now().timestamp_millis(),
+        "progress_ms": player.position().as_millis() as u64,
+        "is_playing": player.paused_at.is_none(),
+        "item": track_json(&state, track),
+        "currently_playing_type": "track",
+        "actions": { "disallows": {} },
+    }))
+    .into_response()
+}
+
+/// plays the first of the uris, or resumes the current song without a body
+async fn play(State(state): State<MockState>, body: String) -> Response {
+    let mut player = state.player.lock().unwrap_or_else(|e| e.into_inner());
+    let uri = serde_json::from_str::<Value>(&body).ok().and_then(|body| {
+        body["uris"][0]
+            .as_str()
+            .map(|uri| plain_id(uri).to_string())
+    });
+    match uri {
+        Some(id) if find(&state, &id).is_none() => {
+            return error(StatusCode::NOT_FOUND, "non existing id");
+        }
+        Some(id) => player.start(Some(id)),
+        None => {
+            if let Some(paused_at) = player.paused_at.take() {
+                player.started_at = Instant::now().checked_sub(paused_at);
+            }
+        }
+    }
+    StatusCode::NO_CONTENT.into_response()
+}
+
+async fn pause(State(state): State<MockState>) -> StatusCode {
+    let mut player = state.player.lock().unwrap_or_else(|e| e.into_inner());
+    if player.paused_at.is_none() {
+        player.paused_at = Some(player.position());
+    }
+    StatusCode::NO_CONTENT
+}
+
+async fn next(State(state): State<MockState>) -> StatusCode {
+    let mut player = state.player.lock().unwrap_or_else(|e| e.into_inner());
+    let next = player.queue.pop_front();
+    player.start(next);
+    StatusCode::NO_CONTENT
+}
+
+async fn queue(
+    State(state): State<MockState>,
+    Query(query): Query<HashMap<String, String>>,
+) -> Response {
+    let id = match query.get("uri").map(|uri| plain_id(uri).to_string()) {
+        Some(id) if find(&state, &id).is_some() => id,
+        _ => return error(StatusCode::NOT_FOUND, "non existing id"),
+    };
+    let mut player = state.player.lock().unwrap_or_else(|e| e.into_inner());
+    player.queue.push_back(id);
+    StatusCode::NO_CONTENT.into_response()
+}
+
+async fn seek(
+    State(state): State<MockState>,
+    Query(query): Query<HashMap<String, String>>,
+) -> Response {
+    let position = match query
+        .get("position_ms")
+        .and_then(|ms| ms.parse::<u64>().ok())
+    {
+        Some(position) => Duration::from_millis(position),
+        None => return error(StatusCode::BAD_REQUEST, "position_ms is missing"),
+    };
+    let mut player = state.player.lock().unwrap_or_else(|e| e.into_inner());
+    if player.paused_at.is_some() {
+        player.paused_at = Some(position);
+    } else if player.current.is_some() {
+        player.started_at = Instant::now().checked_sub(position);
+    }
+    StatusCode::NO_CONTENT.into_response()
+}
+
+/// the mock plays nothing out loud, so the volume is only checked
+async fn volume(Query(query): Query<HashMap<String, String>>) -> Response {
+    match query
+        .get("volume_percent")
+        .and_then(|percent| percent.parse::<u8>().ok())
+    {
+        Some(percent) if percent <= 100 => StatusCode::NO_CONTENT.into_response(),
+        _ => error(
+            StatusCode::BAD_REQUEST,
+            "volume_percent has to be between 0 and 100",
+        ),
+    }
+}
+
+/// there is only one player, every device plays the same songs, so switching does nothing
+async fn transfer() -> StatusCode {
+    StatusCode::NO_CONTENT
+}
+
+/// a single speaker, so the device picker has something to show next to the browser
+async fn devices() -> Json<Value> {
+    Json(json!({
+        "devices": [{
+            "id": "mockspeaker",
+            "is_active": false,
+            "is_private_session": false,
+            "is_restricted": false,
+            "name": "Mock Speaker",
+            "type": "Speaker",
+            "volume_percent": 50,
+        }]
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::functions::{refresh_token, spotify_client};
+    use crate::model::types::{Error, SpotifyCredentials, SpotifyUrls};
+    use rspotify::{
+        clients::{BaseClient, OAuthClient},
+        model::{AlbumId, ArtistId, PlayableItem, PlaylistId, SearchResult, SearchType, TrackId},
+    };
+
+    /// the mock on a free port, with credentials that point the app at it
+    async fn start() -> SpotifyCredentials {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let router = router(&base_url);
+        tokio::spawn(async move { axum::serve(listener, router).await });
+        let urls = SpotifyUrls::mock(&base_url);
+        SpotifyCredentials {
+            id: "mock".to_string(),
+            secret: "mock".to_string(),
+            api_url: urls.api_url,
+            accounts_url: urls.accounts_url,
+        }
+    }
+
+    fn token(refresh_token: &str) -> rspotify::Token {
+        rspotify::Token {
+            access_token: "mock-access".to_string(),
+            refresh_token: Some(refresh_token.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn refreshes_tokens_until_they_are_revoked() {
+        let credentials = start().await;
+
+        let new_token = refresh_token(token("mock-refresh"), credentials.clone())
+            .await
+            .unwrap();
+        assert!(new_token.access_token.starts_with("mock-access-"));
+
+        let revoked = refresh_token(token("revoked"), credentials).await;
+        assert!(matches!(revoked, Err(Error::SpotifyLoginExpired(_))));
+    }
+
+    #[tokio::test]
+    async fn answers_the_calls_of_the_app() {
+        let credentials = start().await;
+        let client = spotify_client(token("mock-refresh"), &credentials);
+
+        let found = client
+            .search("return", SearchType::Track, None, None, Some(20), Some(0))
+            .await
+            .unwrap();
+        let SearchResult::Tracks(found) = found else {
+            panic!("asked for tracks, got something else");
+        };
+        assert_eq!(found.items.len(), 1);
+
+        let playlists = client
+            .search("stubs", SearchType::Playlist, None, None, Some(20), Some(0))
+            .await
+            .unwrap();
+        assert!(matches!(playlists, SearchResult::Playlists(page) if page.items.len() == 1));
+
+        let seed = TrackId::from_id("mocktrack0000000000000").unwrap();
+        let recommendations = client
+            .recommendations(
+                [],
+                None::<Vec<ArtistId>>,
+                None::<Vec<&str>>,
+                Some([seed.clone()]),
+                None,
+                Some(5),
+            )
+            .await
+            .unwrap();
+        assert_eq!(recommendations.tracks.len(), 5);
+        assert!(
+            recommendations
+                .tracks
+                .iter()
+                .all(|track| track.id.as_ref() != Some(&seed))
+        );
+
+        let album = AlbumId::from_id(album_id(1)).unwrap();
+        let album_tracks = client
+            .album_track_manual(album, None, Some(20), Some(0))
+            .await
+            .unwrap();
+        assert_eq!(album_tracks.items.len(), TRACKS.len() / ALBUMS.len());
+
+        let playlist = PlaylistId::from_id(playlist_id(1)).unwrap();
+        let items = client
+            .playlist_items_manual(playlist, None, None, Some(4), Some(0))
+            .await
+            .unwrap();
+        assert_eq!(items.items.len(), 4);
+        assert!(items.next.is_some());
+        assert!(
+            items
+                .items
+                .iter()
+                .all(|item| matches!(item.track, Some(PlayableItem::Track(_))))
+        );
+
+        let saved = client
+            .current_user_saved_tracks_manual(None, Some(20), Some(0))
+            .await
+            .unwrap();
+        assert_eq!(saved.items.len(), TRACKS.len());
+
+        let user_playlists = client
+            .current_user_playlists_manual(Some(20), Some(0))
+            .await
+            .unwrap();
+        assert_eq!(user_playlists.items.len(), PLAYLISTS.len());
+    }
+}
@@ -0,0 +1,174 @@
+use super::{
+    get_time_until_song_end, go_to_next_song_if_ended, notify, queue_next_song_if_due,
+    start_jam_if_due,
+};
+use crate::model::types::{real_time::Changed, *};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// the clock wakes up at least this often, so it notices when the song changed or the jam started
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Keeps track of the jams that have a running clock, so every jam has exactly one timer,
+/// no matter how many times the host connects
+#[derive(Clone, Debug, Default)]
+pub struct JamClocks(Arc<Mutex<HashSet<String>>>);
+
+impl JamClocks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// starts the clock of the jam, if it isn't running already,
+    /// the clock stops by itself once the jam ends
+    pub fn start(&self, jam_id: String, pool: sqlx::PgPool, credentials: SpotifyCredentials) {
+        match self.0.lock() {
+            Ok(mut running) => {
+                if !running.insert(jam_id.clone()) {
+                    return;
+                }
+            }
+            Err(e) => {
+                eprintln!("the jam clocks are poisoned, could not start clock: {}", e);
+                return;
+            }
+        }
+
+        let clocks = self.clone();
+        tokio::spawn(async move {
+            run_clock(&jam_id, &pool, credentials).await;
+            if let Ok(mut running) = clocks.0.lock() {
+                running.remove(&jam_id);
+            }
+        });
+    }
+
+    /// starts the clocks of all jams, used when the server starts
+    pub async fn start_all(
+        &self,
+        pool: &sqlx::PgPool,
+        credentials: SpotifyCredentials,
+    ) -> Result<(), Error> {
+        let jams = sqlx::query!("SELECT id FROM jams").fetch_all(pool).await?;
+        for jam in jams {
+            self.start(jam.id, pool.clone(), credentials.clone());
+        }
+        Ok(())
+    }
+}
+
+/// starts the jam once it is due, queues the next song shortly before the current one ends,
+/// and then goes to it, until the jam ends
+async fn run_clock(jam_id: &str, pool: &sqlx::PgPool, credentials: SpotifyCredentials) {
+    loop {
+        match get_time_until_song_end(jam_id, pool).await {
+            Ok(Some(remaining)) if remaining.is_zero() => (),
+            Ok(Some(remaining)) => {
+                match queue_next(jam_id, pool, credentials.clone()).await {
+                    Ok(()) => (),
+                    Err(Error::SpotifyLoginExpired(_)) => {
+                        login_expired(jam_id);
+                        return;
+                    }
+                    Err(e) => eprintln!(
+                        "error while queueing the next song in jam {}: {:?}",
+                        jam_id, e
+                    ),
+                }
+                tokio::time::sleep(remaining.min(CHECK_INTERVAL)).await;
+                continue;
+            }
+            Ok(None) => {
+                match start(jam_id, pool, credentials.clone()).await {
+                    Ok(()) => (),
+                    Err(Error::SpotifyLoginExpired(_)) => {
+                        login_expired(jam_id);
+                        return;
+                    }
+                    Err(e) => eprintln!("error while starting jam {}: {:?}", jam_id, e),
+                }
+                tokio::time::sleep(CHECK_INTERVAL).await;
+                continue;
+            }
+            Err(Error::DoesNotExist(_)) => {
+                println!("jam {} ended, stopping its clock", jam_id);
+                return;
+            }
+            Err(Error::SpotifyLoginExpired(_)) => {
+                login_expired(jam_id);
+                return;
+            }
+            Err(e) => {
+                eprintln!("error while reading the clock of jam {}: {:?}", jam_id, e);
+                tokio::time::sleep(CHECK_INTERVAL).await;
+                continue;
+            }
+        }
+
+        match advance(jam_id, pool, credentials.clone()).await {
+            Ok(()) => (),
+            Err(Error::SpotifyLoginExpired(_)) => {
+                login_expired(jam_id);
+                return;
+            }
+            Err(e) => {
+                eprintln!(
+                    "error while going to the next song in jam {}: {:?}",
+                    jam_id, e
+                );
+                tokio::time::sleep(CHECK_INTERVAL).await;
+            }
+        }
+    }
+}
+
+/// nothing can play until the host logs in again, the clock starts again once the host connects
+fn login_expired(jam_id: &str) {
+    println!(
+        "the spotify login of the host of jam {} expired, stopping its clock",
+        jam_id
+    );
+}
+
+/// the changes are only sent and kept if the step worked, a revoked login is remembered when the token is refreshed
+async fn finish(
+    result: Result<Changed, Error>,
+    jam_id: &str,
+    mut transaction: sqlx::Transaction<'_, sqlx::Postgres>,
+) -> Result<(), Error> {
+    let changed = result?;
+    notify(changed, vec![], jam_id, &mut transaction).await?;
+    transaction.commit().await?;
+    Ok(())
+}
+
+async fn start(
+    jam_id: &str,
+    pool: &sqlx::PgPool,
+    credentials: SpotifyCredentials,
+) -> Result<(), Error> {
+    let mut transaction = pool.begin().await?;
+    let result = start_jam_if_due(jam_id, &mut transaction, credentials).await;
+    finish(result, jam_id, transaction).await
+}
+
+async fn advance(
+    jam_id: &str,
+    pool: &sqlx::PgPool,
+    credentials: SpotifyCredentials,
+) -> Result<(), Error> {
+    let mut transaction = pool.begin().await?;
+    let result = go_to_next_song_if_ended(jam_id, &mut transaction, credentials).await;
+    finish(result, jam_id, transaction).await
+}
+
+async fn queue_next(
+    jam_id: &str,
+    pool: &sqlx::PgPool,
+    credentials: SpotifyCredentials,
+) -> Result<(), Error> {
+    let mut transaction = pool.begin().await?;
+    let result = queue_next_song_if_due(jam_id, &mut transaction, credentials).await;
+    finish(result, jam_id, transaction).await
+}
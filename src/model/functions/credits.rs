@@ -0,0 +1,160 @@
+use super::is_song_queued;
+use crate::model::types::*;
+
+pub async fn get_song_limit<'e>(
+    jam_id: &str,
+    executor: impl sqlx::PgExecutor<'e>,
+) -> Result<SongLimit, Error> {
+    let song_limit = sqlx::query!("SELECT song_limit FROM jams WHERE id = $1", jam_id)
+        .fetch_one(executor)
+        .await?
+        .song_limit;
+    Ok(SongLimit::from_db_str(&song_limit))
+}
+
+/// adds the credits the user earned over time since the last time, and returns the new balance
+pub async fn get_credits(
+    user_id: &str,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+) -> Result<Credits, Error> {
+    let user = match sqlx::query!(
+        "SELECT credits, credits_updated_at FROM users WHERE id = $1 FOR UPDATE",
+        user_id
+    )
+    .fetch_optional(&mut **transaction)
+    .await?
+    {
+        Some(user) => user,
+        None => {
+            return Err(Error::DoesNotExist(format!(
+                "user with id {} does not exist, could not get credits",
+                user_id
+            )));
+        }
+    };
+
+    let now = chrono::Utc::now().timestamp_millis();
+    let mut balance = user.credits.max(0) as u32;
+    let mut updated_at = user.credits_updated_at;
+
+    if balance >= Credits::MAX {
+        // a full balance doesn't earn anything, the time only starts counting once credits are spent
+        updated_at = now;
+    } else {
+        let earned = (now - updated_at) / Credits::INTERVAL;
+        balance = (balance as i64 + earned).min(Credits::MAX as i64) as u32;
+        updated_at = if balance >= Credits::MAX {
+            now
+        } else {
+            updated_at + earned * Credits::INTERVAL
+        };
+    }
+
+    sqlx::query!(
+        "UPDATE users SET credits = $1, credits_updated_at = $2 WHERE id = $3",
+        balance as i32,
+        updated_at,
+        user_id
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(Credits {
+        balance,
+        next_credit_at: (balance < Credits::MAX).then_some(updated_at + Credits::INTERVAL),
+    })
+}
+
+/// takes the credits from the balance of the user, fails if the user can't afford it
+pub async fn spend_credits(
+    user_id: &str,
+    amount: u32,
+    action: &str,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+) -> Result<(), Error> {
+    let credits = get_credits(user_id, transaction).await?;
+    if credits.balance < amount {
+        return Err(Error::NotEnoughCredits(format!(
+            "{} costs {} credits, you have {}",
+            action, amount, credits.balance
+        )));
+    }
+
+    sqlx::query!(
+        "UPDATE users SET credits = credits - $1 WHERE id = $2",
+        amount as i32,
+        user_id
+    )
+    .execute(&mut **transaction)
+    .await?;
+    Ok(())
+}
+
+/// rewards everyone who voted for the song, called when it starts playing
+pub async fn reward_voters(
+    song_id: &str,
+    jam_id: &str,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+) -> Result<real_time::Changed, Error> {
+    if get_song_limit(jam_id, &mut **transaction).await? != SongLimit::Credits {
+        return Ok(real_time::Changed::new());
+    }
+
+    let res = sqlx::query!(
+        "UPDATE users SET credits = LEAST(credits + $1, $2)
+        WHERE id IN (SELECT user_id FROM votes WHERE song_id = $3)",
+        Credits::VOTE_REWARD as i32,
+        Credits::MAX as i32,
+        song_id
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    if res.rows_affected() == 0 {
+        return Ok(real_time::Changed::new());
+    }
+    Ok(real_time::Changed::new().credits())
+}
+
+/// moves the song up in the queue, a boost counts like an extra vote
+pub async fn boost_song(
+    song_id: &str,
+    user_id: &str,
+    jam_id: &str,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+) -> Result<real_time::Changed, Error> {
+    if get_song_limit(jam_id, &mut **transaction).await? != SongLimit::Credits {
+        return Err(Error::Forbidden(
+            "songs can only be boosted in jams with credits".to_string(),
+        ));
+    }
+    if is_song_queued(song_id, &mut **transaction).await? {
+        return Err(Error::Forbidden(
+            "this song plays next, it can't be boosted anymore".to_string(),
+        ));
+    }
+
+    let is_in_jam = sqlx::query!(
+        "SELECT EXISTS(SELECT 1 FROM songs WHERE id = $1 AND user_id IN (SELECT id FROM users WHERE jam_id = $2) AND user_id <> $2)",
+        song_id,
+        jam_id
+    )
+    .fetch_one(&mut **transaction)
+    .await?
+    .exists
+    .unwrap_or(false);
+    if !is_in_jam {
+        return Err(Error::DoesNotExist(format!(
+            "song with id {} is not in the queue, could not boost it",
+            song_id
+        )));
+    }
+
+    spend_credits(user_id, Credits::BOOST_COST, "boosting a song", transaction).await?;
+
+    sqlx::query!("UPDATE songs SET boosts = boosts + 1 WHERE id = $1", song_id)
+        .execute(&mut **transaction)
+        .await?;
+
+    Ok(real_time::Changed::new().songs().votes().credits())
+}
@@ -0,0 +1,467 @@
+use super::{
+    get_playlist_tracks, get_recommended_tracks, get_top_tracks, get_track_from_spotify,
+    track_to_song,
+};
+use crate::model::types::*;
+use rand::SeedableRng;
+use rand::seq::IndexedRandom;
+use rspotify::model::FullTrack;
+use std::collections::HashSet;
+
+/// how many of the last played songs are considered as seeds for the recommendations
+const SEED_HISTORY_LENGTH: i64 = 20;
+
+/// how many of the songs played the longest time ago are tried when everything was played already
+const REPLAY_CANDIDATES: i64 = 5;
+
+/// remembers that the song was played in the jam, so it isn't picked again by the fallback
+/// and can't be added again within the no repeat window
+pub async fn record_played_song<'e>(
+    song: &Song,
+    jam_id: &str,
+    executor: impl sqlx::PgExecutor<'e>,
+) -> Result<(), Error> {
+    sqlx::query!(
+        "INSERT INTO played_songs (id, jam_id, spotify_id, song_key, votes, played_at) VALUES ($1, $2, $3, $4, $5, $6)",
+        cuid2::create_id(),
+        jam_id,
+        song.spotify_id,
+        super::song_key(&song.name, &song.artists),
+        song.votes.votes as i32,
+        chrono::Utc::now().timestamp_millis()
+    )
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+pub async fn get_played_spotify_ids<'e>(
+    jam_id: &str,
+    executor: impl sqlx::PgExecutor<'e>,
+) -> Result<HashSet<String>, Error> {
+    let played = sqlx::query!(
+        "SELECT spotify_id FROM played_songs WHERE jam_id = $1",
+        jam_id
+    )
+    .fetch_all(executor)
+    .await?
+    .into_iter()
+    .map(|song| song.spotify_id)
+    .collect();
+    Ok(played)
+}
+
+/// the best liked of the recently played songs, so the recommendations fit the taste of the guests
+async fn get_seeds<'e>(
+    jam_id: &str,
+    executor: impl sqlx::PgExecutor<'e>,
+) -> Result<Vec<String>, Error> {
+    let seeds = sqlx::query!(
+        "SELECT spotify_id FROM (
+            SELECT spotify_id, votes, played_at FROM played_songs
+            WHERE jam_id = $1 ORDER BY played_at DESC LIMIT $2
+        ) recent
+        ORDER BY votes DESC, played_at DESC
+        LIMIT 5",
+        jam_id,
+        SEED_HISTORY_LENGTH
+    )
+    .fetch_all(executor)
+    .await?
+    .into_iter()
+    .map(|song| song.spotify_id)
+    .collect();
+    Ok(seeds)
+}
+
+/// picks a song when nobody queued one, takes the next track of the fallback list of the host,
+/// then songs similar to what was liked in the jam, then the top tracks of the host,
+/// songs that were already played in the jam and songs that break the content policy are skipped
+pub async fn get_fallback_song(
+    jam_id: &str,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    credentials: SpotifyCredentials,
+) -> Result<Song, Error> {
+    let allow_explicit = sqlx::query!("SELECT allow_explicit FROM jams WHERE id = $1", jam_id)
+        .fetch_one(&mut **transaction)
+        .await?
+        .allow_explicit;
+
+    if let Some(song) = get_next_fallback_track(jam_id, allow_explicit, &mut **transaction).await? {
+        return Ok(song);
+    }
+
+    let played = get_played_spotify_ids(jam_id, &mut **transaction).await?;
+    let pick = |tracks: Vec<FullTrack>| {
+        let tracks = tracks
+            .into_iter()
+            .filter(|track| allow_explicit || !track.explicit)
+            .filter(|track| {
+                track
+                    .id
+                    .as_ref()
+                    .map(|id| !played.contains(rspotify::prelude::Id::id(id)))
+                    .unwrap_or(false)
+            })
+            .collect::<Vec<_>>();
+        let mut rng = rand::prelude::StdRng::from_os_rng();
+        tracks.choose(&mut rng).cloned().map(track_to_song)
+    };
+
+    let seeds = get_seeds(jam_id, &mut **transaction).await?;
+    if !seeds.is_empty() {
+        match get_recommended_tracks(&seeds, jam_id, transaction, credentials.clone()).await {
+            Ok(tracks) => {
+                if let Some(song) = pick(tracks) {
+                    return Ok(song);
+                }
+            }
+            Err(e) => eprintln!("error getting song recommendations: {}", e),
+        }
+    }
+
+    match get_top_tracks(jam_id, transaction, credentials.clone()).await {
+        Ok(tracks) => {
+            if let Some(song) = pick(tracks) {
+                return Ok(song);
+            }
+        }
+        Err(e) => eprintln!("error getting the top tracks of the host: {}", e),
+    }
+
+    // everything was played already, so the song that was played the longest time ago comes again,
+    // songs played from the spotify app can break the content policy, so those are passed over
+    let oldest = sqlx::query!(
+        "SELECT spotify_id FROM played_songs WHERE jam_id = $1 ORDER BY played_at ASC LIMIT $2",
+        jam_id,
+        REPLAY_CANDIDATES
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+    for oldest in oldest {
+        let track =
+            get_track_from_spotify(&oldest.spotify_id, jam_id, transaction, credentials.clone())
+                .await?;
+        if allow_explicit || !track.explicit {
+            return Ok(track_to_song(track));
+        }
+    }
+    Err(Error::Spotify(
+        "could not find any song to play, add a song to the queue".to_string(),
+    ))
+}
+
+/// the first track of the fallback list that wasn't played yet, or a random one if the list is shuffled
+async fn get_next_fallback_track<'e>(
+    jam_id: &str,
+    allow_explicit: bool,
+    executor: impl sqlx::PgExecutor<'e>,
+) -> Result<Option<Song>, Error> {
+    let track = sqlx::query!(
+        "SELECT spotify_id, name, album, duration, artists, image_url, explicit FROM fallback_tracks
+        WHERE jam_id = $1
+            AND ($2 OR NOT explicit)
+            AND spotify_id NOT IN (SELECT spotify_id FROM played_songs WHERE jam_id = $1)
+        ORDER BY
+            CASE WHEN (SELECT fallback_shuffle FROM jams WHERE id = $1) THEN random() ELSE 0 END,
+            position
+        LIMIT 1",
+        jam_id,
+        allow_explicit
+    )
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(track.map(|track| Song {
+        id: None,
+        spotify_id: track.spotify_id,
+        user_id: None,
+        name: track.name,
+        artists: track.artists,
+        album: track.album,
+        duration: track.duration as u32,
+        image_url: track.image_url,
+        votes: Vote {
+            votes: 0,
+            have_you_voted: None,
+        },
+        details: SongDetails {
+            explicit: track.explicit,
+            ..Default::default()
+        },
+    }))
+}
+
+pub async fn get_fallback(
+    jam_id: &str,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+) -> Result<Fallback, Error> {
+    let jam = match sqlx::query!(
+        "SELECT fallback_shuffle, fallback_playlist_id FROM jams WHERE id = $1",
+        jam_id
+    )
+    .fetch_optional(&mut **transaction)
+    .await?
+    {
+        Some(jam) => jam,
+        None => {
+            return Err(Error::DoesNotExist(format!(
+                "jam with id {} does not exist, could not get the fallback list",
+                jam_id
+            )));
+        }
+    };
+
+    let tracks = sqlx::query!(
+        "SELECT id, spotify_id, name, album, duration, artists, image_url, explicit FROM fallback_tracks WHERE jam_id = $1 ORDER BY position",
+        jam_id
+    )
+    .fetch_all(&mut **transaction)
+    .await?
+    .into_iter()
+    .map(|track| Song {
+        id: Some(track.id),
+        spotify_id: track.spotify_id,
+        user_id: None,
+        name: track.name,
+        artists: track.artists,
+        album: track.album,
+        duration: track.duration as u32,
+        image_url: track.image_url,
+        votes: Vote {
+            votes: 0,
+            have_you_voted: None,
+        },
+        details: SongDetails {
+            explicit: track.explicit,
+            ..Default::default()
+        },
+    })
+    .collect();
+
+    let played = get_played_spotify_ids(jam_id, &mut **transaction)
+        .await?
+        .into_iter()
+        .collect();
+
+    Ok(Fallback {
+        tracks,
+        shuffle: jam.fallback_shuffle,
+        playlist_id: jam.fallback_playlist_id,
+        played,
+    })
+}
+
+/// appends the tracks to the end of the fallback list
+async fn insert_fallback_tracks(
+    tracks: Vec<FullTrack>,
+    jam_id: &str,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+) -> Result<(), Error> {
+    let mut position = sqlx::query!(
+        "SELECT COALESCE(MAX(position) + 1, 0) AS \"position!\" FROM fallback_tracks WHERE jam_id = $1",
+        jam_id
+    )
+    .fetch_one(&mut **transaction)
+    .await?
+    .position;
+
+    for track in tracks {
+        let explicit = track.explicit;
+        let song = track_to_song(track);
+        sqlx::query!(
+            "INSERT INTO fallback_tracks (id, jam_id, spotify_id, name, album, duration, artists, image_url, explicit, position)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)",
+            cuid2::create_id(),
+            jam_id,
+            song.spotify_id,
+            song.name,
+            song.album,
+            song.duration as i32,
+            &song.artists,
+            song.image_url,
+            explicit,
+            position
+        )
+        .execute(&mut **transaction)
+        .await?;
+        position += 1;
+    }
+    Ok(())
+}
+
+/// replaces the fallback list with the tracks of the playlist,
+/// the playlist can be given as a link, an uri or an id
+pub async fn import_fallback_playlist(
+    playlist: &str,
+    jam_id: &str,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    credentials: SpotifyCredentials,
+) -> Result<real_time::Changed, Error> {
+    let playlist_id = match parse_playlist_id(playlist) {
+        Some(id) => id,
+        None => {
+            return Err(Error::InvalidRequest(format!(
+                "{} is not a spotify playlist",
+                playlist
+            )));
+        }
+    };
+
+    let tracks = get_playlist_tracks(&playlist_id, jam_id, transaction, credentials).await?;
+
+    sqlx::query!("DELETE FROM fallback_tracks WHERE jam_id = $1", jam_id)
+        .execute(&mut **transaction)
+        .await?;
+    sqlx::query!(
+        "UPDATE jams SET fallback_playlist_id = $1 WHERE id = $2",
+        playlist_id,
+        jam_id
+    )
+    .execute(&mut **transaction)
+    .await?;
+    insert_fallback_tracks(tracks, jam_id, transaction).await?;
+
+    Ok(real_time::Changed::new().fallback())
+}
+
+pub async fn clear_fallback(
+    jam_id: &str,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+) -> Result<real_time::Changed, Error> {
+    sqlx::query!("DELETE FROM fallback_tracks WHERE jam_id = $1", jam_id)
+        .execute(&mut **transaction)
+        .await?;
+    sqlx::query!(
+        "UPDATE jams SET fallback_playlist_id = NULL WHERE id = $1",
+        jam_id
+    )
+    .execute(&mut **transaction)
+    .await?;
+    Ok(real_time::Changed::new().fallback())
+}
+
+pub async fn add_fallback_track(
+    spotify_song_id: &str,
+    jam_id: &str,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    credentials: SpotifyCredentials,
+) -> Result<real_time::Changed, Error> {
+    let spotify_song_id = super::parse_track_id(spotify_song_id)?;
+    let track = get_track_from_spotify(&spotify_song_id, jam_id, transaction, credentials).await?;
+    insert_fallback_tracks(vec![track], jam_id, transaction).await?;
+    Ok(real_time::Changed::new().fallback())
+}
+
+pub async fn remove_fallback_track<'e>(
+    track_id: &str,
+    jam_id: &str,
+    executor: impl sqlx::PgExecutor<'e>,
+) -> Result<real_time::Changed, Error> {
+    let res = sqlx::query!(
+        "DELETE FROM fallback_tracks WHERE id = $1 AND jam_id = $2",
+        track_id,
+        jam_id
+    )
+    .execute(executor)
+    .await?;
+
+    if res.rows_affected() == 0 {
+        return Err(Error::DoesNotExist(format!(
+            "track with id {} is not in the fallback list, could not remove it",
+            track_id
+        )));
+    }
+    Ok(real_time::Changed::new().fallback())
+}
+
+/// moves the track to the position, counted from 0, the tracks after it move down by one
+pub async fn move_fallback_track(
+    track_id: &str,
+    position: u32,
+    jam_id: &str,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+) -> Result<real_time::Changed, Error> {
+    let mut track_ids = sqlx::query!(
+        "SELECT id FROM fallback_tracks WHERE jam_id = $1 ORDER BY position FOR UPDATE",
+        jam_id
+    )
+    .fetch_all(&mut **transaction)
+    .await?
+    .into_iter()
+    .map(|track| track.id)
+    .collect::<Vec<_>>();
+
+    let Some(index) = track_ids.iter().position(|id| id == track_id) else {
+        return Err(Error::DoesNotExist(format!(
+            "track with id {} is not in the fallback list, could not move it",
+            track_id
+        )));
+    };
+    let track_id = track_ids.remove(index);
+    track_ids.insert((position as usize).min(track_ids.len()), track_id);
+
+    for (position, track_id) in track_ids.iter().enumerate() {
+        sqlx::query!(
+            "UPDATE fallback_tracks SET position = $1 WHERE id = $2",
+            position as i32,
+            track_id
+        )
+        .execute(&mut **transaction)
+        .await?;
+    }
+    Ok(real_time::Changed::new().fallback())
+}
+
+pub async fn set_fallback_shuffle<'e>(
+    shuffle: bool,
+    jam_id: &str,
+    executor: impl sqlx::PgExecutor<'e>,
+) -> Result<real_time::Changed, Error> {
+    sqlx::query!(
+        "UPDATE jams SET fallback_shuffle = $1 WHERE id = $2",
+        shuffle,
+        jam_id
+    )
+    .execute(executor)
+    .await?;
+    Ok(real_time::Changed::new().fallback())
+}
+
+/// accepts the id, the uri or the link of a spotify playlist, returns the id
+pub fn parse_playlist_id(input: &str) -> Option<String> {
+    if let Some(link) = real_time::SpotifyLink::parse(input) {
+        return (link.kind == real_time::SearchKind::Playlist).then_some(link.spotify_id);
+    }
+
+    let id = input.trim();
+    if !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric()) {
+        Some(id.to_string())
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn playlist_ids_come_from_links_uris_or_ids() {
+        let id = Some("37i9dQZF1DXcBWIGoYBM5M".to_string());
+        assert_eq!(
+            parse_playlist_id("https://open.spotify.com/playlist/37i9dQZF1DXcBWIGoYBM5M?si=x"),
+            id
+        );
+        assert_eq!(
+            parse_playlist_id("spotify:playlist:37i9dQZF1DXcBWIGoYBM5M"),
+            id
+        );
+        assert_eq!(parse_playlist_id(" 37i9dQZF1DXcBWIGoYBM5M "), id);
+        assert_eq!(
+            parse_playlist_id("https://open.spotify.com/track/4uLU6hMCjMI75M1A2tKUQC"),
+            None
+        );
+        assert_eq!(parse_playlist_id("my playlist"), None);
+    }
+}
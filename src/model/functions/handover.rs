@@ -0,0 +1,204 @@
+use super::{
+    get_current_song, get_current_song_position, get_jam_state, get_playback_devices, kick_user,
+    notify, play_song, seek_playback, set_playback_device,
+};
+use crate::model::types::*;
+use real_time::Changed;
+
+/// offers the jam to a user, only one offer can be pending per jam, so this replaces the previous one
+pub async fn offer_handover<'e>(
+    jam_id: &str,
+    user_id: &str,
+    executor: impl sqlx::PgExecutor<'e>,
+) -> Result<Changed, Error> {
+    let res = sqlx::query!(
+        "INSERT INTO handovers (jam_id, user_id)
+        SELECT jam_id, id FROM users WHERE id=$1 AND jam_id=$2 AND id<>$2
+        ON CONFLICT (jam_id) DO UPDATE
+        SET user_id = EXCLUDED.user_id, new_host_id = NULL",
+        user_id,
+        jam_id
+    )
+    .execute(executor)
+    .await?;
+
+    if res.rows_affected() == 0 {
+        return Err(Error::DoesNotExist(format!(
+            "user with id: {} is not in jam: {}, could not hand over the jam",
+            user_id, jam_id
+        )));
+    }
+
+    Ok(Changed::new().handover())
+}
+
+pub async fn cancel_handover<'e>(
+    jam_id: &str,
+    executor: impl sqlx::PgExecutor<'e>,
+) -> Result<Changed, Error> {
+    sqlx::query!("DELETE FROM handovers WHERE jam_id=$1", jam_id)
+        .execute(executor)
+        .await?;
+
+    Ok(Changed::new().handover())
+}
+
+pub async fn decline_handover<'e>(
+    user_id: &str,
+    executor: impl sqlx::PgExecutor<'e>,
+) -> Result<Changed, Error> {
+    let res = sqlx::query!("DELETE FROM handovers WHERE user_id=$1", user_id)
+        .execute(executor)
+        .await?;
+
+    if res.rows_affected() == 0 {
+        return Err(Error::DoesNotExist(format!(
+            "the jam was not offered to user with id: {}, could not decline",
+            user_id
+        )));
+    }
+
+    Ok(Changed::new().handover())
+}
+
+/// returns the id of the user the jam was offered to, if there is a pending offer
+pub async fn get_handover<'e>(
+    jam_id: &str,
+    executor: impl sqlx::PgExecutor<'e>,
+) -> Result<Option<String>, Error> {
+    let handover = sqlx::query!("SELECT user_id FROM handovers WHERE jam_id=$1", jam_id)
+        .fetch_optional(executor)
+        .await?;
+
+    Ok(handover.map(|handover| handover.user_id))
+}
+
+pub async fn is_host_of_jam<'e>(
+    host_id: &str,
+    jam_id: &str,
+    executor: impl sqlx::PgExecutor<'e>,
+) -> Result<bool, Error> {
+    let is_host = sqlx::query!(
+        "SELECT EXISTS(SELECT 1 FROM jams WHERE id=$1 AND host_id=$2)",
+        jam_id,
+        host_id
+    )
+    .fetch_one(executor)
+    .await?
+    .exists
+    .unwrap_or(false);
+
+    Ok(is_host)
+}
+
+/// creates the host the user will become once they authenticated with spotify,
+/// returns the id of the new host, which has to be passed as the state of the oauth flow
+pub async fn accept_handover<'e>(
+    user_id: &str,
+    transaction: &mut sqlx::Transaction<'e, sqlx::Postgres>,
+) -> Result<String, Error> {
+    let new_host_id = cuid2::create_id();
+
+    sqlx::query!("INSERT INTO hosts(id) VALUES ($1)", new_host_id)
+        .execute(&mut **transaction)
+        .await?;
+
+    let res = sqlx::query!(
+        "UPDATE handovers SET new_host_id=$1 WHERE user_id=$2",
+        new_host_id,
+        user_id
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    if res.rows_affected() == 0 {
+        return Err(Error::DoesNotExist(format!(
+            "the jam was not offered to user with id: {}, could not accept",
+            user_id
+        )));
+    }
+
+    Ok(new_host_id)
+}
+
+/// should be called after the new host got its access token, moves the jam to the new host,
+/// so spotify is controlled with the access token of the new host from now on,
+/// the new host leaves the jam as a user and the playback moves to the device they are listening on,
+/// returns the id of the jam if the host was created to take over one
+pub async fn complete_handover<'e>(
+    new_host_id: &str,
+    transaction: &mut sqlx::Transaction<'e, sqlx::Postgres>,
+    credentials: SpotifyCredentials,
+) -> Result<Option<JamId>, Error> {
+    let handover = match sqlx::query!(
+        "DELETE FROM handovers WHERE new_host_id=$1 RETURNING jam_id, user_id",
+        new_host_id
+    )
+    .fetch_optional(&mut **transaction)
+    .await?
+    {
+        Some(handover) => handover,
+        None => return Ok(None),
+    };
+    let jam_id = handover.jam_id;
+
+    sqlx::query!(
+        "UPDATE jams SET host_id=$1 WHERE id=$2",
+        new_host_id,
+        jam_id
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    let changed = kick_user(&handover.user_id, &jam_id, &mut **transaction).await?;
+
+    // spotify errors don't undo the handover, the new host can still pick a device themselves
+    if let Err(e) = move_playback_to_new_host(&jam_id, transaction, credentials).await {
+        eprintln!(
+            "could not move the playback of jam {} to the new host: {}",
+            jam_id, e
+        );
+    }
+
+    notify(changed.handover(), vec![], &jam_id, transaction).await?;
+
+    Ok(Some(jam_id))
+}
+
+/// the remembered device belongs to the spotify account of the old host, so the jam plays on the device
+/// the new host is listening on, or in their browser once the player there is ready,
+/// the current song continues where it was if the jam is playing
+async fn move_playback_to_new_host<'e>(
+    jam_id: &str,
+    transaction: &mut sqlx::Transaction<'e, sqlx::Postgres>,
+    credentials: SpotifyCredentials,
+) -> Result<(), Error> {
+    set_playback_device(None, jam_id, transaction, credentials.clone()).await?;
+
+    let device_id = get_playback_devices(jam_id, transaction, credentials.clone())
+        .await?
+        .devices
+        .into_iter()
+        .find(|device| device.is_active)
+        .map(|device| device.id);
+    let Some(device_id) = device_id else {
+        return Ok(());
+    };
+    set_playback_device(Some(&device_id), jam_id, transaction, credentials.clone()).await?;
+
+    if get_jam_state(jam_id, &mut **transaction).await? != JamState::Playing {
+        return Ok(());
+    }
+    let Some(song) = get_current_song(jam_id, &mut **transaction).await? else {
+        return Ok(());
+    };
+    let position = get_current_song_position(jam_id, &mut **transaction).await?;
+    play_song(&song.spotify_id, jam_id, transaction, credentials.clone()).await?;
+    seek_playback(
+        (position * song.duration as f32) as u32,
+        jam_id,
+        transaction,
+        credentials,
+    )
+    .await
+}
@@ -0,0 +1,167 @@
+use crate::model::types::*;
+
+/// links the freshly created host to the spotify account it logged in with,
+/// if the spotify account already has a host, the new host is merged into it,
+/// returns the id of the host that should be used from now on
+pub async fn claim_host_account<'e>(
+    host_id: &str,
+    spotify_user_id: &str,
+    transaction: &mut sqlx::Transaction<'e, sqlx::Postgres>,
+) -> Result<String, Error> {
+    let existing = sqlx::query!(
+        "SELECT id FROM hosts WHERE spotify_user_id=$1",
+        spotify_user_id
+    )
+    .fetch_optional(&mut **transaction)
+    .await?
+    .map(|host| host.id);
+
+    let existing = match existing {
+        Some(existing) if existing != host_id => existing,
+        Some(_) => return Ok(host_id.to_string()),
+        None => {
+            sqlx::query!(
+                "UPDATE hosts SET spotify_user_id=$1 WHERE id=$2",
+                spotify_user_id,
+                host_id
+            )
+            .execute(&mut **transaction)
+            .await?;
+            return Ok(host_id.to_string());
+        }
+    };
+
+    // the new token replaces the old one, so the account always has a working token
+    sqlx::query!("DELETE FROM access_tokens WHERE host_id=$1", existing)
+        .execute(&mut **transaction)
+        .await?;
+    sqlx::query!(
+        "UPDATE access_tokens SET host_id=$1 WHERE host_id=$2",
+        existing,
+        host_id
+    )
+    .execute(&mut **transaction)
+    .await?;
+    // a jam could have been handed over to the new host
+    sqlx::query!(
+        "UPDATE jams SET host_id=$1 WHERE host_id=$2",
+        existing,
+        host_id
+    )
+    .execute(&mut **transaction)
+    .await?;
+    sqlx::query!("DELETE FROM hosts WHERE id=$1", host_id)
+        .execute(&mut **transaction)
+        .await?;
+
+    Ok(existing)
+}
+
+pub async fn get_past_jams<'e>(
+    host_id: &str,
+    executor: impl sqlx::PgExecutor<'e>,
+) -> Result<Vec<PastJam>, Error> {
+    // sqlx is built without chrono, so the time is read as a unix timestamp
+    let past_jams = sqlx::query!(
+        "SELECT id, jam_id, name, extract(epoch FROM ended_at)::BIGINT AS \"ended_at!\" FROM past_jams WHERE host_id=$1 ORDER BY ended_at DESC",
+        host_id
+    )
+    .fetch_all(executor)
+    .await?
+    .into_iter()
+    .map(|past_jam| PastJam {
+        id: past_jam.id,
+        jam_id: past_jam.jam_id,
+        name: past_jam.name,
+        ended_on: chrono::DateTime::from_timestamp(past_jam.ended_at, 0)
+            .map(|ended_at| ended_at.format("%Y-%m-%d").to_string())
+            .unwrap_or_default(),
+    })
+    .collect();
+
+    Ok(past_jams)
+}
+
+pub async fn get_templates<'e>(
+    host_id: &str,
+    executor: impl sqlx::PgExecutor<'e>,
+) -> Result<Vec<JamTemplate>, Error> {
+    let templates = sqlx::query!(
+        "SELECT * FROM jam_templates WHERE host_id=$1 ORDER BY name",
+        host_id
+    )
+    .fetch_all(executor)
+    .await?
+    .into_iter()
+    .map(|template| JamTemplate {
+        id: template.id,
+        name: template.name,
+        max_song_count: template.max_song_count as u8,
+        queue_strategy: QueueStrategy::from_db_str(&template.queue_strategy),
+        allow_explicit: template.allow_explicit,
+        song_limit: SongLimit::from_db_str(&template.song_limit),
+        no_repeat_minutes: template.no_repeat_minutes as u32,
+        fallback_shuffle: template.fallback_shuffle,
+        external_changes: ExternalChanges::from_db_str(&template.external_changes),
+    })
+    .collect();
+
+    Ok(templates)
+}
+
+/// the id of the template is ignored, a new one is generated, returns the id of the new template
+pub async fn save_template<'e>(
+    host_id: &str,
+    template: &JamTemplate,
+    executor: impl sqlx::PgExecutor<'e>,
+) -> Result<String, Error> {
+    let name = template.name.trim();
+    if name.is_empty() || name.chars().count() > 30 {
+        return Err(Error::InvalidRequest(
+            "the name of the template has to be between 1 and 30 characters".to_string(),
+        ));
+    }
+
+    let template_id = cuid2::create_id();
+    sqlx::query!(
+        "INSERT INTO jam_templates (id, host_id, name, max_song_count, queue_strategy, allow_explicit, song_limit, no_repeat_minutes, fallback_shuffle, external_changes)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)",
+        template_id,
+        host_id,
+        name,
+        template.max_song_count as i16,
+        template.queue_strategy.as_db_str(),
+        template.allow_explicit,
+        template.song_limit.as_db_str(),
+        template.no_repeat_minutes.min(i32::MAX as u32) as i32,
+        template.fallback_shuffle,
+        template.external_changes.as_db_str()
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(template_id)
+}
+
+pub async fn delete_template<'e>(
+    host_id: &str,
+    template_id: &str,
+    executor: impl sqlx::PgExecutor<'e>,
+) -> Result<(), Error> {
+    let res = sqlx::query!(
+        "DELETE FROM jam_templates WHERE id=$1 AND host_id=$2",
+        template_id,
+        host_id
+    )
+    .execute(executor)
+    .await?;
+
+    if res.rows_affected() == 0 {
+        return Err(Error::DoesNotExist(format!(
+            "template with id {} does not exist, could not delete it",
+            template_id
+        )));
+    }
+
+    Ok(())
+}
@@ -22,9 +22,106 @@ pub async fn get_jam<'e>(jam_id: &str, executor: impl sqlx::PgExecutor<'e>) -> R
         id: jam.id,
         name: jam.name,
         max_song_count: jam.max_song_count as u8,
+        queue_strategy: QueueStrategy::from_db_str(&jam.queue_strategy),
+        allow_explicit: jam.allow_explicit,
+        state: JamState::from_db_str(&jam.state),
+        starts_at: jam.starts_at,
+        no_repeat_minutes: jam.no_repeat_minutes as u32,
+        song_limit: SongLimit::from_db_str(&jam.song_limit),
+        external_changes: ExternalChanges::from_db_str(&jam.external_changes),
+        volume_percent: jam.volume_percent as u8,
+        spotify_unavailable_until: super::spotify_unavailable_until(&jam.host_id),
     })
 }
 
+pub async fn get_host_jams<'e>(
+    host_id: &str,
+    transaction: &mut sqlx::Transaction<'e, sqlx::Postgres>,
+) -> Result<Vec<Jam>, Error> {
+    let host_exists = sqlx::query!("SELECT EXISTS(SELECT 1 FROM hosts WHERE id = $1)", host_id)
+        .fetch_one(&mut **transaction)
+        .await?
+        .exists
+        .unwrap_or(false);
+
+    if !host_exists {
+        return Err(Error::DoesNotExist(format!(
+            "host with id {} does not exist, could not get jams",
+            host_id
+        )));
+    }
+
+    let jams = sqlx::query!(
+        "SELECT id, name, max_song_count, queue_strategy, allow_explicit, state, starts_at, no_repeat_minutes, song_limit, external_changes, volume_percent FROM jams WHERE host_id = $1 ORDER BY created_at DESC",
+        host_id
+    )
+    .fetch_all(&mut **transaction)
+    .await?
+    .into_iter()
+    .map(|jam| Jam {
+        id: jam.id,
+        name: jam.name,
+        max_song_count: jam.max_song_count as u8,
+        queue_strategy: QueueStrategy::from_db_str(&jam.queue_strategy),
+        allow_explicit: jam.allow_explicit,
+        state: JamState::from_db_str(&jam.state),
+        starts_at: jam.starts_at,
+        no_repeat_minutes: jam.no_repeat_minutes as u32,
+        song_limit: SongLimit::from_db_str(&jam.song_limit),
+        external_changes: ExternalChanges::from_db_str(&jam.external_changes),
+        volume_percent: jam.volume_percent as u8,
+        spotify_unavailable_until: super::spotify_unavailable_until(host_id),
+    })
+    .collect();
+
+    Ok(jams)
+}
+
+pub async fn update_jam_settings<'e>(
+    jam_id: &str,
+    name: &str,
+    max_song_count: u8,
+    no_repeat_minutes: u32,
+    song_limit: SongLimit,
+    external_changes: ExternalChanges,
+    executor: impl sqlx::PgExecutor<'e>,
+) -> Result<real_time::Changed, Error> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > 30 {
+        return Err(Error::InvalidRequest(
+            "the name of the jam has to be between 1 and 30 characters".to_string(),
+        ));
+    }
+    if max_song_count == 0 {
+        return Err(Error::InvalidRequest(
+            "the max song count has to be at least 1".to_string(),
+        ));
+    }
+
+    let res = sqlx::query!(
+        "UPDATE jams SET name = $1, max_song_count = $2, no_repeat_minutes = $3, song_limit = $4, external_changes = $5 WHERE id = $6",
+        name,
+        max_song_count as i16,
+        no_repeat_minutes.min(i32::MAX as u32) as i32,
+        song_limit.as_db_str(),
+        external_changes.as_db_str(),
+        jam_id
+    )
+    .execute(executor)
+    .await?;
+
+    if res.rows_affected() == 0 {
+        return Err(Error::DoesNotExist(format!(
+            "jam with id {} does not exist, could not update settings",
+            jam_id
+        )));
+    }
+
+    // users get their balance once the jam switches to credits
+    Ok(real_time::Changed::new().settings().credits())
+}
+
+/// returns the spotify user id of the account the host logged in with
 pub async fn create_host<'e>(
     code: String,
     host_id: String,
@@ -32,76 +129,57 @@ pub async fn create_host<'e>(
     reqwest_client: &reqwest::Client,
     executor: impl sqlx::PgExecutor<'e>,
     redirect_uri: &str,
-) -> Result<(), Error> {
-    use http::StatusCode;
-    use serde::{Deserialize, Serialize};
-    use std::collections::HashMap;
-
-    let body = {
-        let mut body = HashMap::new();
-        body.insert("code", code.as_str());
-        body.insert("redirect_uri", redirect_uri);
-        body.insert("grant_type", "authorization_code");
-        body.insert("client_id", &spotify_credentials.id);
-        body.insert("client_secret", &spotify_credentials.secret);
-        body
-    };
+) -> Result<String, Error> {
+    use serde::Deserialize;
+
     let client = reqwest_client;
-    let res = match client
-        .post("https://accounts.spotify.com/api/token")
-        .form(&body)
-        .send()
+    let token = match super::request_spotify_token(&code, redirect_uri, spotify_credentials, client)
         .await
     {
-        Ok(res) => res,
+        Ok(token) => token,
         Err(e) => {
-            return Err(Error::Spotify(format!(
-                "error while acquiring temp spotify token: {:#?}",
-                e
-            )));
-        }
-    };
-
-    #[derive(Serialize, Deserialize, Debug)]
-    struct AccessToken {
-        access_token: String,
-        token_type: String,
-        scope: String,
-        expires_in: i64,
-        refresh_token: String,
-    }
-
-    let res = match &res.status() {
-        &StatusCode::OK | &StatusCode::CREATED => res.text().await,
-        _ => {
-            eprintln!("Error: {:?}", res);
             sqlx::query!("DELETE FROM hosts WHERE id = $1", host_id)
                 .execute(executor)
                 .await?;
-            return Err(Error::Database(format!(
-                "error while acquiring spotify token, spotify returned not ok response code: {:#?}",
-                res
-            )));
+            return Err(e);
         }
     };
 
-    let res = match res {
-        Ok(res) => res,
-        Err(e) => {
-            return Err(Error::Decode(format!(
-                "error while getting text from spotify response: {:#?}",
-                e
-            )));
-        }
-    };
+    #[derive(Deserialize)]
+    struct SpotifyUser {
+        id: String,
+    }
 
-    let token: AccessToken = match serde_json::from_str(res.as_str()) {
-        Ok(token) => token,
+    let spotify_user: Result<SpotifyUser, Error> = match client
+        .get(format!("{}me", spotify_credentials.api_url))
+        .bearer_auth(&token.access_token)
+        .send()
+        .await
+    {
+        Ok(res) => res
+            .text()
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|res| serde_json::from_str(&res).map_err(|e| e.to_string()))
+            .map_err(|e| {
+                Error::Decode(format!(
+                    "error while deserializing the spotify user: {:#?}",
+                    e
+                ))
+            }),
+        Err(e) => Err(Error::Spotify(format!(
+            "error while getting the spotify user: {:#?}",
+            e
+        ))),
+    };
+    // without the spotify user the host can't log in, so the host is removed like above
+    let spotify_user = match spotify_user {
+        Ok(user) => user,
         Err(e) => {
-            return Err(Error::Decode(format!(
-                "error while deserializing spotify response spotify token: {:#?}",
-                e
-            )));
+            sqlx::query!("DELETE FROM hosts WHERE id = $1", host_id)
+                .execute(executor)
+                .await?;
+            return Err(e);
         }
     };
 
@@ -124,7 +202,7 @@ pub async fn create_host<'e>(
     .execute(executor)
     .await?;
 
-    Ok(())
+    Ok(spotify_user.id)
 }
 
 pub async fn delete_jam<'e>(
@@ -132,9 +210,16 @@ pub async fn delete_jam<'e>(
     executor: impl sqlx::PgExecutor<'e>,
 ) -> Result<real_time::Changed, Error> {
     tokio::time::sleep(std::time::Duration::from_secs(1)).await;
-    let res = sqlx::query!("DELETE FROM jams WHERE id = $1", jam_id)
-        .execute(executor)
-        .await?;
+    // the jam is kept in the history of the host
+    let res = sqlx::query!(
+        "WITH deleted AS (DELETE FROM jams WHERE id = $1 RETURNING id, host_id, name, created_at)
+        INSERT INTO past_jams (id, jam_id, host_id, name, created_at)
+        SELECT $2, id, host_id, name, created_at FROM deleted",
+        jam_id,
+        cuid2::create_id()
+    )
+    .execute(executor)
+    .await?;
     if res.rows_affected() == 0 {
         return Err(Error::DoesNotExist(format!(
             "jam with id {} does not exist, could not delete jam, maybe it was already deleted",
@@ -144,59 +229,45 @@ pub async fn delete_jam<'e>(
     Ok(real_time::Changed::new().ended())
 }
 
+/// the jam gets the settings and the name of the template, its id is ignored,
+/// if `starts_at` is in the future the jam is scheduled, and nothing is played until it starts
 pub async fn create_jam<'e>(
-    name: &str,
     host_id: &str,
-    max_song_count: i16,
+    settings: &JamTemplate,
+    starts_at: Option<i64>,
     transaction: &mut sqlx::Transaction<'e, sqlx::Postgres>,
     credentials: SpotifyCredentials,
 ) -> Result<JamId, Error> {
-    println!("checking if jam exists");
-    let jam_exists = sqlx::query!(
-        "SELECT EXISTS(SELECT 1 FROM jams WHERE host_id=$1)",
-        host_id
-    )
-    .fetch_one(&mut **transaction)
-    .await?
-    .exists
-    .unwrap_or(false);
-
-    if jam_exists {
-        println!("host already in jam");
-        match sqlx::query!("SELECT id FROM jams WHERE host_id=$1", host_id)
-            .fetch_one(&mut **transaction)
-            .await
-        {
-            Ok(jam) => {
-                return Err(Error::HostAlreadyInJam { jam_id: jam.id });
-            }
-            Err(sqlx::Error::RowNotFound) => {
-                println!("idk what happened");
-                return Err(Error::DoesNotExist(format!(
-                    "jam with host id {} does not exist, oooooo i don't know what happened",
-                    host_id
-                )));
-            }
-            Err(e) => {
-                println!("error getting already existing jam id: {:#?}", e);
-                return Err(e.into());
-            }
-        };
-    }
-
+    let name = settings.name.as_str();
     let jam_id = cuid2::CuidConstructor::new()
         .with_length(6)
         .create_id()
         .to_lowercase();
 
+    let starts_at = starts_at.filter(|starts_at| *starts_at > chrono::Utc::now().timestamp());
+    let state = if starts_at.is_some() {
+        JamState::Scheduled
+    } else {
+        JamState::Playing
+    };
+
     println!("trying to insert jam");
 
     sqlx::query!(
-        "INSERT INTO jams (id, max_song_count, host_id, name) VALUES ($1, $2, $3, $4)",
+        "INSERT INTO jams (id, max_song_count, host_id, name, queue_strategy, allow_explicit, song_limit, no_repeat_minutes, fallback_shuffle, external_changes, state, starts_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)",
         &jam_id,
-        &max_song_count,
+        settings.max_song_count as i16,
         host_id,
-        name
+        name,
+        settings.queue_strategy.as_db_str(),
+        settings.allow_explicit,
+        settings.song_limit.as_db_str(),
+        settings.no_repeat_minutes.min(i32::MAX as u32) as i32,
+        settings.fallback_shuffle,
+        settings.external_changes.as_db_str(),
+        state.as_db_str(),
+        starts_at
     )
     .execute(&mut **transaction)
     .await?;
@@ -213,6 +284,11 @@ pub async fn create_jam<'e>(
     .execute(&mut **transaction)
     .await?;
 
+    if state.has_not_started() {
+        println!("scheduled jam with id: {}", jam_id);
+        return Ok(jam_id);
+    }
+
     println!("getting next song");
 
     let song = get_next_song(&jam_id, &mut *transaction, credentials).await?;
@@ -225,10 +301,72 @@ pub async fn create_jam<'e>(
     Ok(jam_id)
 }
 
-pub async fn set_current_song_position(
+/// how far the player of the host can be off, before the clock of the server is corrected, in milliseconds
+const MAX_DRIFT: i64 = 2000;
+
+/// where the current song is at, all the timestamps are unix timestamps in milliseconds
+struct PlaybackClock {
+    started_at: i64,
+    paused_at: Option<i64>,
+    duration: i64,
+    spotify_id: String,
+}
+
+impl PlaybackClock {
+    fn elapsed(&self, now: i64) -> i64 {
+        self.paused_at.unwrap_or(now) - self.started_at
+    }
+
+    /// when the song has to have started for the reported position to be right,
+    /// none if the clock is close enough to the player already
+    fn corrected_start(&self, percentage: f32, now: i64) -> Option<i64> {
+        let reported = (percentage as f64 * self.duration as f64) as i64;
+        ((reported - self.elapsed(now)).abs() > MAX_DRIFT).then_some(now - reported)
+    }
+}
+
+/// the clock is none if no song was played yet
+async fn get_playback_clock<'e>(
+    jam_id: &str,
+    executor: impl sqlx::PgExecutor<'e>,
+) -> Result<(JamState, Option<PlaybackClock>), Error> {
+    let jam = match sqlx::query!(
+        r#"SELECT j.state, j.song_started_at, j.paused_at, s.duration AS "duration?", s.spotify_id AS "spotify_id?"
+        FROM jams j LEFT JOIN songs s ON s.user_id = j.id
+        WHERE j.id = $1"#,
+        jam_id
+    )
+    .fetch_optional(executor)
+    .await?
+    {
+        Some(jam) => jam,
+        None => {
+            return Err(Error::DoesNotExist(format!(
+                "jam with id {} does not exist, could not get the playback clock",
+                jam_id
+            )));
+        }
+    };
+
+    let clock = match (jam.song_started_at, jam.duration, jam.spotify_id) {
+        (Some(started_at), Some(duration), Some(spotify_id)) => Some(PlaybackClock {
+            started_at,
+            paused_at: jam.paused_at,
+            duration: duration as i64,
+            spotify_id,
+        }),
+        _ => None,
+    };
+
+    Ok((JamState::from_db_str(&jam.state), clock))
+}
+
+/// the host reports where its player is at, the clock of the server is only corrected if it drifted off,
+/// reports about other songs are ignored, because the player can lag behind when the song changes
+pub async fn report_song_position(
     jam_id: &str,
     percentage: f32,
-    credentials: SpotifyCredentials,
+    spotify_id: &str,
     transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
 ) -> Result<real_time::Changed, Error> {
     if !(0.0..=1.0).contains(&percentage) {
@@ -237,49 +375,153 @@ pub async fn set_current_song_position(
         ));
     }
 
-    if percentage > 0.99 {
-        let changed = go_to_next_song(jam_id, transaction, credentials).await?;
-        println!("new song");
-        return Ok(changed.position());
+    let clock = match get_playback_clock(jam_id, &mut **transaction).await? {
+        (JamState::Playing, Some(clock)) if clock.spotify_id == spotify_id => clock,
+        _ => return Ok(real_time::Changed::new()),
+    };
+
+    let now = chrono::Utc::now().timestamp_millis();
+    let Some(started_at) = clock.corrected_start(percentage, now) else {
+        return Ok(real_time::Changed::new());
+    };
+
+    sqlx::query!(
+        "UPDATE jams SET song_started_at = $1 WHERE id = $2",
+        started_at,
+        jam_id
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(real_time::Changed::new().position())
+}
+
+/// moves the current song to the position, in the player of the host and on the clock of the server,
+/// so the progress bars of the users jump there too
+pub async fn seek_song(
+    jam_id: &str,
+    position_ms: u32,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    credentials: SpotifyCredentials,
+) -> Result<Changed, Error> {
+    sqlx::query!("SELECT id FROM jams WHERE id = $1 FOR UPDATE", jam_id)
+        .fetch_optional(&mut **transaction)
+        .await?;
+
+    let clock = match get_playback_clock(jam_id, &mut **transaction).await? {
+        (JamState::Playing | JamState::Paused, Some(clock)) => clock,
+        _ => {
+            return Err(Error::InvalidRequest(
+                "nothing is playing yet, so there is nothing to seek in".to_string(),
+            ));
+        }
+    };
+    // the last second is left out, otherwise the clock would go to the next song before the player does
+    let position = (position_ms as i64).min((clock.duration - 1000).max(0));
+
+    super::seek_playback(position as u32, jam_id, transaction, credentials).await?;
+
+    let now = chrono::Utc::now().timestamp_millis();
+    sqlx::query!(
+        "UPDATE jams SET song_started_at = $1 WHERE id = $2",
+        clock.paused_at.unwrap_or(now) - position,
+        jam_id
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(Changed::new().position())
+}
+
+/// sets the volume of the player of the host, it is kept, so the player starts with it again after a reload
+pub async fn set_volume(
+    jam_id: &str,
+    percent: u8,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    credentials: SpotifyCredentials,
+) -> Result<Changed, Error> {
+    if percent > 100 {
+        return Err(Error::InvalidRequest(
+            "the volume has to be between 0 and 100".to_string(),
+        ));
     }
 
-    let res = sqlx::query!(
-        "UPDATE jams SET song_position = $1 WHERE id = $2",
-        percentage,
+    super::set_playback_volume(percent, jam_id, transaction, credentials).await?;
+
+    sqlx::query!(
+        "UPDATE jams SET volume_percent = $1 WHERE id = $2",
+        percent as i16,
         jam_id
     )
     .execute(&mut **transaction)
     .await?;
 
-    if res.rows_affected() == 0 {
-        return Err(Error::DoesNotExist(format!(
-            "jam with id {} does not exist, could not set song position",
+    Ok(Changed::new().volume())
+}
+
+/// the volume the host set, from 0 to 100
+pub async fn get_volume<'e>(
+    jam_id: &str,
+    executor: impl sqlx::PgExecutor<'e>,
+) -> Result<u8, Error> {
+    match sqlx::query!("SELECT volume_percent FROM jams WHERE id = $1", jam_id)
+        .fetch_optional(executor)
+        .await?
+    {
+        Some(jam) => Ok(jam.volume_percent as u8),
+        None => Err(Error::DoesNotExist(format!(
+            "jam with id {} does not exist, could not get its volume",
             jam_id
-        )));
+        ))),
     }
-
-    Ok(real_time::Changed::new().position())
 }
 
 pub async fn get_current_song_position<'e>(
     jam_id: &str,
     executor: impl sqlx::PgExecutor<'e>,
 ) -> Result<f32, Error> {
-    let row = match sqlx::query!("SELECT song_position FROM jams WHERE id = $1", jam_id)
-        .fetch_one(executor)
-        .await
-    {
-        Ok(row) => row,
-        Err(sqlx::Error::RowNotFound) => {
-            return Err(Error::DoesNotExist(format!(
-                "jam with id {} does not exist, could not get song position",
-                jam_id
-            )));
-        }
-        Err(e) => return Err(e.into()),
+    let clock = match get_playback_clock(jam_id, executor).await? {
+        (_, Some(clock)) if clock.duration > 0 => clock,
+        _ => return Ok(0.0),
     };
+    let elapsed = clock.elapsed(chrono::Utc::now().timestamp_millis());
+    Ok((elapsed as f32 / clock.duration as f32).clamp(0.0, 1.0))
+}
 
-    Ok(row.song_position)
+/// none if the jam is not playing, so there is nothing to wait for
+pub async fn get_time_until_song_end<'e>(
+    jam_id: &str,
+    executor: impl sqlx::PgExecutor<'e>,
+) -> Result<Option<std::time::Duration>, Error> {
+    let clock = match get_playback_clock(jam_id, executor).await? {
+        (JamState::Playing, Some(clock)) => clock,
+        _ => return Ok(None),
+    };
+    let remaining = clock.duration - clock.elapsed(chrono::Utc::now().timestamp_millis());
+    Ok(Some(std::time::Duration::from_millis(
+        remaining.max(0) as u64
+    )))
+}
+
+/// goes to the next song if the current one finished, the time is checked under the lock of the jam,
+/// so a song that was skipped in the meantime isn't taken for finished
+pub async fn go_to_next_song_if_ended(
+    jam_id: &str,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    credentials: SpotifyCredentials,
+) -> Result<Changed, Error> {
+    sqlx::query!("SELECT id FROM jams WHERE id = $1 FOR UPDATE", jam_id)
+        .fetch_optional(&mut **transaction)
+        .await?;
+
+    match get_time_until_song_end(jam_id, &mut **transaction).await? {
+        Some(remaining) if remaining.is_zero() => {
+            Ok(go_to_next_song(jam_id, None, transaction, credentials)
+                .await?
+                .position())
+        }
+        _ => Ok(Changed::new()),
+    }
 }
 
 pub async fn get_current_song<'e>(
@@ -295,11 +537,19 @@ pub async fn get_current_song<'e>(
         pub duration: i32,
         pub artists: Option<Vec<String>>,
         pub image_url: String,
+        pub preview_url: Option<String>,
+        pub release_year: Option<i32>,
+        pub explicit: bool,
+        pub popularity: Option<i32>,
     }
 
-    let song = match sqlx::query_as!(SongDb, "SELECT * FROM songs WHERE user_id=$1", jam_id)
-        .fetch_optional(executor)
-        .await
+    let song = match sqlx::query_as!(
+        SongDb,
+        "SELECT id, spotify_id, user_id, name, album, duration, artists, image_url, preview_url, release_year, explicit, popularity FROM songs WHERE user_id=$1",
+        jam_id
+    )
+    .fetch_optional(executor)
+    .await
     {
         Ok(song) => song,
         Err(sqlx::Error::RowNotFound) => {
@@ -331,10 +581,17 @@ pub async fn get_current_song<'e>(
         album: song.album,
         duration: song.duration as u32,
         image_url: song.image_url,
+        details: SongDetails {
+            preview_url: song.preview_url,
+            release_year: song.release_year.map(|year| year as u16),
+            explicit: song.explicit,
+            popularity: song.popularity.map(|popularity| popularity as u8),
+        },
     }))
 }
 
-/// doesn't need to have the song id as some, it will generate a new one, either way
+/// doesn't need to have the song id as some, it will generate a new one, either way,
+/// if the song id is some, the song is removed from the queue of the jam
 pub async fn set_current_song<'e>(
     song: &Song,
     jam_id: &str,
@@ -343,41 +600,61 @@ pub async fn set_current_song<'e>(
     println!("set song: {:#?}", song);
     let song_id = cuid2::create_id(); // Generate a new song ID
 
-    // Try to update the song first
-    let res = sqlx::query!(
-        "UPDATE songs 
-        SET name = $1, album = $2, duration = $3, artists = $4, image_url = $5, spotify_id = $6 
-        WHERE user_id = $7",
+    // the row of the current song is replaced instead of updated, so every song gets a new id
+    // and a skip can tell if the song it wanted to skip is still playing
+    sqlx::query!("DELETE FROM songs WHERE user_id = $1", jam_id)
+        .execute(&mut **transaction)
+        .await?;
+
+    sqlx::query!(
+        "INSERT INTO songs (id, name, album, duration, artists, image_url, spotify_id, user_id, preview_url, release_year, explicit, popularity, song_key) 
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)",
+        song_id,
         song.name,
         song.album,
         song.duration as i32,
         &song.artists,
         song.image_url,
         song.spotify_id,
+        jam_id,
+        song.details.preview_url,
+        song.details.release_year.map(|year| year as i32),
+        song.details.explicit,
+        song.details.popularity.map(|popularity| popularity as i32),
+        super::song_key(&song.name, &song.artists)
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    sqlx::query!(
+        "UPDATE jams SET song_started_at = $1, paused_at = NULL, queued_spotify_id = NULL, queued_song_id = NULL WHERE id = $2",
+        chrono::Utc::now().timestamp_millis(),
         jam_id
     )
     .execute(&mut **transaction)
     .await?;
 
-    // If no rows were affected, insert the new song
-    if res.rows_affected() == 0 {
-        sqlx::query!(
-            "INSERT INTO songs (id, name, album, duration, artists, image_url, spotify_id, user_id) 
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
-            song_id,
-            song.name,
-            song.album,
-            song.duration as i32,
-            &song.artists,
-            song.image_url,
-            song.spotify_id,
+    super::record_played_song(song, jam_id, &mut **transaction).await?;
+
+    // the song leaves the queue of the jam once it is played,
+    // the fallback list of the host shows which tracks were played
+    let mut changed = real_time::Changed::new().current_song().fallback();
+    if let Some(played_song_id) = &song.id {
+        changed = changed
+            .merge_with_other(super::reward_voters(played_song_id, jam_id, transaction).await?);
+        let res = sqlx::query!(
+            "DELETE FROM songs WHERE id = $1 AND user_id <> $2",
+            played_song_id,
             jam_id
         )
         .execute(&mut **transaction)
         .await?;
+        if res.rows_affected() > 0 {
+            changed = changed.songs();
+        }
     }
 
-    Ok(real_time::Changed::new().current_song()) // Return success
+    Ok(changed) // Return success
 }
 
 pub async fn dose_jam_exist<'e>(
@@ -394,6 +671,7 @@ pub async fn dose_jam_exist<'e>(
     .map_err(|e| e.into())
 }
 
+/// the song with the best spot in the queue, or a fallback song if the queue is empty
 pub async fn get_next_song<'e>(
     jam_id: &str,
     transaction: &mut sqlx::Transaction<'e, sqlx::Postgres>,
@@ -405,39 +683,480 @@ pub async fn get_next_song<'e>(
         return Ok(s);
     }
 
-    if let Ok(Some(s)) = get_next_song_from_player(jam_id, transaction, credentials.clone()).await {
-        return Ok(s);
+    get_fallback_song(jam_id, transaction, credentials).await
+}
+
+/// the jam is locked until the transaction ends, so a skip and the clock can't both move the jam on,
+/// skipped song id is the id of the current song the caller wants to skip, if another song plays by the time
+/// the lock is taken, someone else already moved on and nothing happens, none skips whatever plays
+pub async fn go_to_next_song(
+    jam_id: &str,
+    skipped_song_id: Option<&str>,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    credentials: SpotifyCredentials,
+) -> Result<Changed, Error> {
+    use super::*;
+
+    sqlx::query!("SELECT id FROM jams WHERE id = $1 FOR UPDATE", jam_id)
+        .fetch_optional(&mut **transaction)
+        .await?;
+
+    if let Some(skipped_song_id) = skipped_song_id {
+        let current_song_id = get_current_song(jam_id, &mut **transaction)
+            .await?
+            .and_then(|song| song.id);
+        if current_song_id.as_deref() != Some(skipped_song_id) {
+            println!(
+                "the song {} of jam {} was already skipped",
+                skipped_song_id, jam_id
+            );
+            return Ok(Changed::new());
+        }
     }
 
-    match get_song_recommendation(credentials.clone(), jam_id, transaction).await {
-        Ok(s) => return Ok(s),
-        Err(e) => {
-            eprintln!("error getting song recommendation: {}", e);
+    if get_jam_state(jam_id, &mut **transaction)
+        .await?
+        .has_not_started()
+    {
+        return Err(Error::InvalidRequest(
+            "the jam has not started yet, so there is no song to skip".to_string(),
+        ));
+    }
+
+    let queued = sqlx::query!(
+        "SELECT queued_spotify_id, queued_song_id FROM jams WHERE id = $1",
+        jam_id
+    )
+    .fetch_one(&mut **transaction)
+    .await?;
+
+    let mut changed = match queued.queued_spotify_id {
+        // the song is already in the queue of the player, so the player plays it without a gap
+        Some(queued_spotify_id) => {
+            let mut song =
+                get_song(&queued_spotify_id, jam_id, transaction, credentials.clone()).await?;
+            song.id = queued.queued_song_id;
+
+            let player_is_on_queued_song =
+                get_current_song_from_player(jam_id, transaction, credentials.clone())
+                    .await
+                    .ok()
+                    .flatten()
+                    .map(|player_song| player_song.spotify_id == queued_spotify_id)
+                    .unwrap_or(false);
+            if !player_is_on_queued_song {
+                play_later_if_unavailable(
+                    skip_to_next_in_player_queue(jam_id, transaction, credentials).await,
+                    jam_id,
+                )?;
+            }
+
+            set_current_song(&song, jam_id, transaction).await?
+        }
+        None => {
+            let top_song = get_next_song(jam_id, transaction, credentials.clone()).await?;
+            let changed = set_current_song(&top_song, jam_id, transaction).await?;
+            play_later_if_unavailable(
+                play_song(&top_song.spotify_id, jam_id, transaction, credentials).await,
+                jam_id,
+            )?;
+            changed
         }
+    };
+
+    // the next song starts playing right away, so a paused jam is playing again
+    let res = sqlx::query!(
+        "UPDATE jams SET state = $1 WHERE id = $2 AND state = $3",
+        JamState::Playing.as_db_str(),
+        jam_id,
+        JamState::Paused.as_db_str()
+    )
+    .execute(&mut **transaction)
+    .await?;
+    if res.rows_affected() > 0 {
+        changed = changed.settings();
     }
 
-    let never_gonna = search("Never gonna give you up", transaction, jam_id, credentials)
+    let changed = reset_votes(jam_id, &mut **transaction)
         .await?
-        .remove(0);
-    Ok(never_gonna)
+        .merge_with_other(changed);
+
+    Ok(changed)
 }
 
-pub async fn go_to_next_song(
+/// how long before the end of the current song the next one is picked and sent to the player
+const QUEUE_AHEAD: std::time::Duration = std::time::Duration::from_secs(15);
+
+/// picks the next song shortly before the current one ends and adds it to the queue of the player,
+/// from then on the votes of the song are locked, because it is going to play for sure
+pub async fn queue_next_song_if_due(
     jam_id: &str,
     transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
     credentials: SpotifyCredentials,
 ) -> Result<Changed, Error> {
     use super::*;
 
-    let top_song = get_next_song(jam_id, transaction, credentials.clone()).await?;
+    let is_queued = match sqlx::query!(
+        "SELECT queued_spotify_id FROM jams WHERE id = $1 FOR UPDATE",
+        jam_id
+    )
+    .fetch_optional(&mut **transaction)
+    .await?
+    {
+        Some(jam) => jam.queued_spotify_id.is_some(),
+        None => {
+            return Err(Error::DoesNotExist(format!(
+                "jam with id {} does not exist, could not queue the next song",
+                jam_id
+            )));
+        }
+    };
+    if is_queued {
+        return Ok(Changed::new());
+    }
+
+    match get_time_until_song_end(jam_id, &mut **transaction).await? {
+        Some(remaining) if remaining <= QUEUE_AHEAD => (),
+        _ => return Ok(Changed::new()),
+    }
 
-    let changed = set_current_song(&top_song, jam_id, transaction).await?;
+    let song = get_next_song(jam_id, transaction, credentials.clone()).await?;
+    add_song_to_player_queue(&song.spotify_id, jam_id, transaction, credentials).await?;
 
-    let changed = reset_votes(jam_id, &mut **transaction)
+    sqlx::query!(
+        "UPDATE jams SET queued_spotify_id = $1, queued_song_id = $2 WHERE id = $3",
+        song.spotify_id,
+        song.id,
+        jam_id
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(Changed::new())
+}
+
+/// the spotify id of the song that was sent to the queue of the player, if there is one
+pub async fn get_queued_spotify_id<'e>(
+    jam_id: &str,
+    executor: impl sqlx::PgExecutor<'e>,
+) -> Result<Option<String>, Error> {
+    let queued = sqlx::query!("SELECT queued_spotify_id FROM jams WHERE id = $1", jam_id)
+        .fetch_optional(executor)
+        .await?;
+    Ok(queued.and_then(|jam| jam.queued_spotify_id))
+}
+
+/// the song that is going to play next can't be voted on or removed anymore
+pub async fn is_song_queued<'e>(
+    song_id: &str,
+    executor: impl sqlx::PgExecutor<'e>,
+) -> Result<bool, Error> {
+    let is_queued = sqlx::query!(
+        "SELECT EXISTS(SELECT 1 FROM jams WHERE queued_song_id = $1)",
+        song_id
+    )
+    .fetch_one(executor)
+    .await?
+    .exists
+    .unwrap_or(false);
+    Ok(is_queued)
+}
+
+pub async fn get_jam_state<'e>(
+    jam_id: &str,
+    executor: impl sqlx::PgExecutor<'e>,
+) -> Result<JamState, Error> {
+    match sqlx::query!("SELECT state FROM jams WHERE id = $1", jam_id)
+        .fetch_optional(executor)
         .await?
-        .merge_with_other(changed);
+    {
+        Some(jam) => Ok(JamState::from_db_str(&jam.state)),
+        None => Err(Error::DoesNotExist(format!(
+            "jam with id {} does not exist, could not get its state",
+            jam_id
+        ))),
+    }
+}
+
+/// moves the jam to the next state of its lifecycle, when the jam starts playing the first song is picked,
+/// if the player of the host is not ready yet, the song is played once it connects
+pub async fn set_jam_state(
+    jam_id: &str,
+    state: JamState,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    credentials: SpotifyCredentials,
+) -> Result<Changed, Error> {
+    use super::*;
 
-    play_song(&top_song.spotify_id, jam_id, transaction, credentials).await?;
+    let current_state = get_jam_state(jam_id, &mut **transaction).await?;
+    if !current_state.can_transition_to(state) {
+        return Err(Error::InvalidRequest(format!(
+            "a jam can't go from {:?} to {:?}",
+            current_state, state
+        )));
+    }
+
+    // spotify goes first, if it refuses the state in the database stays like what is playing
+    match (current_state, state) {
+        (JamState::Playing, JamState::Paused) => {
+            pause_playback(jam_id, transaction, credentials.clone()).await?;
+            sqlx::query!(
+                "UPDATE jams SET paused_at = $1 WHERE id = $2",
+                chrono::Utc::now().timestamp_millis(),
+                jam_id
+            )
+            .execute(&mut **transaction)
+            .await?;
+        }
+        (JamState::Paused, JamState::Playing) => {
+            resume_playback(jam_id, transaction, credentials.clone()).await?;
+            // the pause doesn't count towards the progress of the song
+            sqlx::query!(
+                "UPDATE jams SET song_started_at = song_started_at + ($1 - paused_at), paused_at = NULL WHERE id = $2",
+                chrono::Utc::now().timestamp_millis(),
+                jam_id
+            )
+            .execute(&mut **transaction)
+            .await?;
+        }
+        _ => (),
+    }
+
+    sqlx::query!(
+        "UPDATE jams SET state = $1 WHERE id = $2",
+        state.as_db_str(),
+        jam_id
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    let mut changed = Changed::new().settings();
+
+    if current_state.has_not_started() && state == JamState::Playing {
+        let song = get_next_song(jam_id, transaction, credentials.clone()).await?;
+        changed = set_current_song(&song, jam_id, transaction)
+            .await?
+            .merge_with_other(reset_votes(jam_id, &mut **transaction).await?)
+            .merge_with_other(changed)
+            .songs()
+            .position();
+
+        if let Err(e) = play_song(&song.spotify_id, jam_id, transaction, credentials).await {
+            eprintln!("could not start playing the jam {}, yet: {}", jam_id, e);
+        }
+    }
 
     Ok(changed)
 }
+
+/// starts a scheduled jam once its start time passed
+pub async fn start_jam_if_due(
+    jam_id: &str,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    credentials: SpotifyCredentials,
+) -> Result<Changed, Error> {
+    let is_due = sqlx::query!(
+        "SELECT EXISTS(SELECT 1 FROM jams WHERE id = $1 AND state IN ('scheduled', 'open') AND starts_at <= $2)",
+        jam_id,
+        chrono::Utc::now().timestamp()
+    )
+    .fetch_one(&mut **transaction)
+    .await?
+    .exists
+    .unwrap_or(false);
+
+    if !is_due {
+        return Ok(Changed::new());
+    }
+
+    set_jam_state(jam_id, JamState::Playing, transaction, credentials).await
+}
+
+/// right after the jam changed songs the player can still report the old one, so it isn't taken as a change yet
+const PLAYER_SETTLE_TIME: i64 = 10_000;
+
+/// compares the player of the host with the jam, when the host skipped, paused or picked a song in the spotify app,
+/// the jam either adopts it or takes the player back, depending on the external changes setting of the jam
+pub async fn sync_with_player(
+    jam_id: &str,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    credentials: SpotifyCredentials,
+) -> Result<Changed, Error> {
+    use super::*;
+
+    let jam = match sqlx::query!(
+        "SELECT state, external_changes, song_started_at, paused_at FROM jams WHERE id = $1 FOR UPDATE",
+        jam_id
+    )
+    .fetch_optional(&mut **transaction)
+    .await?
+    {
+        Some(jam) => jam,
+        None => {
+            return Err(Error::DoesNotExist(format!(
+                "jam with id {} does not exist, could not sync it with the player",
+                jam_id
+            )));
+        }
+    };
+    let state = JamState::from_db_str(&jam.state);
+    if state.has_not_started() {
+        return Ok(Changed::new());
+    }
+    let current_song = match get_current_song(jam_id, &mut **transaction).await? {
+        Some(song) => song,
+        None => return Ok(Changed::new()),
+    };
+
+    let player = match get_player_state(jam_id, transaction, credentials.clone()).await? {
+        Some(player) => player,
+        // the player of the host is not connected, or was closed, it gets the song back once it is there
+        None => {
+            if state == JamState::Playing {
+                play_song(&current_song.spotify_id, jam_id, transaction, credentials).await?;
+            }
+            return Ok(Changed::new());
+        }
+    };
+
+    let now = chrono::Utc::now().timestamp_millis();
+    let last_change = jam.song_started_at.max(jam.paused_at).unwrap_or(0);
+    if now - last_change < PLAYER_SETTLE_TIME {
+        return Ok(Changed::new());
+    }
+
+    let queued_spotify_id = get_queued_spotify_id(jam_id, &mut **transaction).await?;
+    let is_on_current = player.song.spotify_id == current_song.spotify_id;
+    let is_on_queued = queued_spotify_id.as_ref() == Some(&player.song.spotify_id);
+
+    if ExternalChanges::from_db_str(&jam.external_changes) == ExternalChanges::Override {
+        // the player already moved on to the queued song, the clock of the jam catches up with it
+        if state == JamState::Playing && !is_on_current && !is_on_queued {
+            println!(
+                "playing song: {:?}, set away from: {}",
+                current_song.name, player.song.name
+            );
+            play_song(&current_song.spotify_id, jam_id, transaction, credentials).await?;
+        }
+        return Ok(Changed::new());
+    }
+
+    let changed = if is_on_queued {
+        println!("the player of jam {} skipped to the queued song", jam_id);
+        go_to_next_song(jam_id, None, transaction, credentials).await?
+    } else if !is_on_current {
+        println!(
+            "the host of jam {} picked {:?} in the spotify app",
+            jam_id, player.song.name
+        );
+        adopt_player_song(&player, jam_id, transaction).await?
+    } else if (state == JamState::Playing) == player.is_playing {
+        return Ok(Changed::new());
+    } else {
+        Changed::new()
+    };
+
+    Ok(follow_player(&player, jam_id, transaction)
+        .await?
+        .merge_with_other(changed))
+}
+
+/// a song picked in the spotify app becomes the current song, if it is in the queue of the jam it counts as played,
+/// the song that was already sent to the queue of the player stays there, so it plays after it
+async fn adopt_player_song(
+    player: &PlayerState,
+    jam_id: &str,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+) -> Result<Changed, Error> {
+    let queued = sqlx::query!(
+        "SELECT queued_spotify_id, queued_song_id FROM jams WHERE id = $1",
+        jam_id
+    )
+    .fetch_one(&mut **transaction)
+    .await?;
+
+    let mut song = player.song.clone();
+    song.id = sqlx::query!(
+        "SELECT songs.id FROM songs JOIN users ON songs.user_id = users.id WHERE users.jam_id = $1 AND songs.spotify_id = $2 LIMIT 1",
+        jam_id,
+        player.song.spotify_id
+    )
+    .fetch_optional(&mut **transaction)
+    .await?
+    .map(|song| song.id);
+
+    let changed = set_current_song(&song, jam_id, transaction).await?;
+
+    sqlx::query!(
+        "UPDATE jams SET queued_spotify_id = $1, queued_song_id = $2 WHERE id = $3",
+        queued.queued_spotify_id,
+        queued.queued_song_id,
+        jam_id
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(super::reset_votes(jam_id, &mut **transaction)
+        .await?
+        .merge_with_other(changed))
+}
+
+/// sets the clock and the state of the jam to where the player is, so a pause in the spotify app pauses the jam
+async fn follow_player(
+    player: &PlayerState,
+    jam_id: &str,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+) -> Result<Changed, Error> {
+    let now = chrono::Utc::now().timestamp_millis();
+    let state = if player.is_playing {
+        JamState::Playing
+    } else {
+        JamState::Paused
+    };
+    sqlx::query!(
+        "UPDATE jams SET state = $1, song_started_at = $2, paused_at = $3 WHERE id = $4",
+        state.as_db_str(),
+        now - player.progress as i64,
+        (!player.is_playing).then_some(now),
+        jam_id
+    )
+    .execute(&mut **transaction)
+    .await?;
+    Ok(Changed::new().settings().position())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clock(started_at: i64, paused_at: Option<i64>) -> PlaybackClock {
+        PlaybackClock {
+            started_at,
+            paused_at,
+            duration: 200_000,
+            spotify_id: "mocktrack0000000000000".to_string(),
+        }
+    }
+
+    #[test]
+    fn elapsed_stops_while_paused() {
+        assert_eq!(clock(10_000, None).elapsed(70_000), 60_000);
+        assert_eq!(clock(10_000, Some(40_000)).elapsed(70_000), 30_000);
+    }
+
+    #[test]
+    fn small_drift_is_ignored() {
+        // 50% of the song is 100 seconds in, the clock says 101.5 seconds
+        let clock = clock(0, None);
+        assert_eq!(clock.corrected_start(0.5, 101_500), None);
+        assert_eq!(clock.corrected_start(0.5, 98_000), None);
+    }
+
+    #[test]
+    fn large_drift_moves_the_start() {
+        let clock = clock(0, None);
+        assert_eq!(clock.corrected_start(0.5, 102_001), Some(2_001));
+        assert_eq!(clock.corrected_start(0.5, 90_000), Some(-10_000));
+        assert_eq!(clock.corrected_start(0.0, 5_000), Some(5_000));
+    }
+}
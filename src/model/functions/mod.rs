@@ -1,6 +1,9 @@
 pub mod spotify;
 pub use spotify::*;
 
+mod spotify_retry;
+pub use spotify_retry::*;
+
 mod song;
 pub use song::*;
 
@@ -15,3 +18,27 @@ pub use real_time::*;
 
 mod jam;
 pub use jam::*;
+
+mod handover;
+pub use handover::*;
+
+mod host;
+pub use host::*;
+
+mod clock;
+pub use clock::*;
+
+mod fallback;
+pub use fallback::*;
+
+mod credits;
+pub use credits::*;
+
+mod user_spotify;
+pub use user_spotify::*;
+
+mod track_cache;
+pub use track_cache::*;
+
+mod search_cache;
+pub use search_cache::*;
@@ -0,0 +1,95 @@
+use crate::model::types::*;
+use real_time::{SearchCollection, SearchQuery};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// how many pages of results are kept, the least recently used one is dropped first
+const SEARCH_CACHE_CAPACITY: usize = 500;
+/// spotify results don't change often, but new releases should show up eventually
+const SEARCH_CACHE_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// A page of results, before the songs that are already in the jam are left out
+#[derive(Clone, Debug)]
+pub struct CachedSearch {
+    pub songs: Vec<Song>,
+    pub collections: Vec<SearchCollection>,
+    pub next_offset: Option<u32>,
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    search: CachedSearch,
+    cached_at: Instant,
+    last_used: u64,
+}
+
+#[derive(Debug, Default)]
+struct Lru {
+    entries: HashMap<(SearchQuery, u32), CacheEntry>,
+    uses: u64,
+}
+
+/// The recent searches of all jams, so the same query doesn't ask spotify again,
+/// only searches made with the token of the host are cached, the library of a guest is their own
+#[derive(Clone, Debug, Default)]
+pub struct SearchCache(Arc<Mutex<Lru>>);
+
+impl SearchCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, query: &SearchQuery, offset: u32) -> Option<CachedSearch> {
+        let mut lru = match self.0.lock() {
+            Ok(lru) => lru,
+            Err(e) => {
+                eprintln!("the search cache is poisoned, could not read it: {}", e);
+                return None;
+            }
+        };
+        lru.uses += 1;
+        let uses = lru.uses;
+        let key = (query.clone(), offset);
+        match lru.entries.get_mut(&key) {
+            Some(entry) if entry.cached_at.elapsed() < SEARCH_CACHE_TTL => {
+                entry.last_used = uses;
+                Some(entry.search.clone())
+            }
+            Some(_) => {
+                lru.entries.remove(&key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    pub fn insert(&self, query: SearchQuery, offset: u32, search: CachedSearch) {
+        let mut lru = match self.0.lock() {
+            Ok(lru) => lru,
+            Err(e) => {
+                eprintln!("the search cache is poisoned, could not write to it: {}", e);
+                return;
+            }
+        };
+        lru.uses += 1;
+        let uses = lru.uses;
+        if lru.entries.len() >= SEARCH_CACHE_CAPACITY
+            && let Some(oldest) = lru
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone())
+        {
+            lru.entries.remove(&oldest);
+        }
+        lru.entries.insert(
+            (query, offset),
+            CacheEntry {
+                search,
+                cached_at: Instant::now(),
+                last_used: uses,
+            },
+        );
+    }
+}
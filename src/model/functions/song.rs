@@ -1,20 +1,23 @@
-use crate::model::functions::get_access_token;
+use crate::model::functions::is_song_queued;
 use crate::model::types::*;
 use itertools::Itertools;
-use rand::seq::SliceRandom;
 use rand::SeedableRng;
-use rspotify::model::TrackId;
+use rand::seq::{IndexedRandom, SliceRandom};
 use std::collections::HashMap;
 
+/// co-hosts with the permission can remove any song, so `can_remove_any_song` skips the ownership check
 pub async fn remove_song<'e>(
     song_id: &str,
     id: &Id,
+    can_remove_any_song: bool,
     transaction: &mut sqlx::Transaction<'e, sqlx::Postgres>,
 ) -> Result<real_time::Changed, Error> {
     // Check if the ID is a user and if the song belongs to the user
-    if let IdType::User(user_id) = &id.id {
+    if let IdType::User(user_id) = &id.id
+        && !can_remove_any_song
+    {
         let song_user = sqlx::query!(
-            "SELECT * FROM songs WHERE id=$1 AND user_id=$2",
+            "SELECT id FROM songs WHERE id=$1 AND user_id=$2",
             song_id,
             user_id
         )
@@ -29,10 +32,20 @@ pub async fn remove_song<'e>(
         }
     }
 
-    // Proceed to delete the song
-    let res = sqlx::query!("DELETE FROM songs WHERE id=$1;", song_id)
-        .execute(&mut **transaction)
-        .await?;
+    if is_song_queued(song_id, &mut **transaction).await? {
+        return Err(Error::Forbidden(
+            "this song plays next, it can't be removed anymore".to_string(),
+        ));
+    }
+
+    // Proceed to delete the song, the current song of the jam can't be removed
+    let res = sqlx::query!(
+        "DELETE FROM songs WHERE id=$1 AND user_id IN (SELECT id FROM users WHERE jam_id=$2) AND user_id<>$2;",
+        song_id,
+        id.jam_id()
+    )
+    .execute(&mut **transaction)
+    .await?;
     if res.rows_affected() < 1 {
         return Err(Error::DoesNotExist(format!(
             "could not delete song, no song found with id: {}",
@@ -60,9 +73,35 @@ pub async fn get_top_song<'e>(
         return Ok(None);
     }
 
-    let mut songs = songs.into_iter().max_set_by_key(|s| s.votes.votes);
-    songs.shuffle(&mut rng);
-    Ok(songs.into_iter().next())
+    let queue_strategy = sqlx::query!("SELECT queue_strategy FROM jams WHERE id=$1", id.jam_id())
+        .fetch_one(&mut **transaction)
+        .await?
+        .queue_strategy;
+
+    match QueueStrategy::from_db_str(&queue_strategy) {
+        QueueStrategy::Votes => {
+            let mut songs = songs.into_iter().max_set_by_key(|s| s.votes.votes);
+            songs.shuffle(&mut rng);
+            Ok(songs.into_iter().next())
+        }
+        QueueStrategy::FirstCome => {
+            let first_song_id = sqlx::query!(
+                "SELECT s.id FROM songs s
+                JOIN users u ON s.user_id = u.id
+                WHERE u.jam_id = $1 AND s.user_id <> $1
+                ORDER BY s.added_at ASC
+                LIMIT 1",
+                id.jam_id()
+            )
+            .fetch_optional(&mut **transaction)
+            .await?
+            .map(|song| song.id);
+            Ok(songs
+                .into_iter()
+                .find(|song| song.id.is_some() && song.id == first_song_id))
+        }
+        QueueStrategy::Shuffle => Ok(songs.choose(&mut rng).cloned()),
+    }
 }
 
 pub async fn get_songs<'e>(
@@ -79,11 +118,15 @@ pub async fn get_songs<'e>(
         pub votes: Option<i64>,
         pub artists: Option<Vec<String>>,
         pub image_url: String,
+        pub preview_url: Option<String>,
+        pub release_year: Option<i32>,
+        pub explicit: bool,
+        pub popularity: Option<i32>,
     }
 
     let vec = sqlx::query_as!(
         SongDb,
-        "SELECT s.id, s.spotify_id ,s.artists, s.image_url, s.user_id, s.name, s.album, s.duration, COUNT(v.id) AS votes
+        "SELECT s.id, s.spotify_id ,s.artists, s.image_url, s.user_id, s.name, s.album, s.duration, s.preview_url, s.release_year, s.explicit, s.popularity, COUNT(v.id) + s.boosts AS votes
         FROM songs s
         JOIN users u ON s.user_id = u.id
         LEFT JOIN votes v ON s.id = v.song_id
@@ -162,6 +205,12 @@ pub async fn get_songs<'e>(
             album: song.album,
             duration: song.duration as u32,
             image_url: song.image_url,
+            details: SongDetails {
+                preview_url: song.preview_url,
+                release_year: song.release_year.map(|year| year as u16),
+                explicit: song.explicit,
+                popularity: song.popularity.map(|popularity| popularity as u8),
+            },
         })
         .collect::<Vec<_>>();
 
@@ -175,9 +224,9 @@ pub async fn add_song<'e>(
     transaction: &mut sqlx::Transaction<'e, sqlx::Postgres>,
     credentials: SpotifyCredentials,
 ) -> Result<real_time::Changed, Error> {
-    use rspotify::AuthCodeSpotify;
-    use rspotify::prelude::*;
     println!("adding song, with id: {}", spotify_song_id);
+    let spotify_song_id = parse_track_id(spotify_song_id)?;
+    let spotify_song_id = spotify_song_id.as_str();
 
     let does_song_exist = sqlx::query!("SELECT EXISTS(SELECT 1 FROM songs WHERE spotify_id=$1 AND user_id IN (SELECT id FROM users WHERE jam_id=$2) AND user_id <> $2)", spotify_song_id, jam_id)
         .fetch_one(&mut **transaction)
@@ -193,40 +242,226 @@ pub async fn add_song<'e>(
         .count
         .unwrap_or(0);
 
-    let max_amount_of_songs = sqlx::query!("SELECT max_song_count FROM jams WHERE id=$1", jam_id)
-        .fetch_one(&mut **transaction)
-        .await?
-        .max_song_count;
+    let jam = sqlx::query!(
+        "SELECT max_song_count, allow_explicit, state, song_limit, no_repeat_minutes FROM jams WHERE id=$1",
+        jam_id
+    )
+    .fetch_one(&mut **transaction)
+    .await?;
+
+    if !JamState::from_db_str(&jam.state).accepts_songs() {
+        return Err(Error::Forbidden(
+            "the host has not opened the jam for requests yet".to_string(),
+        ));
+    }
 
-    if amount_of_songs as i16 >= max_amount_of_songs {
+    let song_limit = SongLimit::from_db_str(&jam.song_limit);
+    if song_limit == SongLimit::MaxSongs && amount_of_songs as i16 >= jam.max_song_count {
         return Err(Error::UserHasTooTheMaxSongAmount);
     }
 
-    let token = get_access_token(transaction, jam_id, credentials).await?;
-    let client = AuthCodeSpotify::from_token(token);
-    let track_id = TrackId::from_id(spotify_song_id)?;
-    let song = client.track(track_id, None).await?;
+    // most songs are added from the search, which cached them
+    let song = super::get_song(spotify_song_id, jam_id, transaction, credentials).await?;
+
+    if song.details.explicit && !jam.allow_explicit {
+        return Err(Error::Forbidden(
+            "explicit songs are not allowed in this jam".to_string(),
+        ));
+    }
+
+    let key = song_key(&song.name, &song.artists);
+    check_for_repeats(
+        &song.name,
+        &song.artists,
+        &key,
+        spotify_song_id,
+        jam_id,
+        jam.no_repeat_minutes as i64,
+        transaction,
+    )
+    .await?;
+
+    let mut changed = real_time::Changed::new().songs();
+    if song_limit == SongLimit::Credits {
+        super::spend_credits(user_id, Credits::SONG_COST, "adding a song", transaction).await?;
+        changed = changed.credits();
+    }
 
     sqlx::query!(
         "INSERT INTO songs 
-            (id, user_id, name, album, duration, image_url, artists, spotify_id) 
+            (id, user_id, name, album, duration, image_url, artists, spotify_id, preview_url, release_year, explicit, popularity, song_key) 
         VALUES 
-            ($1, $2, $3, $4, $5, $6, $7, $8);",
+            ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13);",
         cuid2::create_id(),
         user_id,
         song.name,
-        song.album.name,
-        song.duration.num_milliseconds() as i32,
-        song.album.images[0].url,
-        &song
-            .artists
-            .into_iter()
-            .map(|a| a.name)
-            .collect::<Vec<String>>(),
+        song.album,
+        song.duration as i32,
+        song.image_url,
+        &song.artists,
         spotify_song_id,
+        song.details.preview_url,
+        song.details.release_year.map(|year| year as i32),
+        song.details.explicit,
+        song.details.popularity.map(|popularity| popularity as i32),
+        key
     )
     .execute(&mut **transaction)
     .await?;
 
-    Ok(real_time::Changed::new().songs())
+    Ok(changed)
+}
+
+/// accepts the id, the uri or the link of a spotify track, returns the id,
+/// links to albums, artists and playlists are opened in the search instead
+pub fn parse_track_id(input: &str) -> Result<String, Error> {
+    match real_time::SpotifyLink::parse(input) {
+        Some(link) if link.kind == real_time::SearchKind::Track => Ok(link.spotify_id),
+        Some(_) => Err(Error::InvalidRequest(
+            "only links to songs can be added directly, open albums, artists and playlists in the search to pick songs"
+                .to_string(),
+        )),
+        None => Ok(input.trim().to_string()),
+    }
+}
+
+/// the title without version info like (Remastered 2011) or - Single Version, and the primary artist,
+/// in lower case and without punctuation, so different releases of the same song have the same key
+pub fn song_key(name: &str, artists: &[String]) -> String {
+    let normalize = |s: &str| {
+        s.to_lowercase()
+            .chars()
+            .filter(|c| c.is_alphanumeric() || c.is_whitespace())
+            .collect::<String>()
+            .split_whitespace()
+            .join(" ")
+    };
+
+    let title = name.split(" - ").next().unwrap_or(name);
+    let mut depth = 0_usize;
+    let title = title
+        .chars()
+        .filter(|c| match c {
+            '(' | '[' => {
+                depth += 1;
+                false
+            }
+            ')' | ']' => {
+                depth = depth.saturating_sub(1);
+                false
+            }
+            _ => depth == 0,
+        })
+        .collect::<String>();
+    let title = match normalize(&title) {
+        title if title.is_empty() => normalize(name),
+        title => title,
+    };
+
+    let artist = artists.first().map(|a| normalize(a)).unwrap_or_default();
+    format!("{}|{}", title, artist)
+}
+
+/// rejects other versions of songs that are already queued,
+/// and songs that were played within the no repeat window of the jam,
+/// `key` is the [`song_key`] of the song
+async fn check_for_repeats(
+    name: &str,
+    artists: &[String],
+    key: &str,
+    spotify_song_id: &str,
+    jam_id: &str,
+    no_repeat_minutes: i64,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+) -> Result<(), Error> {
+    let artist = artists.first().cloned().unwrap_or_default();
+
+    let queued = sqlx::query!(
+        "SELECT name FROM songs WHERE song_key = $2 AND user_id IN (SELECT id FROM users WHERE jam_id=$1) AND user_id <> $1 LIMIT 1",
+        jam_id,
+        key
+    )
+    .fetch_optional(&mut **transaction)
+    .await?;
+    if let Some(queued) = queued {
+        return Err(Error::DuplicateSong(format!(
+            "{} by {} is already in the queue as {}",
+            name, artist, queued.name
+        )));
+    }
+
+    if no_repeat_minutes == 0 {
+        return Ok(());
+    }
+
+    let now = chrono::Utc::now().timestamp_millis();
+    let window_start = now - no_repeat_minutes * 60 * 1000;
+    let played = sqlx::query!(
+        "SELECT played_at FROM played_songs
+        WHERE jam_id = $1 AND played_at >= $2 AND (spotify_id = $3 OR song_key = $4)
+        ORDER BY played_at DESC LIMIT 1",
+        jam_id,
+        window_start,
+        spotify_song_id,
+        key
+    )
+    .fetch_optional(&mut **transaction)
+    .await?;
+    if let Some(played) = played {
+        let minutes_ago = (now - played.played_at) / 60_000;
+        let minutes_left = no_repeat_minutes - minutes_ago;
+        return Err(Error::SongRecentlyPlayed(format!(
+            "{} by {} was played {} minutes ago, it can be added again in {} minutes",
+            name, artist, minutes_ago, minutes_left
+        )));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn track_ids_come_from_links_or_as_they_are() {
+        assert_eq!(
+            parse_track_id("https://open.spotify.com/track/4uLU6hMCjMI75M1A2tKUQC?si=x").unwrap(),
+            "4uLU6hMCjMI75M1A2tKUQC"
+        );
+        assert_eq!(
+            parse_track_id(" 4uLU6hMCjMI75M1A2tKUQC ").unwrap(),
+            "4uLU6hMCjMI75M1A2tKUQC"
+        );
+        assert!(matches!(
+            parse_track_id("spotify:album:1DFixLWuPkv3KT3TnV35m3"),
+            Err(Error::InvalidRequest(_))
+        ));
+    }
+
+    #[test]
+    fn other_versions_of_a_song_have_the_same_key() {
+        let queen = vec!["Queen".to_string(), "David Bowie".to_string()];
+        let key = song_key("Don't Stop Me Now", &queen);
+        assert_eq!(key, "dont stop me now|queen");
+        assert_eq!(song_key("Don't Stop Me Now - Remastered 2011", &queen), key);
+        assert_eq!(song_key("Don't Stop Me Now (Live) [2011 Mix]", &queen), key);
+        assert_eq!(song_key("DON'T  STOP ME NOW", &["queen".to_string()]), key);
+    }
+
+    #[test]
+    fn different_songs_have_different_keys() {
+        let queen = vec!["Queen".to_string()];
+        assert_ne!(
+            song_key("Don't Stop Me Now", &queen),
+            song_key("Don't Stop Me Now", &["McFly".to_string()])
+        );
+        assert_ne!(
+            song_key("Don't Stop Me Now", &queen),
+            song_key("Don't Stop Believin'", &queen)
+        );
+        // a title that is only version info is kept, so it isn't the same as every other such title
+        assert_eq!(song_key("(Intro)", &queen), "intro|queen");
+        assert_eq!(song_key("Intro", &[]), "intro|");
+    }
 }
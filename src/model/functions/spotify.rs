@@ -1,11 +1,115 @@
+use super::{CachedSearch, SearchCache, call_spotify, call_spotify_once, get_user_access_token};
 use crate::model::types::*;
+use itertools::Itertools;
 use rand::{Rng, SeedableRng};
+use real_time::{LibrarySource, SearchCollection, SearchKind, SearchQuery, SpotifyLink};
 use rspotify::{
     AuthCodeSpotify,
     clients::{BaseClient, OAuthClient},
-    model::{Id, PlayableId, SearchResult, TrackId},
+    model::{
+        AlbumId, ArtistId, FullTrack, Id, Market, PlayableId, PlaylistId, SearchResult, TrackId,
+    },
 };
+use std::sync::OnceLock;
 
+/// the url the host is redirected to, to give access to their spotify account,
+/// spotify redirects back to /create-host with the host id as the state
+pub fn spotify_authorize_url(
+    credentials: &SpotifyCredentials,
+    site_url: &str,
+    host_id: &str,
+) -> String {
+    format!(
+        "{}authorize?response_type=code&client_id={}&scope={}&redirect_uri={}/create-host&state={}&show_dialog=true",
+        credentials.accounts_url,
+        credentials.id,
+        "user-read-playback-state user-modify-playback-state user-read-currently-playing streaming user-read-private user-read-email user-read-recently-played user-top-read",
+        site_url,
+        host_id
+    )
+}
+
+/// the url a guest is redirected to, to browse their own library, the access is read only,
+/// spotify redirects back to /connect-spotify with the user id as the state
+pub fn spotify_user_authorize_url(
+    credentials: &SpotifyCredentials,
+    site_url: &str,
+    user_id: &str,
+) -> String {
+    format!(
+        "{}authorize?response_type=code&client_id={}&scope={}&redirect_uri={}/connect-spotify&state={}&show_dialog=true",
+        credentials.accounts_url,
+        credentials.id,
+        "user-library-read playlist-read-private playlist-read-collaborative user-top-read",
+        site_url,
+        user_id
+    )
+}
+
+/// the token spotify gives for the code from the oauth redirect
+#[derive(serde::Deserialize, Debug)]
+pub struct SpotifyTokenResponse {
+    pub access_token: String,
+    pub scope: String,
+    pub expires_in: i64,
+    pub refresh_token: String,
+}
+
+/// trades the code from the oauth redirect for a token, the redirect uri has to be the one the code was requested with
+pub async fn request_spotify_token(
+    code: &str,
+    redirect_uri: &str,
+    spotify_credentials: &SpotifyCredentials,
+    reqwest_client: &reqwest::Client,
+) -> Result<SpotifyTokenResponse, Error> {
+    let body = [
+        ("code", code),
+        ("redirect_uri", redirect_uri),
+        ("grant_type", "authorization_code"),
+        ("client_id", spotify_credentials.id.as_str()),
+        ("client_secret", spotify_credentials.secret.as_str()),
+    ];
+    let res = match reqwest_client
+        .post(format!("{}api/token", spotify_credentials.accounts_url))
+        .form(&body)
+        .send()
+        .await
+    {
+        Ok(res) => res,
+        Err(e) => {
+            return Err(Error::Spotify(format!(
+                "error while acquiring temp spotify token: {:#?}",
+                e
+            )));
+        }
+    };
+
+    if !res.status().is_success() {
+        eprintln!("Error: {:?}", res);
+        return Err(Error::Spotify(format!(
+            "error while acquiring spotify token, spotify returned not ok response code: {:#?}",
+            res
+        )));
+    }
+
+    let res = match res.text().await {
+        Ok(res) => res,
+        Err(e) => {
+            return Err(Error::Decode(format!(
+                "error while getting text from spotify response: {:#?}",
+                e
+            )));
+        }
+    };
+
+    match serde_json::from_str(res.as_str()) {
+        Ok(token) => Ok(token),
+        Err(e) => Err(Error::Decode(format!(
+            "error while deserializing spotify response spotify token: {:#?}",
+            e
+        ))),
+    }
+}
 
 pub async fn switch_playback_to_device<'e>(
     device_id: &str,
@@ -13,191 +117,780 @@ pub async fn switch_playback_to_device<'e>(
     transaction: &mut sqlx::Transaction<'e, sqlx::Postgres>,
     credentials: SpotifyCredentials,
 ) -> Result<(), Error> {
-    let token = get_access_token(transaction, jam_id, credentials).await?;
-    let client = AuthCodeSpotify::from_token(token);
-    if let Err(e) = client.transfer_playback(device_id, Some(true)).await {
-        return Err(Error::Spotify(format!(
-            "could not switch playback to device: {}",
-            e
-        )));
+    let client = get_host_client(transaction, jam_id, credentials).await?;
+    if let Err(e) = call_spotify(&client.host_id, || {
+        client.transfer_playback(device_id, Some(true))
+    })
+    .await
+    {
+        return Err(describe(e, "could not switch playback to device"));
     };
     Ok(())
 }
 
-#[allow(dead_code)]
+/// the spotify connect devices of the host, devices without an id, like restricted ones, can't be played on
+pub async fn get_playback_devices<'e>(
+    jam_id: &str,
+    transaction: &mut sqlx::Transaction<'e, sqlx::Postgres>,
+    credentials: SpotifyCredentials,
+) -> Result<PlaybackDevices, Error> {
+    let selected = get_playback_device(jam_id, &mut **transaction).await?;
+    let client = get_host_client(transaction, jam_id, credentials).await?;
+    let devices = call_spotify(&client.host_id, || client.device())
+        .await?
+        .into_iter()
+        .filter_map(|device| {
+            Some(PlaybackDevice {
+                id: device.id?,
+                name: device.name,
+                kind: format!("{:?}", device._type),
+                is_active: device.is_active,
+                volume_percent: device.volume_percent,
+            })
+        })
+        .collect();
+    Ok(PlaybackDevices { devices, selected })
+}
+
+/// the device the jam plays on, none if it plays in the browser of the host
+pub async fn get_playback_device<'e>(
+    jam_id: &str,
+    executor: impl sqlx::PgExecutor<'e>,
+) -> Result<Option<String>, Error> {
+    match sqlx::query!("SELECT device_id FROM jams WHERE id = $1", jam_id)
+        .fetch_optional(executor)
+        .await?
+    {
+        Some(jam) => Ok(jam.device_id),
+        None => Err(Error::DoesNotExist(format!(
+            "jam with id {} does not exist, could not get its device",
+            jam_id
+        ))),
+    }
+}
+
+/// remembers the device the jam plays on and moves the playback to it, so everything is played there from now on,
+/// none means the browser of the host, its player takes the playback back once it is ready
+pub async fn set_playback_device<'e>(
+    device_id: Option<&str>,
+    jam_id: &str,
+    transaction: &mut sqlx::Transaction<'e, sqlx::Postgres>,
+    credentials: SpotifyCredentials,
+) -> Result<(), Error> {
+    if let Some(device_id) = device_id {
+        switch_playback_to_device(device_id, jam_id, transaction, credentials).await?;
+    }
+    sqlx::query!(
+        "UPDATE jams SET device_id = $1 WHERE id = $2",
+        device_id,
+        jam_id
+    )
+    .execute(&mut **transaction)
+    .await?;
+    Ok(())
+}
+
 #[derive(Debug, Clone)]
 struct AccessTokenDb {
+    pub id: String,
     pub refresh_token: String,
     pub access_token: String,
     pub expires_at: i64,
     pub scope: String,
-    pub id: String,
-    pub host_id: String,
+    pub revoked: bool,
+}
+
+/// tokens are refreshed this many seconds before they expire,
+/// so a token that is handed out, like to the player of the host, doesn't run out right away
+pub const TOKEN_REFRESH_MARGIN: i64 = 5 * 60;
+
+impl AccessTokenDb {
+    fn into_token(self) -> Result<(String, rspotify::Token), Error> {
+        if self.revoked {
+            return Err(spotify_login_expired());
+        }
+
+        let expires_at = chrono::DateTime::from_timestamp(self.expires_at, 0).unwrap();
+        let expires_at = Some(expires_at);
+        let expires_in = self.expires_at - chrono::Utc::now().timestamp();
+        let expires_in = chrono::TimeDelta::new(expires_in, 0).unwrap();
+
+        let token = rspotify::Token {
+            access_token: self.access_token,
+            expires_in,
+            expires_at,
+            refresh_token: Some(self.refresh_token),
+            scopes: rspotify::scopes!(self.scope),
+        };
+
+        Ok((self.id, token))
+    }
 }
 
+/// the token of the host of the jam, with the id of its row
 async fn get_maybe_expired_access_token<'e>(
     executor: impl sqlx::PgExecutor<'e>,
     jam_id: &str,
-) -> Result<rspotify::Token, Error> {
-    let token = match sqlx::query_as!(
+) -> Result<(String, rspotify::Token), Error> {
+    match sqlx::query_as!(
         AccessTokenDb,
-        "SELECT * FROM access_tokens WHERE host_id=(SELECT host_id FROM jams WHERE id=$1) ",
+        "SELECT id, refresh_token, access_token, expires_at, scope, revoked FROM access_tokens
+        WHERE host_id=(SELECT host_id FROM jams WHERE id=$1)",
         jam_id
     )
-    .fetch_one(executor)
-    .await
+    .fetch_optional(executor)
+    .await?
     {
-        Ok(token) => token,
-        Err(sqlx::Error::RowNotFound) => {
-            return Err(Error::DoesNotExist(format!(
-                "no access token found for jam with id: {}, could not get access token",
-                jam_id
-            )));
-        }
-        Err(e) => return Err(e.into()),
-    };
+        Some(token) => token.into_token(),
+        None => Err(Error::DoesNotExist(format!(
+            "no access token found for jam with id: {}, could not get access token",
+            jam_id
+        ))),
+    }
+}
 
-    let expires_at = chrono::DateTime::from_timestamp(token.expires_at, 0).unwrap();
-    let expires_at = Some(expires_at);
-    let expires_in = token.expires_at - chrono::Utc::now().timestamp();
-    let expires_in = chrono::TimeDelta::new(expires_in, 0).unwrap();
-
-    let token = rspotify::Token {
-        access_token: token.access_token,
-        expires_in,
-        expires_at,
-        refresh_token: Some(token.refresh_token),
-        scopes: rspotify::scopes!(token.scope),
-    };
+/// true if the token expires within the refresh margin
+pub fn needs_refresh(token: &rspotify::Token) -> bool {
+    let now = chrono::Utc::now().timestamp();
+    token.expires_at.unwrap_or_default().timestamp() - TOKEN_REFRESH_MARGIN <= now
+}
 
-    Ok(token)
+fn spotify_login_expired() -> Error {
+    Error::SpotifyLoginExpired(
+        "the host has to log in with spotify again, spotify doesn't accept the old login anymore"
+            .to_string(),
+    )
+}
+
+/// a client for the api in the credentials, that is spotify, unless a stand-in server is configured
+pub fn spotify_client(token: rspotify::Token, credentials: &SpotifyCredentials) -> AuthCodeSpotify {
+    AuthCodeSpotify::from_token_with_config(
+        token,
+        rspotify::Credentials::new(&credentials.id, &credentials.secret),
+        rspotify::OAuth::default(),
+        rspotify::Config {
+            api_base_url: credentials.api_url.clone(),
+            auth_base_url: credentials.accounts_url.clone(),
+            ..Default::default()
+        },
+    )
+}
+
+/// A client with the token of the host of a jam, the calls are counted for the host,
+/// because all jams of a host use the same token
+struct HostClient {
+    host_id: String,
+    client: AuthCodeSpotify,
+}
+
+impl std::ops::Deref for HostClient {
+    type Target = AuthCodeSpotify;
+
+    fn deref(&self) -> &Self::Target {
+        &self.client
+    }
+}
+
+/// a client with the token of the host of the jam
+async fn get_host_client(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    jam_id: &str,
+    credentials: SpotifyCredentials,
+) -> Result<HostClient, Error> {
+    let token = get_access_token(transaction, jam_id, credentials.clone()).await?;
+    let host_id = sqlx::query!("SELECT host_id FROM jams WHERE id = $1", jam_id)
+        .fetch_one(&mut **transaction)
+        .await?
+        .host_id;
+    Ok(HostClient {
+        host_id,
+        client: spotify_client(token, &credentials),
+    })
 }
 
-///this also refreshes the token if it is expired
+/// this also refreshes the token shortly before it expires
 pub async fn get_access_token<'e>(
     transaction: &mut sqlx::Transaction<'e, sqlx::Postgres>,
     jam_id: &str,
     credentials: SpotifyCredentials,
 ) -> Result<rspotify::Token, Error> {
-    let token = get_maybe_expired_access_token(&mut **transaction, jam_id).await?;
-    let now = chrono::Utc::now().timestamp();
-    if now < token.expires_at.unwrap_or_default().timestamp() {
+    let (token_id, token) = get_maybe_expired_access_token(&mut **transaction, jam_id).await?;
+    if !needs_refresh(&token) {
         return Ok(token);
     }
-    let old_access_token = token.access_token.clone();
-    let client = rspotify::AuthCodeSpotify::from_token_with_config(
-        token,
-        rspotify::Credentials {
-            id: credentials.id,
-            secret: Some(credentials.secret),
-        },
-        rspotify::OAuth::default(),
-        rspotify::Config::default(),
-    );
-    client.refetch_token().await?;
-    client.refresh_token().await?;
-    let new_token = client
-        .get_token()
-        .as_ref()
-        .lock()
-        .await
-        .unwrap()
-        .clone()
-        .unwrap();
+    refresh_access_token(&token_id, jam_id, credentials).await
+}
+
+/// the pool refreshed tokens are saved with, it lives outside of the app state like the spotify breakers,
+/// because every spotify call might have to refresh the token
+static TOKEN_POOL: OnceLock<sqlx::PgPool> = OnceLock::new();
+
+/// has to be called once when the server starts, before spotify is called with the token of a host
+pub fn set_token_pool(pool: sqlx::PgPool) {
+    if TOKEN_POOL.set(pool).is_err() {
+        eprintln!("the pool for refreshing tokens was already set");
+    }
+}
 
-    let res=sqlx::query!(
-        "UPDATE access_tokens SET access_token=$1, expires_at=$2, scope=$3, refresh_token=$4 WHERE access_token=$5;",
+/// refreshes in its own transaction that is committed right away, spotify can invalidate the old refresh token
+/// with every refresh, so the new one must not be lost if the transaction of the caller is rolled back,
+/// the row of the token is locked while refreshing, so concurrent requests wait for the new token
+/// instead of refreshing it again with the same refresh token
+async fn refresh_access_token(
+    token_id: &str,
+    jam_id: &str,
+    credentials: SpotifyCredentials,
+) -> Result<rspotify::Token, Error> {
+    let Some(pool) = TOKEN_POOL.get() else {
+        return Err(Error::Database(
+            "the pool for refreshing tokens is not set, could not refresh the access token"
+                .to_string(),
+        ));
+    };
+    let mut transaction = pool.begin().await?;
+    // the transaction of the caller could hold the lock itself, so it isn't waited for forever
+    sqlx::query!("SELECT set_config('lock_timeout', '10s', true)")
+        .execute(&mut *transaction)
+        .await?;
+    let token = sqlx::query_as!(
+        AccessTokenDb,
+        "SELECT id, refresh_token, access_token, expires_at, scope, revoked FROM access_tokens
+        WHERE id=$1 FOR UPDATE",
+        token_id
+    )
+    .fetch_optional(&mut *transaction)
+    .await?;
+    let (_, token) = match token {
+        Some(token) => token.into_token()?,
+        None => {
+            return Err(Error::DoesNotExist(format!(
+                "the access token of jam with id: {} is gone, could not refresh it",
+                jam_id
+            )));
+        }
+    };
+    // another request might have refreshed it while this one waited for the lock
+    if !needs_refresh(&token) {
+        return Ok(token);
+    }
+
+    let new_token = match refresh_token(token, credentials).await {
+        Ok(new_token) => new_token,
+        Err(Error::SpotifyLoginExpired(e)) => {
+            // remembered, so spotify isn't asked again with a refresh token it already rejected
+            sqlx::query!(
+                "UPDATE access_tokens SET revoked=true WHERE id=$1",
+                token_id
+            )
+            .execute(&mut *transaction)
+            .await?;
+            transaction.commit().await?;
+            eprintln!(
+                "the spotify login of the host of jam {} expired: {}",
+                jam_id, e
+            );
+            return Err(spotify_login_expired());
+        }
+        Err(e) => return Err(e),
+    };
+
+    let now = chrono::Utc::now().timestamp();
+    sqlx::query!(
+        "UPDATE access_tokens SET access_token=$1, expires_at=$2, scope=$3, refresh_token=COALESCE($4, refresh_token) WHERE id=$5",
         new_token.access_token,
         now + new_token.expires_in.num_seconds(),
         new_token.scopes.clone().into_iter().collect::<Vec<_>>().join(" "),
         new_token.refresh_token,
-        old_access_token
+        token_id
     )
-    .execute(&mut **transaction)
+    .execute(&mut *transaction)
     .await?;
-
-    if res.rows_affected() < 1 {
-        return Err(Error::DoesNotExist(format!(
-            "could not update access token, no token found with access token: {}",
-            old_access_token
-        )));
-    }
+    transaction.commit().await?;
 
     println!("updated token");
 
     Ok(new_token)
 }
 
-pub async fn get_song_recommendation<'e>(
+/// asks spotify for a new access token with the refresh token of an expired one,
+/// if spotify rejects the refresh token, the user revoked the access or it expired, so they have to log in again
+pub async fn refresh_token(
+    token: rspotify::Token,
     credentials: SpotifyCredentials,
+) -> Result<rspotify::Token, Error> {
+    let client = spotify_client(token, &credentials);
+    // only one request to spotify, every refresh might give a new refresh token and invalidate the old one
+    if let Err(e) = client.refresh_token().await {
+        let rejected = match &e {
+            rspotify::ClientError::Http(http) => matches!(
+                http.as_ref(),
+                rspotify::http::HttpError::StatusCode(response)
+                    if matches!(response.status().as_u16(), 400 | 401)
+            ),
+            _ => false,
+        };
+        if rejected {
+            return Err(Error::SpotifyLoginExpired(format!(
+                "spotify rejected the refresh token: {}",
+                e
+            )));
+        }
+        return Err(e.into());
+    }
+    let new_token = match client.get_token().lock().await {
+        Ok(token) => token.clone(),
+        Err(e) => {
+            return Err(Error::Spotify(format!(
+                "could not read the refreshed token: {:?}",
+                e
+            )));
+        }
+    };
+    new_token.ok_or_else(|| {
+        Error::Spotify("spotify refreshed the token, but did not return one".to_string())
+    })
+}
+
+/// the top tracks of the host, from a random offset so it isn't always the same ones
+pub async fn get_top_tracks<'e>(
     jam_id: &str,
     transaction: &mut sqlx::Transaction<'e, sqlx::Postgres>,
-) -> Result<Song, Error> {
-    let token = get_access_token(transaction, jam_id, credentials).await?;
-    let client = AuthCodeSpotify::from_token(token);
-    let mut tracks = client
-        .current_user_top_tracks_manual(
+    credentials: SpotifyCredentials,
+) -> Result<Vec<FullTrack>, Error> {
+    let client = get_host_client(transaction, jam_id, credentials).await?;
+    let offset = rand::prelude::StdRng::from_os_rng().random_range(0..20);
+    let tracks = call_spotify(&client.host_id, || {
+        client.current_user_top_tracks_manual(
             Some(rspotify::model::TimeRange::MediumTerm),
             Some(20),
-            Some(rand::prelude::StdRng::from_os_rng().random_range(0..20)),
+            Some(offset),
         )
-        .await?;
-    let track = tracks.items.remove(0);
-    Ok(track_to_song(track))
+    })
+    .await?;
+    Ok(tracks.items)
 }
 
-pub async fn search<'e>(
-    query: &str,
-    transaction: &mut sqlx::Transaction<'e, sqlx::Postgres>,
+/// tracks similar to the seeds, spotify takes at most 5 seeds
+pub async fn get_recommended_tracks<'e>(
+    seed_spotify_ids: &[String],
     jam_id: &str,
+    transaction: &mut sqlx::Transaction<'e, sqlx::Postgres>,
     credentials: SpotifyCredentials,
-) -> Result<Vec<Song>, Error> {
-    use rspotify::AuthCodeSpotify;
-    use rspotify::prelude::*;
+) -> Result<Vec<FullTrack>, Error> {
+    let client = get_host_client(transaction, jam_id, credentials).await?;
+    let seeds = seed_spotify_ids
+        .iter()
+        .take(5)
+        .map(|id| TrackId::from_id(id.as_str()))
+        .collect::<Result<Vec<_>, _>>()?;
+    let recommendations = call_spotify(&client.host_id, || {
+        client.recommendations(
+            [],
+            None::<Vec<rspotify::model::ArtistId>>,
+            None::<Vec<&str>>,
+            Some(seeds.clone()),
+            None,
+            Some(20),
+        )
+    })
+    .await?;
 
-    if query.is_empty() {
+    // the recommendations don't have album covers, so the full tracks are needed
+    let ids = recommendations
+        .tracks
+        .into_iter()
+        .filter_map(|track| track.id)
+        .collect::<Vec<_>>();
+    if ids.is_empty() {
         return Ok(vec![]);
     }
+    call_spotify(&client.host_id, || client.tracks(ids.clone(), None)).await
+}
 
-    let token = get_access_token(transaction, jam_id, credentials).await?;
-    let client = AuthCodeSpotify::from_token(token);
-    let result = client
-        .search(
-            query,
-            rspotify::model::SearchType::Track,
-            None,
-            None,
-            Some(30),
-            Some(0),
-        )
+pub async fn get_playlist_tracks<'e>(
+    playlist_id: &str,
+    jam_id: &str,
+    transaction: &mut sqlx::Transaction<'e, sqlx::Postgres>,
+    credentials: SpotifyCredentials,
+) -> Result<Vec<FullTrack>, Error> {
+    let client = get_host_client(transaction, jam_id, credentials).await?;
+    let playlist_id = PlaylistId::from_id(playlist_id)?;
+
+    let mut tracks = Vec::new();
+    let mut offset = 0;
+    loop {
+        let page = call_spotify(&client.host_id, || {
+            client.playlist_items_manual(playlist_id.as_ref(), None, None, Some(100), Some(offset))
+        })
         .await?;
-    let songs = if let SearchResult::Tracks(tracks) = result {
-        tracks
-    } else {
-        return Err(Error::Spotify(
-            "Error in search, returned other then tracks".to_string(),
-        ));
+        offset += page.items.len() as u32;
+        tracks.extend(page.items.into_iter().filter_map(|item| match item.track {
+            Some(rspotify::model::PlayableItem::Track(track)) => Some(track),
+            _ => None,
+        }));
+        if page.next.is_none() {
+            break;
+        }
+    }
+    Ok(tracks)
+}
+
+/// how many results are sent at once, the next page is loaded when the user scrolls to the end
+const SEARCH_PAGE_SIZE: u32 = 20;
+
+/// one page of search results, the songs of an opened album, artist or playlist are also a search,
+/// songs that are already in the jam are left out, after the cache, because they change all the time,
+/// the user id is some if a user searches, they might have logged in with spotify to browse their library
+pub async fn search<'e>(
+    query: SearchQuery,
+    offset: u32,
+    search_id: String,
+    transaction: &mut sqlx::Transaction<'e, sqlx::Postgres>,
+    jam_id: &str,
+    user_id: Option<&str>,
+    search_cache: &SearchCache,
+    credentials: SpotifyCredentials,
+) -> Result<real_time::SearchResult, Error> {
+    let mut result = real_time::SearchResult {
+        songs: vec![],
+        collections: vec![],
+        search_id,
+        query,
+        offset,
+        next_offset: None,
+    };
+    if let SearchQuery::Text { query, .. } = &result.query
+        && query.is_empty()
+    {
+        return Ok(result);
+    }
+
+    let cacheable = match &result.query {
+        SearchQuery::Library(_) => false,
+        SearchQuery::Collection(collection) => {
+            collection.kind != SearchKind::Playlist || user_id.is_none()
+        }
+        SearchQuery::Text { .. } => true,
+    };
+    let page = match cacheable
+        .then(|| search_cache.get(&result.query, offset))
+        .flatten()
+    {
+        Some(page) => page,
+        None => {
+            let page = fetch_search(
+                &result.query,
+                offset,
+                transaction,
+                jam_id,
+                user_id,
+                credentials,
+            )
+            .await?;
+            super::cache_songs(&page.songs, transaction).await?;
+            if cacheable {
+                search_cache.insert(result.query.clone(), offset, page.clone());
+            }
+            page
+        }
     };
 
     let songs_in_jam = sqlx::query!(
-        "SELECT id FROM songs WHERE user_id IN (SELECT id FROM users WHERE jam_id=$1);",
+        "SELECT spotify_id FROM songs WHERE user_id IN (SELECT id FROM users WHERE jam_id=$1);",
         jam_id
     )
     .fetch_all(&mut **transaction)
     .await?
     .into_iter()
-    .map(|song| song.id)
+    .map(|song| song.spotify_id)
     .collect::<Vec<String>>();
 
-    let songs = songs
-        .items
+    result.songs = page
+        .songs
         .into_iter()
-        .filter(|song| !songs_in_jam.contains(&song.id.as_ref().unwrap().id().to_owned()))
-        .collect::<Vec<_>>();
+        .filter(|song| !songs_in_jam.contains(&song.spotify_id))
+        .collect();
+    result.collections = page.collections;
+    result.next_offset = page.next_offset;
+
+    Ok(result)
+}
+
+/// asks spotify for a page of results, the results of searches made with the token of the host can be cached,
+/// the library and the private playlists of a user need their own token
+async fn fetch_search(
+    query: &SearchQuery,
+    offset: u32,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    jam_id: &str,
+    user_id: Option<&str>,
+    credentials: SpotifyCredentials,
+) -> Result<CachedSearch, Error> {
+    let client = get_host_client(transaction, jam_id, credentials.clone()).await?;
+    let needs_user_token = match query {
+        SearchQuery::Library(_) => true,
+        SearchQuery::Collection(collection) => collection.kind == SearchKind::Playlist,
+        SearchQuery::Text { .. } => false,
+    };
+    let user_client = match user_id.filter(|_| needs_user_token) {
+        Some(user_id) => get_user_access_token(user_id, transaction, credentials.clone())
+            .await?
+            .map(|token| spotify_client(token, &credentials)),
+        None => None,
+    };
 
-    let songs = songs.into_iter().map(track_to_song).collect::<Vec<Song>>();
+    // a pasted link shows the song it points to, or the songs of the album, artist or playlist
+    let link = match query {
+        SearchQuery::Text { query: text, .. } => SpotifyLink::parse(text),
+        SearchQuery::Collection(_) | SearchQuery::Library(_) => None,
+    };
 
-    Ok(songs)
+    let mut next_offset = None;
+    let mut collections = vec![];
+    let tracks = match (query, link) {
+        (_, Some(link)) => {
+            let (tracks, next) = get_linked_tracks(&client, &link, offset, &client.host_id).await?;
+            next_offset = next;
+            tracks
+        }
+        (SearchQuery::Text { query: text, kind }, None) => {
+            let search_type = match kind {
+                SearchKind::Track => rspotify::model::SearchType::Track,
+                SearchKind::Album => rspotify::model::SearchType::Album,
+                SearchKind::Artist => rspotify::model::SearchType::Artist,
+                SearchKind::Playlist => rspotify::model::SearchType::Playlist,
+            };
+            match call_spotify(&client.host_id, || {
+                client.search(
+                    text,
+                    search_type,
+                    None,
+                    None,
+                    Some(SEARCH_PAGE_SIZE),
+                    Some(offset),
+                )
+            })
+            .await?
+            {
+                SearchResult::Tracks(page) => {
+                    next_offset = page_next_offset(&page);
+                    page.items
+                }
+                SearchResult::Albums(page) => {
+                    next_offset = page_next_offset(&page);
+                    collections = page
+                        .items
+                        .into_iter()
+                        .filter_map(|album| {
+                            Some(SearchCollection {
+                                kind: SearchKind::Album,
+                                spotify_id: album.id?.id().to_string(),
+                                name: album.name,
+                                subtitle: album.artists.into_iter().map(|a| a.name).join(", "),
+                                image_url: first_image_url(album.images),
+                            })
+                        })
+                        .collect();
+                    vec![]
+                }
+                SearchResult::Artists(page) => {
+                    next_offset = page_next_offset(&page);
+                    collections = page
+                        .items
+                        .into_iter()
+                        .map(|artist| SearchCollection {
+                            kind: SearchKind::Artist,
+                            spotify_id: artist.id.id().to_string(),
+                            name: artist.name,
+                            subtitle: format!("{} followers", artist.followers.total),
+                            image_url: first_image_url(artist.images),
+                        })
+                        .collect();
+                    vec![]
+                }
+                SearchResult::Playlists(page) => {
+                    next_offset = page_next_offset(&page);
+                    collections = page.items.into_iter().map(playlist_to_collection).collect();
+                    vec![]
+                }
+                _ => {
+                    return Err(Error::Spotify(
+                        "Error in search, returned other results than asked for".to_string(),
+                    ));
+                }
+            }
+        }
+        (SearchQuery::Collection(collection), None) => {
+            let link = SpotifyLink {
+                kind: collection.kind,
+                spotify_id: collection.spotify_id.clone(),
+            };
+            // private playlists from the library of the user can only be opened with their token
+            let (client, owner_id) = match (collection.kind, &user_client, user_id) {
+                (SearchKind::Playlist, Some(user_client), Some(user_id)) => (user_client, user_id),
+                _ => (&*client, client.host_id.as_str()),
+            };
+            let (tracks, next) = get_linked_tracks(client, &link, offset, owner_id).await?;
+            next_offset = next;
+            tracks
+        }
+        (SearchQuery::Library(source), None) => {
+            let (client, user_id) = match (&user_client, user_id) {
+                (Some(user_client), Some(user_id)) => (user_client, user_id),
+                _ => {
+                    return Err(Error::Forbidden(
+                        "log in with spotify to browse your library".to_string(),
+                    ));
+                }
+            };
+            match source {
+                LibrarySource::SavedTracks => {
+                    let page = call_spotify(user_id, || {
+                        client.current_user_saved_tracks_manual(
+                            None,
+                            Some(SEARCH_PAGE_SIZE),
+                            Some(offset),
+                        )
+                    })
+                    .await?;
+                    next_offset = page_next_offset(&page);
+                    page.items.into_iter().map(|saved| saved.track).collect()
+                }
+                LibrarySource::TopTracks => {
+                    let page = call_spotify(user_id, || {
+                        client.current_user_top_tracks_manual(
+                            Some(rspotify::model::TimeRange::MediumTerm),
+                            Some(SEARCH_PAGE_SIZE),
+                            Some(offset),
+                        )
+                    })
+                    .await?;
+                    next_offset = page_next_offset(&page);
+                    page.items
+                }
+                LibrarySource::Playlists => {
+                    let page = call_spotify(user_id, || {
+                        client.current_user_playlists_manual(Some(SEARCH_PAGE_SIZE), Some(offset))
+                    })
+                    .await?;
+                    next_offset = page_next_offset(&page);
+                    collections = page.items.into_iter().map(playlist_to_collection).collect();
+                    vec![]
+                }
+            }
+        }
+    };
+
+    Ok(CachedSearch {
+        songs: tracks
+            .into_iter()
+            .filter(|track| track.id.is_some())
+            .map(track_to_song)
+            .collect(),
+        collections,
+        next_offset,
+    })
+}
+
+/// the page of songs a link points to, a track is a single song,
+/// spotify only has the top tracks of an artist, so they are one page,
+/// owner id is the host or the user whose token the client has, for the breaker of their login
+async fn get_linked_tracks(
+    client: &AuthCodeSpotify,
+    link: &SpotifyLink,
+    offset: u32,
+    owner_id: &str,
+) -> Result<(Vec<FullTrack>, Option<u32>), Error> {
+    let id = link.spotify_id.as_str();
+    match link.kind {
+        SearchKind::Track if offset == 0 => {
+            let track_id = TrackId::from_id(id)?;
+            let track = call_spotify(owner_id, || client.track(track_id.clone(), None)).await?;
+            Ok((vec![track], None))
+        }
+        SearchKind::Artist if offset == 0 => {
+            let artist_id = ArtistId::from_id(id)?;
+            let tracks = call_spotify(owner_id, || {
+                client.artist_top_tracks(artist_id.clone(), Some(Market::FromToken))
+            })
+            .await?;
+            Ok((tracks, None))
+        }
+        SearchKind::Track | SearchKind::Artist => Ok((vec![], None)),
+        SearchKind::Album => {
+            let album_id = AlbumId::from_id(id)?;
+            let page = call_spotify(owner_id, || {
+                client.album_track_manual(
+                    album_id.clone(),
+                    None,
+                    Some(SEARCH_PAGE_SIZE),
+                    Some(offset),
+                )
+            })
+            .await?;
+            let next = page_next_offset(&page);
+            // the tracks of an album don't have the album cover, so the full tracks are needed
+            let ids = page
+                .items
+                .into_iter()
+                .filter_map(|track| track.id)
+                .collect::<Vec<_>>();
+            if ids.is_empty() {
+                return Ok((vec![], next));
+            }
+            let tracks = call_spotify(owner_id, || client.tracks(ids.clone(), None)).await?;
+            Ok((tracks, next))
+        }
+        SearchKind::Playlist => {
+            let playlist_id = PlaylistId::from_id(id)?;
+            let page = call_spotify(owner_id, || {
+                client.playlist_items_manual(
+                    playlist_id.clone(),
+                    None,
+                    None,
+                    Some(SEARCH_PAGE_SIZE),
+                    Some(offset),
+                )
+            })
+            .await?;
+            let next = page_next_offset(&page);
+            let tracks = page
+                .items
+                .into_iter()
+                .filter_map(|item| match item.track {
+                    Some(rspotify::model::PlayableItem::Track(track)) => Some(track),
+                    _ => None,
+                })
+                .collect();
+            Ok((tracks, next))
+        }
+    }
+}
+
+fn page_next_offset<T>(page: &rspotify::model::Page<T>) -> Option<u32> {
+    page.next
+        .as_ref()
+        .map(|_| page.offset + page.items.len() as u32)
+}
+
+fn playlist_to_collection(playlist: rspotify::model::SimplifiedPlaylist) -> SearchCollection {
+    SearchCollection {
+        kind: SearchKind::Playlist,
+        spotify_id: playlist.id.id().to_string(),
+        name: playlist.name,
+        subtitle: format!(
+            "by {}",
+            playlist
+                .owner
+                .display_name
+                .unwrap_or(playlist.owner.id.id().to_string())
+        ),
+        image_url: first_image_url(playlist.images),
+    }
+}
+
+fn first_image_url(images: Vec<rspotify::model::Image>) -> String {
+    images.into_iter().next().map(|i| i.url).unwrap_or_default()
 }
 
 pub async fn get_current_song_from_player<'e>(
@@ -205,40 +898,47 @@ pub async fn get_current_song_from_player<'e>(
     transaction: &mut sqlx::Transaction<'e, sqlx::Postgres>,
     credentials: SpotifyCredentials,
 ) -> Result<Option<Song>, Error> {
-    let token = get_access_token(transaction, jam_id, credentials).await?;
-    let client = AuthCodeSpotify::from_token(token);
-    let current = client.current_playing(None, None::<Vec<_>>).await?;
-    let current = match current {
-        Some(song) => song,
-        None => return Ok(None),
-    };
-    let current = match current.item {
-        Some(rspotify::model::PlayableItem::Track(track)) => track,
-        _ => return Ok(None),
-    };
-    Ok(Some(track_to_song(current)))
+    Ok(get_player_state(jam_id, transaction, credentials)
+        .await?
+        .map(|state| state.song))
 }
 
-pub async fn get_next_song_from_player<'e>(
+/// none if the player plays nothing or something that isn't a track, like a podcast
+pub async fn get_player_state<'e>(
     jam_id: &str,
     transaction: &mut sqlx::Transaction<'e, sqlx::Postgres>,
     credentials: SpotifyCredentials,
-) -> Result<Option<Song>, Error> {
-    let token = get_access_token(transaction, jam_id, credentials).await?;
-    let client = AuthCodeSpotify::from_token(token);
-    let current = client.current_user_queue().await?.queue.into_iter().next();
+) -> Result<Option<PlayerState>, Error> {
+    let client = get_host_client(transaction, jam_id, credentials).await?;
+    let current = call_spotify(&client.host_id, || {
+        client.current_playing(None, None::<Vec<_>>)
+    })
+    .await?;
     let current = match current {
         Some(song) => song,
         None => return Ok(None),
     };
-    let current = match current {
-        rspotify::model::PlayableItem::Track(track) => track,
+    let track = match current.item {
+        Some(rspotify::model::PlayableItem::Track(track)) => track,
         _ => return Ok(None),
     };
-    Ok(Some(track_to_song(current)))
+    Ok(Some(PlayerState {
+        song: track_to_song(track),
+        is_playing: current.is_playing,
+        progress: current
+            .progress
+            .map(|progress| progress.num_milliseconds().max(0) as u32)
+            .unwrap_or(0),
+    }))
 }
 
 pub fn track_to_song(track: rspotify::model::FullTrack) -> Song {
+    let details = SongDetails {
+        preview_url: track.preview_url.clone(),
+        release_year: SongDetails::parse_release_year(track.album.release_date.as_deref()),
+        explicit: track.explicit,
+        popularity: Some(track.popularity as u8),
+    };
     Song {
         id: None,
         spotify_id: track
@@ -249,7 +949,7 @@ pub fn track_to_song(track: rspotify::model::FullTrack) -> Song {
         name: track.name,
         artists: track.artists.into_iter().map(|a| a.name).collect(),
         album: track.album.name,
-        duration: track.duration.num_seconds() as u32,
+        duration: track.duration.num_milliseconds() as u32,
         image_url: track
             .album
             .images
@@ -261,6 +961,7 @@ pub fn track_to_song(track: rspotify::model::FullTrack) -> Song {
             votes: 0,
             have_you_voted: None,
         },
+        details,
     }
 }
 
@@ -270,8 +971,8 @@ pub async fn play_song<'e>(
     transaction: &mut sqlx::Transaction<'e, sqlx::Postgres>,
     credentials: SpotifyCredentials,
 ) -> Result<(), Error> {
-    let token = get_access_token(transaction, jam_id, credentials).await?;
-    let client = AuthCodeSpotify::from_token(token);
+    let device_id = get_playback_device(jam_id, &mut **transaction).await?;
+    let client = get_host_client(transaction, jam_id, credentials).await?;
     let song_id = match TrackId::from_id(spotify_song_id) {
         Ok(id) => id,
         Err(e) => {
@@ -281,11 +982,161 @@ pub async fn play_song<'e>(
             )));
         }
     };
-    if let Err(e) = client
-        .start_uris_playback(vec![PlayableId::Track(song_id)], None, None, None)
-        .await
+    if let Err(e) = call_spotify_once(&client.host_id, || {
+        client.start_uris_playback(
+            vec![PlayableId::Track(song_id.clone())],
+            device_id.as_deref(),
+            None,
+            None,
+        )
+    })
+    .await
+    {
+        return Err(describe(e, "could not play song"));
+    };
+    Ok(())
+}
+
+pub async fn pause_playback<'e>(
+    jam_id: &str,
+    transaction: &mut sqlx::Transaction<'e, sqlx::Postgres>,
+    credentials: SpotifyCredentials,
+) -> Result<(), Error> {
+    let device_id = get_playback_device(jam_id, &mut **transaction).await?;
+    let client = get_host_client(transaction, jam_id, credentials).await?;
+    if let Err(e) = call_spotify(&client.host_id, || {
+        client.pause_playback(device_id.as_deref())
+    })
+    .await
     {
-        return Err(Error::Spotify(format!("could not play song: {}", e)));
+        return Err(describe(e, "could not pause playback"));
     };
     Ok(())
 }
+
+pub async fn resume_playback<'e>(
+    jam_id: &str,
+    transaction: &mut sqlx::Transaction<'e, sqlx::Postgres>,
+    credentials: SpotifyCredentials,
+) -> Result<(), Error> {
+    let device_id = get_playback_device(jam_id, &mut **transaction).await?;
+    let client = get_host_client(transaction, jam_id, credentials).await?;
+    if let Err(e) = call_spotify(&client.host_id, || {
+        client.resume_playback(device_id.as_deref(), None)
+    })
+    .await
+    {
+        return Err(describe(e, "could not resume playback"));
+    };
+    Ok(())
+}
+
+pub async fn seek_playback<'e>(
+    position_ms: u32,
+    jam_id: &str,
+    transaction: &mut sqlx::Transaction<'e, sqlx::Postgres>,
+    credentials: SpotifyCredentials,
+) -> Result<(), Error> {
+    let device_id = get_playback_device(jam_id, &mut **transaction).await?;
+    let client = get_host_client(transaction, jam_id, credentials).await?;
+    let position = chrono::TimeDelta::milliseconds(position_ms as i64);
+    if let Err(e) = call_spotify(&client.host_id, || {
+        client.seek_track(position, device_id.as_deref())
+    })
+    .await
+    {
+        return Err(describe(e, "could not seek"));
+    };
+    Ok(())
+}
+
+pub async fn set_playback_volume<'e>(
+    percent: u8,
+    jam_id: &str,
+    transaction: &mut sqlx::Transaction<'e, sqlx::Postgres>,
+    credentials: SpotifyCredentials,
+) -> Result<(), Error> {
+    let device_id = get_playback_device(jam_id, &mut **transaction).await?;
+    let client = get_host_client(transaction, jam_id, credentials).await?;
+    if let Err(e) = call_spotify(&client.host_id, || {
+        client.volume(percent, device_id.as_deref())
+    })
+    .await
+    {
+        return Err(describe(e, "could not change the volume"));
+    };
+    Ok(())
+}
+
+/// adds the song to the queue of the player, so it plays right after the current one without a gap
+pub async fn add_song_to_player_queue<'e>(
+    spotify_song_id: &str,
+    jam_id: &str,
+    transaction: &mut sqlx::Transaction<'e, sqlx::Postgres>,
+    credentials: SpotifyCredentials,
+) -> Result<(), Error> {
+    let device_id = get_playback_device(jam_id, &mut **transaction).await?;
+    let client = get_host_client(transaction, jam_id, credentials).await?;
+    let song_id = match TrackId::from_id(spotify_song_id) {
+        Ok(id) => id,
+        Err(e) => {
+            return Err(Error::Spotify(format!(
+                "could not queue song, song id is not correct: {}",
+                e
+            )));
+        }
+    };
+    if let Err(e) = call_spotify_once(&client.host_id, || {
+        client.add_item_to_queue(PlayableId::Track(song_id.clone()), device_id.as_deref())
+    })
+    .await
+    {
+        return Err(describe(e, "could not queue song"));
+    };
+    Ok(())
+}
+
+/// plays the next song in the queue of the player
+pub async fn skip_to_next_in_player_queue<'e>(
+    jam_id: &str,
+    transaction: &mut sqlx::Transaction<'e, sqlx::Postgres>,
+    credentials: SpotifyCredentials,
+) -> Result<(), Error> {
+    let device_id = get_playback_device(jam_id, &mut **transaction).await?;
+    let client = get_host_client(transaction, jam_id, credentials).await?;
+    if let Err(e) =
+        call_spotify_once(&client.host_id, || client.next_track(device_id.as_deref())).await
+    {
+        return Err(describe(e, "could not skip to the next song"));
+    };
+    Ok(())
+}
+
+/// says what failed, an unavailable spotify already tells the user what is going on
+fn describe(error: Error, what: &str) -> Error {
+    match error {
+        Error::Spotify(e) => Error::Spotify(format!("{}: {}", what, e)),
+        e => e,
+    }
+}
+
+pub async fn get_song_from_spotify<'e>(
+    spotify_song_id: &str,
+    jam_id: &str,
+    transaction: &mut sqlx::Transaction<'e, sqlx::Postgres>,
+    credentials: SpotifyCredentials,
+) -> Result<Song, Error> {
+    let track = get_track_from_spotify(spotify_song_id, jam_id, transaction, credentials).await?;
+    Ok(track_to_song(track))
+}
+
+pub async fn get_track_from_spotify<'e>(
+    spotify_song_id: &str,
+    jam_id: &str,
+    transaction: &mut sqlx::Transaction<'e, sqlx::Postgres>,
+    credentials: SpotifyCredentials,
+) -> Result<FullTrack, Error> {
+    let client = get_host_client(transaction, jam_id, credentials).await?;
+    let track_id = TrackId::from_id(spotify_song_id)?;
+    call_spotify(&client.host_id, || client.track(track_id.clone(), None)).await
+}
@@ -0,0 +1,324 @@
+use crate::model::types::*;
+use rand::Rng;
+use rspotify::ClientError;
+use rspotify::http::HttpError;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{LazyLock, Mutex};
+use std::time::Duration;
+
+/// how often a call is tried before the error is given to the caller
+const MAX_ATTEMPTS: u32 = 3;
+/// the first wait between attempts, it doubles with every attempt and gets some jitter
+const BASE_BACKOFF: Duration = Duration::from_millis(250);
+/// a longer retry-after is not waited for, the caller would time out, the breaker opens instead
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(5);
+/// how many calls in a row have to fail before the breaker opens
+const FAILURE_THRESHOLD: u32 = 5;
+/// how long the breaker stays open if spotify didn't say how long to wait
+const OPEN_DURATION: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Default)]
+struct Breaker {
+    failures_in_a_row: u32,
+    /// unix timestamp in seconds, spotify is not called with this login before it
+    open_until: Option<i64>,
+}
+
+/// One breaker per spotify login, keyed by the id of the host or the user the token belongs to,
+/// all jams of a host share the token of the host and with it its rate limit, a rate limit of one
+/// host doesn't stop the jams of the other hosts, it lives outside of the app state because every spotify call needs it
+static BREAKERS: LazyLock<Mutex<HashMap<String, Breaker>>> = LazyLock::new(Default::default);
+
+enum Failure {
+    /// spotify asked to wait, with how long if it said so
+    RateLimited(Option<Duration>),
+    /// a timeout, a connection problem or a 5xx, might work on the next try
+    Transient,
+    /// a bad request, a missing song or a missing player, trying again won't help
+    Permanent,
+}
+
+fn classify(error: &ClientError) -> Failure {
+    let http = match error {
+        ClientError::Http(http) => http,
+        ClientError::Io(_) => return Failure::Transient,
+        _ => return Failure::Permanent,
+    };
+    match http.as_ref() {
+        HttpError::StatusCode(response) => match response.status().as_u16() {
+            429 => Failure::RateLimited(
+                response
+                    .headers()
+                    .get("retry-after")
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| value.trim().parse::<u64>().ok())
+                    .map(Duration::from_secs),
+            ),
+            500..=599 => Failure::Transient,
+            _ => Failure::Permanent,
+        },
+        HttpError::Client(e) if e.is_timeout() || e.is_connect() || e.is_request() => {
+            Failure::Transient
+        }
+        HttpError::Client(_) => Failure::Permanent,
+    }
+}
+
+/// unix timestamp in seconds until spotify is not called with the login of the host or user, none if it can be called,
+/// shown to everyone in the jams of the host, so they know why nothing is played
+pub fn spotify_unavailable_until(owner_id: &str) -> Option<i64> {
+    let breakers = match BREAKERS.lock() {
+        Ok(breakers) => breakers,
+        Err(e) => {
+            eprintln!(
+                "the spotify breakers are poisoned, could not read them: {}",
+                e
+            );
+            return None;
+        }
+    };
+    let now = chrono::Utc::now().timestamp();
+    breakers
+        .get(owner_id)
+        .and_then(|breaker| breaker.open_until)
+        .filter(|open_until| *open_until > now)
+}
+
+fn record_success(owner_id: &str) {
+    if let Ok(mut breakers) = BREAKERS.lock() {
+        breakers.remove(owner_id);
+    }
+}
+
+/// opens the breaker if spotify asked to wait or after too many failures in a row
+fn record_failure(owner_id: &str, wait: Option<Duration>) {
+    let mut breakers = match BREAKERS.lock() {
+        Ok(breakers) => breakers,
+        Err(e) => {
+            eprintln!(
+                "the spotify breakers are poisoned, could not update them: {}",
+                e
+            );
+            return;
+        }
+    };
+    let breaker = breakers.entry(owner_id.to_string()).or_default();
+    breaker.failures_in_a_row += 1;
+    let wait = match wait {
+        Some(wait) => wait,
+        None if breaker.failures_in_a_row >= FAILURE_THRESHOLD => OPEN_DURATION,
+        None => return,
+    };
+    breaker.open_until = Some(chrono::Utc::now().timestamp() + wait.as_secs().max(1) as i64);
+    println!(
+        "spotify is unavailable for the login of {} for {}s",
+        owner_id,
+        wait.as_secs()
+    );
+}
+
+fn unavailable(open_until: i64) -> Error {
+    let seconds = (open_until - chrono::Utc::now().timestamp()).max(1);
+    Error::SpotifyUnavailable(format!(
+        "Spotify is not reachable right now, try again in {} seconds",
+        seconds
+    ))
+}
+
+/// Calls spotify with the login of the host or user, waits for the retry-after of a rate limit and retries
+/// transient failures with a jittered backoff, fails right away while the breaker of the login is open,
+/// only for calls that can be sent twice without harm, like reads
+pub async fn call_spotify<T, F, Fut>(owner_id: &str, call: F) -> Result<T, Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, ClientError>>,
+{
+    call_with_retries(owner_id, true, call).await
+}
+
+/// Like `call_spotify`, but for calls like queueing or skipping a song, they are only sent again
+/// if spotify rejected them with a rate limit, after a timeout or a 5xx spotify might have acted on them already,
+/// so sending them again could queue the song twice or skip twice
+pub async fn call_spotify_once<T, F, Fut>(owner_id: &str, call: F) -> Result<T, Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, ClientError>>,
+{
+    call_with_retries(owner_id, false, call).await
+}
+
+async fn call_with_retries<T, F, Fut>(
+    owner_id: &str,
+    retry_transient: bool,
+    mut call: F,
+) -> Result<T, Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, ClientError>>,
+{
+    if let Some(open_until) = spotify_unavailable_until(owner_id) {
+        return Err(unavailable(open_until));
+    }
+
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        let error = match call().await {
+            Ok(value) => {
+                record_success(owner_id);
+                return Ok(value);
+            }
+            Err(e) => e,
+        };
+
+        let wait = match classify(&error) {
+            Failure::Permanent => return Err(error.into()),
+            Failure::RateLimited(Some(retry_after)) if retry_after > MAX_RETRY_AFTER => {
+                record_failure(owner_id, Some(retry_after));
+                return Err(spotify_unavailable_until(owner_id)
+                    .map(unavailable)
+                    .unwrap_or_else(|| error.into()));
+            }
+            Failure::RateLimited(Some(retry_after)) => retry_after,
+            Failure::Transient if !retry_transient => {
+                record_failure(owner_id, None);
+                return Err(spotify_unavailable_until(owner_id)
+                    .map(unavailable)
+                    .unwrap_or_else(|| error.into()));
+            }
+            Failure::RateLimited(None) | Failure::Transient => {
+                let backoff = BASE_BACKOFF * 2u32.pow(attempt - 1);
+                let jitter = rand::rng().random_range(0..=backoff.as_millis() as u64 / 2);
+                backoff + Duration::from_millis(jitter)
+            }
+        };
+
+        if attempt >= MAX_ATTEMPTS {
+            record_failure(owner_id, None);
+            return Err(spotify_unavailable_until(owner_id)
+                .map(unavailable)
+                .unwrap_or_else(|| error.into()));
+        }
+        eprintln!(
+            "spotify call failed for the login of {}, trying again in {}ms: {:?}",
+            owner_id,
+            wait.as_millis(),
+            error
+        );
+        tokio::time::sleep(wait).await;
+    }
+}
+
+/// the queue moves on even if spotify can't be reached, the occasional check of the player
+/// plays the current song once the breaker closes again
+pub fn play_later_if_unavailable(result: Result<(), Error>, jam_id: &str) -> Result<(), Error> {
+    match result {
+        Err(Error::SpotifyUnavailable(e)) => {
+            eprintln!("could not play for jam {}, playing it later: {}", jam_id, e);
+            Ok(())
+        }
+        result => result,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// the breakers are shared by all tests, so every test uses its own login
+    fn failures_in_a_row(owner_id: &str) -> u32 {
+        BREAKERS
+            .lock()
+            .unwrap()
+            .get(owner_id)
+            .map(|breaker| breaker.failures_in_a_row)
+            .unwrap_or(0)
+    }
+
+    #[test]
+    fn opens_after_too_many_failures_in_a_row() {
+        let owner_id = "breaker-threshold";
+        for _ in 1..FAILURE_THRESHOLD {
+            record_failure(owner_id, None);
+            assert_eq!(spotify_unavailable_until(owner_id), None);
+        }
+        record_failure(owner_id, None);
+        let now = chrono::Utc::now().timestamp();
+        let open_until = spotify_unavailable_until(owner_id).unwrap();
+        let open_for = OPEN_DURATION.as_secs() as i64;
+        assert!((now + open_for - 1..=now + open_for + 1).contains(&open_until));
+    }
+
+    #[test]
+    fn a_success_closes_it_again() {
+        let owner_id = "breaker-success";
+        for _ in 0..FAILURE_THRESHOLD {
+            record_failure(owner_id, None);
+        }
+        assert!(spotify_unavailable_until(owner_id).is_some());
+        record_success(owner_id);
+        assert_eq!(spotify_unavailable_until(owner_id), None);
+
+        // the count starts over, so the failures before the success don't add up with the new ones
+        for _ in 1..FAILURE_THRESHOLD {
+            record_failure(owner_id, None);
+        }
+        assert_eq!(spotify_unavailable_until(owner_id), None);
+    }
+
+    #[test]
+    fn opens_right_away_when_spotify_asks_to_wait() {
+        let owner_id = "breaker-retry-after";
+        record_failure(owner_id, Some(Duration::from_secs(60)));
+        let now = chrono::Utc::now().timestamp();
+        let open_until = spotify_unavailable_until(owner_id).unwrap();
+        assert!((now + 59..=now + 61).contains(&open_until));
+        // other logins are not affected
+        assert_eq!(spotify_unavailable_until("breaker-other-login"), None);
+    }
+
+    #[tokio::test]
+    async fn fails_fast_while_open() {
+        let owner_id = "breaker-fail-fast";
+        record_failure(owner_id, Some(Duration::from_secs(60)));
+        let calls = &AtomicU32::new(0);
+        let result = call_spotify(owner_id, || async move {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Ok::<_, ClientError>(())
+        })
+        .await;
+        assert!(matches!(result, Err(Error::SpotifyUnavailable(_))));
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn retries_transient_failures_and_counts_them_once() {
+        let owner_id = "breaker-transient";
+        let calls = &AtomicU32::new(0);
+        let result = call_spotify(owner_id, || async move {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err::<(), _>(ClientError::Io(std::io::Error::other("connection reset")))
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), MAX_ATTEMPTS);
+        assert_eq!(failures_in_a_row(owner_id), 1);
+        assert_eq!(spotify_unavailable_until(owner_id), None);
+    }
+
+    #[tokio::test]
+    async fn does_not_send_changes_twice() {
+        let owner_id = "breaker-once";
+        let calls = &AtomicU32::new(0);
+        let result = call_spotify_once(owner_id, || async move {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err::<(), _>(ClientError::Io(std::io::Error::other("timed out")))
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(failures_in_a_row(owner_id), 1);
+    }
+}
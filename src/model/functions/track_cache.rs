@@ -0,0 +1,92 @@
+use crate::model::types::*;
+
+/// how long the metadata of a track is trusted, popularity and previews change over time
+const TRACK_CACHE_TTL_MS: i64 = 7 * 24 * 60 * 60 * 1000;
+
+/// the song from the cache, or from spotify if it isn't cached or the cached one is too old
+pub async fn get_song(
+    spotify_id: &str,
+    jam_id: &str,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    credentials: SpotifyCredentials,
+) -> Result<Song, Error> {
+    if let Some(song) = get_cached_song(spotify_id, &mut **transaction).await? {
+        return Ok(song);
+    }
+
+    let song = super::track_to_song(
+        super::get_track_from_spotify(spotify_id, jam_id, transaction, credentials).await?,
+    );
+    cache_songs(std::slice::from_ref(&song), transaction).await?;
+    Ok(song)
+}
+
+async fn get_cached_song<'e>(
+    spotify_id: &str,
+    executor: impl sqlx::PgExecutor<'e>,
+) -> Result<Option<Song>, Error> {
+    let track = sqlx::query!(
+        "SELECT spotify_id, name, album, duration, artists, image_url, preview_url, release_year, explicit, popularity
+        FROM track_cache WHERE spotify_id=$1 AND cached_at > $2",
+        spotify_id,
+        chrono::Utc::now().timestamp_millis() - TRACK_CACHE_TTL_MS
+    )
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(track.map(|track| Song {
+        id: None,
+        spotify_id: track.spotify_id,
+        user_id: None,
+        name: track.name,
+        artists: track.artists,
+        album: track.album,
+        duration: track.duration as u32,
+        image_url: track.image_url,
+        votes: Vote {
+            votes: 0,
+            have_you_voted: None,
+        },
+        details: SongDetails {
+            preview_url: track.preview_url,
+            release_year: track.release_year.map(|year| year as u16),
+            explicit: track.explicit,
+            popularity: track.popularity.map(|popularity| popularity as u8),
+        },
+    }))
+}
+
+/// saves songs that came from spotify, so adding one of them later doesn't ask spotify again
+pub async fn cache_songs(
+    songs: &[Song],
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+) -> Result<(), Error> {
+    let now = chrono::Utc::now().timestamp_millis();
+    for song in songs {
+        sqlx::query!(
+            "INSERT INTO track_cache
+                (spotify_id, name, album, duration, artists, image_url, preview_url, release_year, explicit, popularity, cached_at)
+            VALUES
+                ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            ON CONFLICT (spotify_id) DO UPDATE
+            SET name = EXCLUDED.name, album = EXCLUDED.album, duration = EXCLUDED.duration,
+                artists = EXCLUDED.artists, image_url = EXCLUDED.image_url, preview_url = EXCLUDED.preview_url,
+                release_year = EXCLUDED.release_year, explicit = EXCLUDED.explicit,
+                popularity = EXCLUDED.popularity, cached_at = EXCLUDED.cached_at",
+            song.spotify_id,
+            song.name,
+            song.album,
+            song.duration as i32,
+            &song.artists,
+            song.image_url,
+            song.details.preview_url,
+            song.details.release_year.map(|year| year as i32),
+            song.details.explicit,
+            song.details.popularity.map(|popularity| popularity as i32),
+            now
+        )
+        .execute(&mut **transaction)
+        .await?;
+    }
+    Ok(())
+}
@@ -1,25 +1,124 @@
 use sqlx::Transaction;
 
+use crate::model::functions::dose_jam_exist;
 use crate::model::types::*;
+use std::collections::HashMap;
 
 ///only the jam is is used from the id
 pub async fn get_users<'e>(
-    executor: impl sqlx::PgExecutor<'e>,
+    transaction: &mut Transaction<'e, sqlx::Postgres>,
     id: &Id,
 ) -> Result<Vec<User>, sqlx::Error> {
-    sqlx::query_as!(User, "SELECT * FROM users WHERE jam_id=$1", id.jam_id())
-        .fetch_all(executor)
-        .await
-        .map(|users| {
-            users
-                .into_iter()
-                .filter(|user| user.id.trim() != id.jam_id())
-                .collect()
+    let co_hosts = sqlx::query!(
+        "SELECT * FROM co_hosts WHERE user_id IN (SELECT id FROM users WHERE jam_id=$1)",
+        id.jam_id()
+    )
+    .fetch_all(&mut **transaction)
+    .await?
+    .into_iter()
+    .map(|co_host| {
+        (
+            co_host.user_id,
+            Permissions {
+                kick: co_host.kick,
+                remove_songs: co_host.remove_songs,
+                skip: co_host.skip,
+                change_settings: co_host.change_settings,
+            },
+        )
+    })
+    .collect::<HashMap<String, Permissions>>();
+
+    let users = sqlx::query!("SELECT * FROM users WHERE jam_id=$1", id.jam_id())
+        .fetch_all(&mut **transaction)
+        .await?
+        .into_iter()
+        .filter(|user| user.id.trim() != id.jam_id())
+        .map(|user| User {
+            permissions: co_hosts.get(&user.id).copied().unwrap_or_default(),
+            id: user.id,
+            jam_id: user.jam_id,
+            name: user.name,
         })
+        .collect();
+
+    Ok(users)
+}
+
+/// returns the default (no) permissions if the user is not a co-host
+pub async fn get_permissions<'e>(
+    user_id: &str,
+    executor: impl sqlx::PgExecutor<'e>,
+) -> Result<Permissions, Error> {
+    let permissions = sqlx::query!(
+        "SELECT kick, remove_songs, skip, change_settings FROM co_hosts WHERE user_id=$1",
+        user_id
+    )
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(permissions
+        .map(|p| Permissions {
+            kick: p.kick,
+            remove_songs: p.remove_songs,
+            skip: p.skip,
+            change_settings: p.change_settings,
+        })
+        .unwrap_or_default())
+}
+
+/// setting the default (no) permissions demotes the co-host to a normal user
+pub async fn set_permissions<'e>(
+    user_id: &str,
+    jam_id: &str,
+    permissions: Permissions,
+    transaction: &mut Transaction<'e, sqlx::Postgres>,
+) -> Result<real_time::Changed, Error> {
+    let is_in_jam = sqlx::query!(
+        "SELECT EXISTS(SELECT 1 FROM users WHERE id=$1 AND jam_id=$2 AND id<>$2)",
+        user_id,
+        jam_id
+    )
+    .fetch_one(&mut **transaction)
+    .await?
+    .exists
+    .unwrap_or(false);
+
+    if !is_in_jam {
+        return Err(Error::DoesNotExist(format!(
+            "user with id: {} is not in jam: {}, could not set permissions",
+            user_id, jam_id
+        )));
+    }
+
+    if permissions == Permissions::default() {
+        sqlx::query!("DELETE FROM co_hosts WHERE user_id=$1;", user_id)
+            .execute(&mut **transaction)
+            .await?;
+    } else {
+        sqlx::query!(
+            "INSERT INTO co_hosts (user_id, kick, remove_songs, skip, change_settings)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (user_id) DO UPDATE
+            SET kick = $2, remove_songs = $3, skip = $4, change_settings = $5",
+            user_id,
+            permissions.kick,
+            permissions.remove_songs,
+            permissions.skip,
+            permissions.change_settings
+        )
+        .execute(&mut **transaction)
+        .await?;
+    }
+
+    Ok(real_time::Changed::new().users())
 }
 
+/// a host can have several jams, so for hosts the jam has to be given,
+/// unless the host only has one jam, for users and spectators the jam is ignored
 pub async fn check_id_type<'e>(
     id: &str,
+    jam_id: Option<&str>,
     transaction: &mut Transaction<'e, sqlx::Postgres>,
 ) -> Result<Id, Error> {
     // Check if the ID exists in the hosts table
@@ -28,16 +127,54 @@ pub async fn check_id_type<'e>(
         .await?;
 
     if host_check.exists.unwrap_or(false) {
-        let jam_id = sqlx::query!("SELECT id FROM jams WHERE host_id = $1", id)
-            .fetch_one(&mut **transaction)
+        let mut jam_ids = sqlx::query!("SELECT id FROM jams WHERE host_id = $1", id)
+            .fetch_all(&mut **transaction)
             .await?
-            .id;
+            .into_iter()
+            .map(|jam| jam.id)
+            .collect::<Vec<_>>();
+
+        let jam_id = match jam_id {
+            Some(jam_id) => match jam_ids.into_iter().find(|id| id == &jam_id.to_lowercase()) {
+                Some(jam_id) => jam_id,
+                None => {
+                    return Err(Error::DoesNotExist(format!(
+                        "host with id: {} does not have a jam with id: {}",
+                        id, jam_id
+                    )));
+                }
+            },
+            None => match jam_ids.len() {
+                1 => jam_ids.remove(0),
+                0 => {
+                    return Err(Error::DoesNotExist(format!(
+                        "host with id: {} does not have a jam",
+                        id
+                    )));
+                }
+                _ => {
+                    return Err(Error::InvalidRequest(format!(
+                        "host with id: {} has multiple jams, the id of the jam has to be given",
+                        id
+                    )));
+                }
+            },
+        };
         return Ok(Id {
             id: IdType::Host(id.to_string()),
             jam_id,
         });
     }
 
+    // A bare jam code identifies a spectator, this has to be checked before the users,
+    // because the jam itself has a user with the same id for the current song
+    if dose_jam_exist(id, &mut **transaction).await? {
+        return Ok(Id {
+            id: IdType::General,
+            jam_id: id.to_lowercase(),
+        });
+    }
+
     let user_check = sqlx::query!("SELECT EXISTS(SELECT 1 FROM users WHERE id = $1)", id)
         .fetch_one(&mut **transaction)
         .await?;
@@ -61,16 +198,21 @@ pub async fn check_id_type<'e>(
 
 pub async fn kick_user<'e>(
     user_id: &str,
+    jam_id: &str,
     executor: impl sqlx::PgExecutor<'e>,
 ) -> Result<real_time::Changed, Error> {
-    let res = sqlx::query!("DELETE FROM users WHERE id=$1;", user_id)
-        .execute(executor)
-        .await?;
+    let res = sqlx::query!(
+        "DELETE FROM users WHERE id=$1 AND jam_id=$2 AND id<>$2;",
+        user_id,
+        jam_id
+    )
+    .execute(executor)
+    .await?;
 
     if res.rows_affected() == 0 {
         return Err(Error::DoesNotExist(format!(
-            "user with id: {} does not exist, could not kick",
-            user_id
+            "user with id: {} does not exist in jam: {}, could not kick",
+            user_id, jam_id
         )));
     }
 
@@ -0,0 +1,128 @@
+use crate::model::types::*;
+
+/// saves the token of a guest who logged in with spotify, logging in again replaces the old token,
+/// returns the id of the jam of the user
+pub async fn connect_user_spotify(
+    code: &str,
+    user_id: &str,
+    spotify_credentials: &SpotifyCredentials,
+    reqwest_client: &reqwest::Client,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    redirect_uri: &str,
+) -> Result<String, Error> {
+    let jam_id = match sqlx::query!("SELECT jam_id FROM users WHERE id=$1", user_id)
+        .fetch_optional(&mut **transaction)
+        .await?
+    {
+        Some(user) => user.jam_id,
+        None => {
+            return Err(Error::DoesNotExist(format!(
+                "user with id: {} does not exist, could not log in with spotify",
+                user_id
+            )));
+        }
+    };
+
+    let token =
+        super::request_spotify_token(code, redirect_uri, spotify_credentials, reqwest_client)
+            .await?;
+    let expires_at = chrono::Utc::now().timestamp() + token.expires_in;
+
+    sqlx::query!(
+        "INSERT INTO user_access_tokens
+            (id, user_id, access_token, expires_at, scope, refresh_token)
+        VALUES
+            ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT (user_id) DO UPDATE
+        SET access_token = EXCLUDED.access_token, expires_at = EXCLUDED.expires_at,
+            scope = EXCLUDED.scope, refresh_token = EXCLUDED.refresh_token",
+        cuid2::create_id(),
+        user_id,
+        token.access_token,
+        expires_at,
+        token.scope,
+        token.refresh_token
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(jam_id)
+}
+
+pub async fn disconnect_user_spotify<'e>(
+    user_id: &str,
+    executor: impl sqlx::PgExecutor<'e>,
+) -> Result<(), Error> {
+    sqlx::query!("DELETE FROM user_access_tokens WHERE user_id=$1", user_id)
+        .execute(executor)
+        .await?;
+    Ok(())
+}
+
+pub async fn is_user_spotify_connected<'e>(
+    user_id: &str,
+    executor: impl sqlx::PgExecutor<'e>,
+) -> Result<bool, Error> {
+    let connected = sqlx::query!(
+        "SELECT EXISTS(SELECT 1 FROM user_access_tokens WHERE user_id=$1)",
+        user_id
+    )
+    .fetch_one(executor)
+    .await?;
+    Ok(connected.exists.unwrap_or(false))
+}
+
+/// none if the user didn't log in with spotify or their login expired,
+/// this also refreshes the token shortly before it expires, with the row locked like the token of the host
+pub async fn get_user_access_token(
+    user_id: &str,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    credentials: SpotifyCredentials,
+) -> Result<Option<rspotify::Token>, Error> {
+    let token = match sqlx::query!(
+        "SELECT access_token, expires_at, scope, refresh_token FROM user_access_tokens WHERE user_id=$1 FOR UPDATE",
+        user_id
+    )
+    .fetch_optional(&mut **transaction)
+    .await?
+    {
+        Some(token) => token,
+        None => return Ok(None),
+    };
+
+    let now = chrono::Utc::now().timestamp();
+    let expires_in = chrono::TimeDelta::new(token.expires_at - now, 0).unwrap_or_default();
+    let token = rspotify::Token {
+        access_token: token.access_token,
+        expires_in,
+        expires_at: chrono::DateTime::from_timestamp(token.expires_at, 0),
+        refresh_token: Some(token.refresh_token),
+        scopes: rspotify::scopes!(token.scope),
+    };
+    if !super::needs_refresh(&token) {
+        return Ok(Some(token));
+    }
+
+    let new_token = match super::refresh_token(token, credentials).await {
+        Ok(new_token) => new_token,
+        // the user revoked the access, so they are logged out and can log in again
+        Err(Error::SpotifyLoginExpired(e)) => {
+            eprintln!("the spotify login of user {} expired: {}", user_id, e);
+            disconnect_user_spotify(user_id, &mut **transaction).await?;
+            return Ok(None);
+        }
+        Err(e) => return Err(e),
+    };
+    sqlx::query!(
+        "UPDATE user_access_tokens SET access_token=$1, expires_at=$2, scope=$3, refresh_token=COALESCE($4, refresh_token) WHERE user_id=$5",
+        new_token.access_token,
+        now + new_token.expires_in.num_seconds(),
+        new_token.scopes.clone().into_iter().collect::<Vec<_>>().join(" "),
+        new_token.refresh_token,
+        user_id
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(Some(new_token))
+}
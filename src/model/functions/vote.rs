@@ -4,8 +4,14 @@ use std::collections::{HashMap, HashSet};
 pub async fn add_vote<'e>(
     song_id: &str,
     user_id: &str,
-    executor: impl sqlx::PgExecutor<'e>,
+    transaction: &mut sqlx::Transaction<'e, sqlx::Postgres>,
 ) -> Result<real_time::Changed, Error> {
+    if is_song_queued(song_id, &mut **transaction).await? {
+        return Err(Error::Forbidden(
+            "this song plays next, its votes can't change anymore".to_string(),
+        ));
+    }
+
     let result = sqlx::query!(
         "INSERT INTO votes (song_id, user_id, id)
          VALUES ($1, $2, $3)
@@ -14,7 +20,7 @@ pub async fn add_vote<'e>(
         user_id,
         format!("{}{}", song_id, user_id)
     )
-    .execute(executor)
+    .execute(&mut **transaction)
     .await?;
 
     if result.rows_affected() == 0 {
@@ -30,14 +36,20 @@ pub async fn add_vote<'e>(
 pub async fn remove_vote<'e>(
     song_id: &str,
     user_id: &str,
-    executor: impl sqlx::PgExecutor<'e>,
+    transaction: &mut sqlx::Transaction<'e, sqlx::Postgres>,
 ) -> Result<real_time::Changed, Error> {
+    if is_song_queued(song_id, &mut **transaction).await? {
+        return Err(Error::Forbidden(
+            "this song plays next, its votes can't change anymore".to_string(),
+        ));
+    }
+
     let result = sqlx::query!(
         "DELETE FROM votes WHERE song_id=$1 AND user_id=$2;",
         song_id,
         user_id
     )
-    .execute(executor)
+    .execute(&mut **transaction)
     .await?;
 
     if result.rows_affected() == 0 {
@@ -63,7 +75,7 @@ pub async fn get_votes<'e>(
     // Fetch the vote counts for all songs in the current jam
     let vec = sqlx::query_as!(
         VotesDb,
-        "SELECT s.id AS song_id, COUNT(v.id) AS votes_nr
+        "SELECT s.id AS song_id, COUNT(v.id) + s.boosts AS votes_nr
         FROM songs s
         JOIN users u ON s.user_id = u.id
         LEFT JOIN votes v ON s.id = v.song_id
@@ -1,3 +1,4 @@
+use crate::model::functions::{JamClocks, SearchCache};
 use crate::model::types::*;
 use axum::extract::FromRef;
 
@@ -8,6 +9,10 @@ pub struct AppState {
     pub spotify_credentials: SpotifyCredentials,
     pub leptos_options: leptos::prelude::LeptosOptions,
     pub site_url: String,
+    /// the timers that go to the next song when the current one ends
+    pub jam_clocks: JamClocks,
+    /// the results of recent searches, shared by all jams
+    pub search_cache: SearchCache,
 }
 
 impl AppState {
@@ -17,16 +22,21 @@ impl AppState {
         spotify_secret: String,
         db_url: String,
         site_url: String,
+        spotify_urls: Option<SpotifyUrls>,
     ) -> Result<Self, Error> {
         let reqwest_client = reqwest::Client::new();
         println!("Connecting to database...",);
 
         let db = Db::new(db_url).await?;
         println!("Connected to database...");
+        crate::model::functions::set_token_pool(db.pool.clone());
 
+        let spotify_urls = spotify_urls.unwrap_or_default();
         let spotify_credentials = SpotifyCredentials {
             id: spotify_id,
             secret: spotify_secret,
+            api_url: spotify_urls.api_url,
+            accounts_url: spotify_urls.accounts_url,
         };
 
         Ok(Self {
@@ -35,6 +45,35 @@ impl AppState {
             spotify_credentials,
             leptos_options,
             site_url,
+            jam_clocks: JamClocks::new(),
+            search_cache: SearchCache::new(),
         })
     }
 }
+
+/// The urls of spotify, set to a stand-in server for development and tests
+#[derive(Clone, Debug)]
+pub struct SpotifyUrls {
+    pub api_url: String,
+    pub accounts_url: String,
+}
+
+impl SpotifyUrls {
+    /// the urls of the mock spotify server, started with `cargo run --bin mock_spotify --features ssr`
+    pub fn mock(base_url: &str) -> Self {
+        let base_url = base_url.trim_end_matches('/');
+        Self {
+            api_url: format!("{}/v1/", base_url),
+            accounts_url: format!("{}/", base_url),
+        }
+    }
+}
+
+impl Default for SpotifyUrls {
+    fn default() -> Self {
+        Self {
+            api_url: SPOTIFY_API_URL.to_string(),
+            accounts_url: SPOTIFY_ACCOUNTS_URL.to_string(),
+        }
+    }
+}
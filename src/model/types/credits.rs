@@ -0,0 +1,33 @@
+use serde::{Deserialize, Serialize};
+
+/// The balance of a user in a jam with the credits song limit,
+/// credits are earned over time and when a song the user voted for is played,
+/// and spent to add songs or to boost songs in the queue
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Credits {
+    pub balance: u32,
+    /// unix timestamp in milliseconds, when the next credit is earned, none if the balance is full
+    pub next_credit_at: Option<i64>,
+}
+
+impl Credits {
+    pub const STARTING: u32 = 3;
+    pub const MAX: u32 = 10;
+    /// how long it takes to earn a credit, in milliseconds
+    pub const INTERVAL: i64 = 5 * 60 * 1000;
+    pub const SONG_COST: u32 = 2;
+    pub const BOOST_COST: u32 = 1;
+    /// what a user gets when a song they voted for is played
+    pub const VOTE_REWARD: u32 = 1;
+
+    /// the balance at the given time, counting the credits earned since this was sent
+    pub fn balance_at(&self, now: i64) -> u32 {
+        match self.next_credit_at {
+            Some(next_credit_at) if now >= next_credit_at => {
+                let earned = 1 + (now - next_credit_at) / Self::INTERVAL;
+                (self.balance as i64 + earned).min(Self::MAX as i64) as u32
+            }
+            _ => self.balance,
+        }
+    }
+}
@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+
+/// A spotify connect device of the host, like a speaker, a tv or the desktop app
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PlaybackDevice {
+    pub id: String,
+    pub name: String,
+    /// like speaker, computer or smartphone
+    pub kind: String,
+    pub is_active: bool,
+    pub volume_percent: Option<u32>,
+}
+
+/// The devices the host can choose from and the one the jam plays on
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct PlaybackDevices {
+    pub devices: Vec<PlaybackDevice>,
+    /// none if the jam plays in the browser of the host
+    pub selected: Option<String>,
+}
+
+/// What the player of the host is doing right now, as spotify reports it
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PlayerState {
+    pub song: super::Song,
+    pub is_playing: bool,
+    /// milliseconds into the song
+    pub progress: u32,
+}
@@ -6,6 +6,10 @@ pub enum Error {
     Database(String),
     #[error("Error from spotify: {0}")]
     Spotify(String),
+    #[error("Spotify is not reachable right now: {0}")]
+    SpotifyUnavailable(String),
+    #[error("The spotify login expired: {0}")]
+    SpotifyLoginExpired(String),
     #[error("Error from serde decode: {0}")]
     Decode(String),
     #[error("Error from serde encode: {0}")]
@@ -18,14 +22,18 @@ pub enum Error {
     FileSystem(String),
     #[error("Your request is incorrect: {0}")]
     InvalidRequest(String),
-    #[error("The host cant create another jam cuz he is in one already, jam id: {jam_id}")]
-    HostAlreadyInJam { jam_id: String },
     #[error("This user has reached |insert pronoun here| song limit")]
     UserHasTooTheMaxSongAmount,
     #[error("A env was not found: {0}")]
     EnvNotFound(String),
     #[error("song already in jam")]
     SongAlreadyInJam,
+    #[error("another version of this song is already in the jam: {0}")]
+    DuplicateSong(String),
+    #[error("this song was played recently: {0}")]
+    SongRecentlyPlayed(String),
+    #[error("not enough credits: {0}")]
+    NotEnoughCredits(String),
     #[error("A entry was not found: {0}")]
     DoesNotExist(String),
 }
@@ -39,12 +47,16 @@ impl Error {
             Error::WebSocket(_) => 4500,
             Error::Forbidden(_) => 4403,
             Error::Spotify(_) => 4500,
+            Error::SpotifyUnavailable(_) => 4503,
+            Error::SpotifyLoginExpired(_) => 4401,
             Error::FileSystem(_) => 4500,
             Error::InvalidRequest(_) => 4400,
-            Error::HostAlreadyInJam { .. } => 4400,
             Error::UserHasTooTheMaxSongAmount => 4400,
             Error::EnvNotFound(_) => 4500,
             Error::SongAlreadyInJam => 4400,
+            Error::DuplicateSong(_) => 4400,
+            Error::SongRecentlyPlayed(_) => 4400,
+            Error::NotEnoughCredits(_) => 4400,
             Error::DoesNotExist(_) => 4404,
         }
     }
@@ -59,14 +71,16 @@ impl From<Error> for String {
             Error::WebSocket(s) => s,
             Error::Forbidden(s) => s,
             Error::Spotify(s) => s,
+            Error::SpotifyUnavailable(s) => s,
+            Error::SpotifyLoginExpired(s) => s,
             Error::FileSystem(s) => s,
             Error::InvalidRequest(s) => s,
-            Error::HostAlreadyInJam { jam_id } => {
-                format!("Host is already in jam with id: {}", jam_id)
-            }
             Error::UserHasTooTheMaxSongAmount => "User has too the max song amount".to_string(),
             Error::EnvNotFound(s) => s,
             Error::SongAlreadyInJam => "Song already in jam".to_string(),
+            Error::DuplicateSong(s) => s,
+            Error::SongRecentlyPlayed(s) => s,
+            Error::NotEnoughCredits(s) => s,
             Error::DoesNotExist(s) => s,
         }
     }
@@ -0,0 +1,15 @@
+use super::Song;
+use serde::{Deserialize, Serialize};
+
+/// The list songs are taken from when nobody queued a song, only the host gets it
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Fallback {
+    /// in the order they are played in, the id of a track is its id in the fallback list
+    pub tracks: Vec<Song>,
+    /// if true, the tracks are played in a random order
+    pub shuffle: bool,
+    /// the spotify playlist the tracks were imported from
+    pub playlist_id: Option<String>,
+    /// the spotify ids of the tracks that were already played in the jam, they are skipped
+    pub played: Vec<String>,
+}
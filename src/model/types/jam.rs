@@ -5,5 +5,221 @@ pub struct Jam {
     pub id: String,
     pub name: String,
     pub max_song_count: u8,
+    pub queue_strategy: QueueStrategy,
+    /// if false, songs marked as explicit by spotify can't be added
+    pub allow_explicit: bool,
+    pub state: JamState,
+    /// unix timestamp in seconds, when the jam starts playing, only set for scheduled jams
+    pub starts_at: Option<i64>,
+    /// how many minutes a played song can't be added again, 0 allows it right away
+    pub no_repeat_minutes: u32,
+    pub song_limit: SongLimit,
+    pub external_changes: ExternalChanges,
+    /// the volume of the player of the host, from 0 to 100
+    pub volume_percent: u8,
+    /// unix timestamp in seconds, until then spotify can't be reached, because of a rate limit or an outage,
+    /// the queue still works, but nothing new is played
+    pub spotify_unavailable_until: Option<i64>,
 }
 
+/// The lifecycle of a jam, a jam that ended is moved to the past jams, so there is no ended state
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum JamState {
+    /// guests can join, but can't add songs yet
+    Scheduled,
+    /// guests can join and add songs, nothing is played yet
+    Open,
+    #[default]
+    Playing,
+    Paused,
+}
+
+impl JamState {
+    /// the value stored in the database
+    pub fn as_db_str(&self) -> &'static str {
+        match self {
+            Self::Scheduled => "scheduled",
+            Self::Open => "open",
+            Self::Playing => "playing",
+            Self::Paused => "paused",
+        }
+    }
+
+    /// unknown values fall back to the default
+    pub fn from_db_str(s: &str) -> Self {
+        match s {
+            "scheduled" => Self::Scheduled,
+            "open" => Self::Open,
+            "paused" => Self::Paused,
+            _ => Self::Playing,
+        }
+    }
+
+    /// a jam can only move forward, except for pausing and resuming
+    pub fn can_transition_to(&self, next: Self) -> bool {
+        matches!(
+            (self, next),
+            (Self::Scheduled, Self::Open)
+                | (Self::Scheduled, Self::Playing)
+                | (Self::Open, Self::Playing)
+                | (Self::Playing, Self::Paused)
+                | (Self::Paused, Self::Playing)
+        )
+    }
+
+    /// true before the first song was played
+    pub fn has_not_started(&self) -> bool {
+        matches!(self, Self::Scheduled | Self::Open)
+    }
+
+    pub fn accepts_songs(&self) -> bool {
+        !matches!(self, Self::Scheduled)
+    }
+}
+
+/// How the next song is picked from the queue
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum QueueStrategy {
+    /// the song with the most votes, ties are broken randomly
+    #[default]
+    Votes,
+    /// the song that was added first, votes are ignored
+    FirstCome,
+    /// a random song from the queue
+    Shuffle,
+}
+
+impl QueueStrategy {
+    pub fn all() -> [Self; 3] {
+        [Self::Votes, Self::FirstCome, Self::Shuffle]
+    }
+
+    /// the value stored in the database
+    pub fn as_db_str(&self) -> &'static str {
+        match self {
+            Self::Votes => "votes",
+            Self::FirstCome => "first_come",
+            Self::Shuffle => "shuffle",
+        }
+    }
+
+    /// unknown values fall back to the default
+    pub fn from_db_str(s: &str) -> Self {
+        match s {
+            "first_come" => Self::FirstCome,
+            "shuffle" => Self::Shuffle,
+            _ => Self::Votes,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Votes => "Most votes",
+            Self::FirstCome => "First come, first served",
+            Self::Shuffle => "Shuffle",
+        }
+    }
+}
+
+/// How users are limited in adding songs
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SongLimit {
+    /// every user can have up to max song count songs in the queue
+    #[default]
+    MaxSongs,
+    /// users spend credits to add and boost songs, see [`Credits`](super::Credits)
+    Credits,
+}
+
+impl SongLimit {
+    pub fn all() -> [Self; 2] {
+        [Self::MaxSongs, Self::Credits]
+    }
+
+    /// the value stored in the database
+    pub fn as_db_str(&self) -> &'static str {
+        match self {
+            Self::MaxSongs => "max_songs",
+            Self::Credits => "credits",
+        }
+    }
+
+    /// unknown values fall back to the default
+    pub fn from_db_str(s: &str) -> Self {
+        match s {
+            "credits" => Self::Credits,
+            _ => Self::MaxSongs,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::MaxSongs => "Max songs per user",
+            Self::Credits => "Credits",
+        }
+    }
+}
+
+/// What happens when the host skips, pauses or picks a song in the spotify app instead of the jam
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExternalChanges {
+    /// the jam follows the player, a skipped or picked song becomes the current song and is kept in the history
+    #[default]
+    Adopt,
+    /// the jam takes the player back and plays its own current song again
+    Override,
+}
+
+impl ExternalChanges {
+    pub fn all() -> [Self; 2] {
+        [Self::Adopt, Self::Override]
+    }
+
+    /// the value stored in the database
+    pub fn as_db_str(&self) -> &'static str {
+        match self {
+            Self::Adopt => "adopt",
+            Self::Override => "override",
+        }
+    }
+
+    /// unknown values fall back to the default
+    pub fn from_db_str(s: &str) -> Self {
+        match s {
+            "override" => Self::Override,
+            _ => Self::Adopt,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Adopt => "Follow the Spotify app",
+            Self::Override => "Keep playing the jam",
+        }
+    }
+}
+
+/// Saved settings a host can start a new jam from, a jam is also created from these settings
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct JamTemplate {
+    pub id: String,
+    pub name: String,
+    pub max_song_count: u8,
+    pub queue_strategy: QueueStrategy,
+    pub allow_explicit: bool,
+    pub song_limit: SongLimit,
+    pub no_repeat_minutes: u32,
+    /// if the fallback list of the jam is played shuffled
+    pub fallback_shuffle: bool,
+    pub external_changes: ExternalChanges,
+}
+
+/// A jam that already ended, kept so the host can look back at it
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PastJam {
+    pub id: String,
+    pub jam_id: String,
+    pub name: String,
+    /// formatted date, the jam ended on
+    pub ended_on: String,
+}
@@ -25,6 +25,12 @@ pub use song::*;
 mod jam;
 pub use jam::*;
 
+mod fallback;
+pub use fallback::*;
+
+mod credits;
+pub use credits::*;
+
 mod user;
 pub use user::*;
 
@@ -32,4 +38,7 @@ mod spotify_credentials;
 pub use spotify_credentials::*;
 
 mod id;
-pub use id::*;
\ No newline at end of file
+pub use id::*;
+
+mod device;
+pub use device::*;
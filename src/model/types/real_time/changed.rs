@@ -11,6 +11,16 @@ pub struct Changed {
     pub ended: bool,
     pub position: bool,
     pub current_song: bool,
+    /// the name, the song limit or the state of the jam changed
+    pub settings: bool,
+    /// the host offered the jam to a user, the offer was withdrawn, or the jam changed hands
+    pub handover: bool,
+    /// the fallback list of the jam changed, only the host gets it
+    pub fallback: bool,
+    /// the balance of users changed, every user gets their own
+    pub credits: bool,
+    /// the host changed the volume, only the volume is sent and not the whole jam, like for the settings
+    pub volume: bool,
 }
 
 impl Changed {
@@ -23,11 +33,26 @@ impl Changed {
             ended: false,
             position: false,
             current_song: false,
+            settings: false,
+            handover: false,
+            fallback: false,
+            credits: false,
+            volume: false,
        }
     }
 
     pub fn has_changed(&self) -> bool {
-        self.users || self.songs || self.votes || self.ended || self.position || self.current_song
+        self.users
+            || self.songs
+            || self.votes
+            || self.ended
+            || self.position
+            || self.current_song
+            || self.settings
+            || self.handover
+            || self.fallback
+            || self.credits
+            || self.volume
     }
 
     /// This function merges the current instance with another instance of the struct
@@ -39,6 +64,11 @@ impl Changed {
             ended: self.ended || other.ended,
             position: self.position || other.position,
             current_song: self.current_song || other.current_song,
+            settings: self.settings || other.settings,
+            handover: self.handover || other.handover,
+            fallback: self.fallback || other.fallback,
+            credits: self.credits || other.credits,
+            volume: self.volume || other.volume,
         }
     }
 
@@ -87,6 +117,41 @@ impl Changed {
         }
     }
 
+    pub fn settings(self) -> Self {
+        Self {
+            settings: true,
+            ..self
+        }
+    }
+
+    pub fn handover(self) -> Self {
+        Self {
+            handover: true,
+            ..self
+        }
+    }
+
+    pub fn fallback(self) -> Self {
+        Self {
+            fallback: true,
+            ..self
+        }
+    }
+
+    pub fn credits(self) -> Self {
+        Self {
+            credits: true,
+            ..self
+        }
+    }
+
+    pub fn volume(self) -> Self {
+        Self {
+            volume: true,
+            ..self
+        }
+    }
+
 
     /// This function sets all the fields to true except for ended
     pub fn all() -> Self {
@@ -97,6 +162,11 @@ impl Changed {
             ended: false,
             position: true,
             current_song: true,
+            settings: true,
+            handover: true,
+            fallback: true,
+            credits: true,
+            volume: true,
         }
     }
 }
\ No newline at end of file
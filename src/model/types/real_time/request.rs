@@ -1,12 +1,121 @@
+use super::super::{ExternalChanges, JamState, Permissions, SongLimit};
+use super::{LibrarySource, SearchCollection, SearchKind, SearchQuery};
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum Request {
-    KickUser { user_id: String },
-    AddSong { song_id: String },
-    RemoveSong { song_id: String },
-    AddVote { song_id: String },
-    RemoveVote { song_id: String },
-    Search { query: String, id: String },
-    Position { percentage: f32 },
+    KickUser {
+        user_id: String,
+    },
+    AddSong {
+        song_id: String,
+    },
+    RemoveSong {
+        song_id: String,
+    },
+    AddVote {
+        song_id: String,
+    },
+    RemoveVote {
+        song_id: String,
+    },
+    /// costs credits, only works in jams with credits
+    BoostSong {
+        song_id: String,
+    },
+    /// the first page of results, older clients only search for songs
+    Search {
+        query: String,
+        id: String,
+        #[serde(default)]
+        kind: SearchKind,
+    },
+    /// the first page of songs of an album, artist or playlist from the search
+    OpenSearchCollection {
+        collection: SearchCollection,
+        id: String,
+    },
+    /// the first page of the library of a user who logged in with spotify, the results come like search results
+    BrowseLibrary {
+        source: LibrarySource,
+        id: String,
+    },
+    /// the page of results after the ones the client already has
+    LoadMoreSearchResults {
+        query: SearchQuery,
+        offset: u32,
+        id: String,
+    },
+    /// the host reports where its player is at, the server keeps the time itself, this only corrects drift
+    Position {
+        percentage: f32,
+        spotify_id: String,
+    },
+    /// only the host can promote or demote co-hosts
+    SetPermissions {
+        user_id: String,
+        permissions: Permissions,
+    },
+    Skip,
+    /// jumps to the position in the current song, for the player of the host and the progress bars of everyone,
+    /// only the host can seek, co-hosts can't
+    Seek {
+        position_ms: u32,
+    },
+    /// the volume of the player of the host, from 0 to 100, only the host can set it
+    Volume {
+        percent: u8,
+    },
+    UpdateSettings {
+        name: String,
+        max_song_count: u8,
+        /// how many minutes a played song can't be added again
+        no_repeat_minutes: u32,
+        song_limit: SongLimit,
+        external_changes: ExternalChanges,
+    },
+    /// moves the jam through its lifecycle, for example to start a scheduled jam early
+    SetJamState {
+        state: JamState,
+    },
+    /// only the host can offer the jam to a user
+    HandOver {
+        user_id: String,
+    },
+    CancelHandOver,
+    /// only the user the jam was offered to can decline, accepting is done through spotify oauth
+    DeclineHandOver,
+    /// replaces the fallback list with the tracks of a spotify playlist, given as a link, uri or id
+    ImportFallbackPlaylist {
+        playlist: String,
+    },
+    ClearFallback,
+    AddFallbackTrack {
+        song_id: String,
+    },
+    /// the track id is the id in the fallback list, not the spotify id
+    RemoveFallbackTrack {
+        track_id: String,
+    },
+    /// the position is counted from 0
+    MoveFallbackTrack {
+        track_id: String,
+        position: u32,
+    },
+    SetFallbackShuffle {
+        shuffle: bool,
+    },
+}
+
+impl Request {
+    /// the id of the search, if the request asks for search results
+    pub fn search_id(&self) -> Option<&str> {
+        match self {
+            Self::Search { id, .. }
+            | Self::OpenSearchCollection { id, .. }
+            | Self::BrowseLibrary { id, .. }
+            | Self::LoadMoreSearchResults { id, .. } => Some(id),
+            _ => None,
+        }
+    }
 }
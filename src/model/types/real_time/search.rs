@@ -4,6 +4,207 @@ use std::vec::Vec;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct SearchResult {
+    /// empty if the results are albums, artists or playlists
     pub songs: Vec<Song>,
+    /// empty if the results are songs
+    pub collections: Vec<SearchCollection>,
     pub search_id: String,
+    /// what the results are for, needed to load the next page
+    pub query: SearchQuery,
+    /// where this page starts, 0 means the results replace the old ones
+    pub offset: u32,
+    /// none if there are no more results
+    pub next_offset: Option<u32>,
+}
+
+/// What the search bar looks for, songs can be added directly, the other kinds are opened to pick a song
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum SearchKind {
+    #[default]
+    Track,
+    Album,
+    Artist,
+    Playlist,
+}
+
+impl SearchKind {
+    pub fn all() -> [Self; 4] {
+        [Self::Track, Self::Album, Self::Artist, Self::Playlist]
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Track => "Songs",
+            Self::Album => "Albums",
+            Self::Artist => "Artists",
+            Self::Playlist => "Playlists",
+        }
+    }
+}
+
+/// an album, artist or playlist from the search, opening it shows its songs
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SearchCollection {
+    pub kind: SearchKind,
+    pub spotify_id: String,
+    pub name: String,
+    /// the artists of an album, the followers of an artist or the owner of a playlist
+    pub subtitle: String,
+    pub image_url: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SearchQuery {
+    /// the text from the search bar
+    Text { query: String, kind: SearchKind },
+    /// the songs of an opened album, artist or playlist, artists only have their top songs
+    Collection(SearchCollection),
+    /// the library of a guest who logged in with spotify
+    Library(LibrarySource),
+}
+
+/// what a guest who logged in with spotify can browse from their own account
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum LibrarySource {
+    #[default]
+    SavedTracks,
+    TopTracks,
+    /// opened like the playlists from the search
+    Playlists,
+}
+
+impl LibrarySource {
+    pub fn all() -> [Self; 3] {
+        [Self::SavedTracks, Self::TopTracks, Self::Playlists]
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::SavedTracks => "Liked Songs",
+            Self::TopTracks => "Top Songs",
+            Self::Playlists => "Playlists",
+        }
+    }
+}
+
+impl SearchQuery {
+    /// the request for the page of results that starts at the offset
+    pub fn into_request(self, offset: u32, search_id: String) -> super::Request {
+        match (self, offset) {
+            (Self::Text { query, kind }, 0) => super::Request::Search {
+                query,
+                id: search_id,
+                kind,
+            },
+            (Self::Collection(collection), 0) => super::Request::OpenSearchCollection {
+                collection,
+                id: search_id,
+            },
+            (Self::Library(source), 0) => super::Request::BrowseLibrary {
+                source,
+                id: search_id,
+            },
+            (query, offset) => super::Request::LoadMoreSearchResults {
+                query,
+                offset,
+                id: search_id,
+            },
+        }
+    }
+}
+
+/// what a pasted spotify link or uri points to, artists, albums and playlists are opened to pick songs
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SpotifyLink {
+    pub kind: SearchKind,
+    pub spotify_id: String,
+}
+
+impl SpotifyLink {
+    /// accepts open.spotify.com links, with or without the locale and the query,
+    /// and spotify: uris, returns none for anything else, like a normal search
+    pub fn parse(input: &str) -> Option<Self> {
+        let input = input.trim();
+        let (kind, id) = if let Some(uri) = input.strip_prefix("spotify:") {
+            uri.split_once(':')?
+        } else {
+            let path = input
+                .strip_prefix("https://")
+                .or_else(|| input.strip_prefix("http://"))
+                .unwrap_or(input)
+                .strip_prefix("open.spotify.com/")?;
+            let mut segments = path.split(['?', '#']).next()?.split('/');
+            let mut kind = segments.next()?;
+            // links can have the locale first, like /intl-de/track/...
+            if kind.starts_with("intl-") {
+                kind = segments.next()?;
+            }
+            (kind, segments.next()?)
+        };
+
+        let kind = match kind {
+            "track" => SearchKind::Track,
+            "album" => SearchKind::Album,
+            "artist" => SearchKind::Artist,
+            "playlist" => SearchKind::Playlist,
+            _ => return None,
+        };
+        if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return None;
+        }
+        Some(Self {
+            kind,
+            spotify_id: id.to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn link(kind: SearchKind, spotify_id: &str) -> Option<SpotifyLink> {
+        Some(SpotifyLink {
+            kind,
+            spotify_id: spotify_id.to_string(),
+        })
+    }
+
+    #[test]
+    fn parses_links_and_uris() {
+        assert_eq!(
+            SpotifyLink::parse("https://open.spotify.com/track/4uLU6hMCjMI75M1A2tKUQC"),
+            link(SearchKind::Track, "4uLU6hMCjMI75M1A2tKUQC")
+        );
+        assert_eq!(
+            SpotifyLink::parse("  spotify:album:1DFixLWuPkv3KT3TnV35m3 "),
+            link(SearchKind::Album, "1DFixLWuPkv3KT3TnV35m3")
+        );
+        assert_eq!(
+            SpotifyLink::parse("open.spotify.com/artist/0OdUWJ0sBjDrqHygGUXeCF"),
+            link(SearchKind::Artist, "0OdUWJ0sBjDrqHygGUXeCF")
+        );
+    }
+
+    #[test]
+    fn ignores_the_locale_and_the_query() {
+        assert_eq!(
+            SpotifyLink::parse(
+                "https://open.spotify.com/intl-de/playlist/37i9dQZF1DXcBWIGoYBM5M?si=abc123#top"
+            ),
+            link(SearchKind::Playlist, "37i9dQZF1DXcBWIGoYBM5M")
+        );
+    }
+
+    #[test]
+    fn rejects_everything_else() {
+        assert_eq!(SpotifyLink::parse("never gonna give you up"), None);
+        assert_eq!(SpotifyLink::parse("https://example.com/track/abc"), None);
+        assert_eq!(
+            SpotifyLink::parse("https://open.spotify.com/show/abc"),
+            None
+        );
+        assert_eq!(SpotifyLink::parse("https://open.spotify.com/track/"), None);
+        assert_eq!(SpotifyLink::parse("spotify:track:not-an-id"), None);
+    }
 }
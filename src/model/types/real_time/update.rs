@@ -19,6 +19,17 @@ pub struct Update {
     pub position: Option<f32>,
     /// the current song may be null, so there is an option inside an option
     pub current_song: Option<Option<Song>>,
+    /// the jam with its current settings, sent when the settings change
+    pub jam: Option<Jam>,
+    /// the id of the user the host offered the jam to, if there is a pending offer,
+    /// the old host gets this together with ended after the jam was handed over
+    pub handover: Option<Option<String>>,
+    /// the fallback list of the jam, only sent to the host
+    pub fallback: Option<Fallback>,
+    /// the balance of the user, only sent to users in jams with credits
+    pub credits: Option<Credits>,
+    /// the volume the host set, from 0 to 100
+    pub volume: Option<u8>,
 }
 
 impl Update {
@@ -34,8 +45,12 @@ impl Update {
     }
 
     #[cfg(feature = "ssr")]
-    pub async fn users_from_jam<'e>(self, id: &Id, executor: impl sqlx::PgExecutor<'e>) -> Self {
-        match functions::get_users(executor, id).await {
+    pub async fn users_from_jam<'e>(
+        self,
+        id: &Id,
+        transaction: &mut sqlx::Transaction<'e, sqlx::Postgres>,
+    ) -> Self {
+        match functions::get_users(transaction, id).await {
             Ok(users) => self.users(users),
             Err(e) => self.error(e.into()),
         }
@@ -141,6 +156,117 @@ impl Update {
         }
     }
 
+    pub fn jam(self, jam: Jam) -> Self {
+        Self {
+            jam: Some(jam),
+            ..self
+        }
+    }
+
+    #[cfg(feature = "ssr")]
+    pub async fn jam_from_jam<'e>(self, jam_id: &str, executor: impl sqlx::PgExecutor<'e>) -> Self {
+        match functions::get_jam(jam_id, executor).await {
+            Ok(jam) => self.jam(jam),
+            Err(e) => self.error(e),
+        }
+    }
+
+    pub fn handover(self, user_id: Option<String>) -> Self {
+        Self {
+            handover: Some(user_id),
+            ..self
+        }
+    }
+
+    /// ends the connection of the host if the jam was handed over to someone else
+    #[cfg(feature = "ssr")]
+    pub async fn handover_from_jam<'e>(
+        self,
+        id: &Id,
+        transaction: &mut sqlx::Transaction<'e, sqlx::Postgres>,
+    ) -> Self {
+        if let IdType::Host(host_id) = &id.id {
+            match functions::is_host_of_jam(host_id, id.jam_id(), &mut **transaction).await {
+                Ok(true) => (),
+                Ok(false) => return self.handover(None).ended(),
+                Err(e) => return self.error(e),
+            }
+        }
+        match functions::get_handover(id.jam_id(), &mut **transaction).await {
+            Ok(user_id) => self.handover(user_id),
+            Err(e) => self.error(e),
+        }
+    }
+
+    pub fn fallback(self, fallback: Fallback) -> Self {
+        Self {
+            fallback: Some(fallback),
+            ..self
+        }
+    }
+
+    /// the fallback list is only for the host, users and spectators don't get it
+    #[cfg(feature = "ssr")]
+    pub async fn fallback_from_jam<'e>(
+        self,
+        id: &Id,
+        transaction: &mut sqlx::Transaction<'e, sqlx::Postgres>,
+    ) -> Self {
+        if !id.is_host() {
+            return self;
+        }
+        match functions::get_fallback(id.jam_id(), transaction).await {
+            Ok(fallback) => self.fallback(fallback),
+            Err(e) => self.error(e),
+        }
+    }
+
+    pub fn credits(self, credits: Credits) -> Self {
+        Self {
+            credits: Some(credits),
+            ..self
+        }
+    }
+
+    #[cfg(feature = "ssr")]
+    pub async fn credits_from_jam<'e>(
+        self,
+        id: &Id,
+        transaction: &mut sqlx::Transaction<'e, sqlx::Postgres>,
+    ) -> Self {
+        let IdType::User(user_id) = &id.id else {
+            return self;
+        };
+        match functions::get_song_limit(id.jam_id(), &mut **transaction).await {
+            Ok(SongLimit::Credits) => (),
+            Ok(SongLimit::MaxSongs) => return self,
+            Err(e) => return self.error(e),
+        }
+        match functions::get_credits(user_id, transaction).await {
+            Ok(credits) => self.credits(credits),
+            Err(e) => self.error(e),
+        }
+    }
+
+    pub fn volume(self, percent: u8) -> Self {
+        Self {
+            volume: Some(percent),
+            ..self
+        }
+    }
+
+    #[cfg(feature = "ssr")]
+    pub async fn volume_from_jam<'e>(
+        self,
+        jam_id: &str,
+        executor: impl sqlx::PgExecutor<'e>,
+    ) -> Self {
+        match functions::get_volume(jam_id, executor).await {
+            Ok(percent) => self.volume(percent),
+            Err(e) => self.error(e),
+        }
+    }
+
     pub fn merge_with_other(self, other: Self, prioritize_other: bool) -> Self {
         if prioritize_other {
             Self {
@@ -152,6 +278,11 @@ impl Update {
                 ended: other.ended.or(self.ended),
                 position: other.position.or(self.position),
                 current_song: other.current_song.or(self.current_song),
+                jam: other.jam.or(self.jam),
+                handover: other.handover.or(self.handover),
+                fallback: other.fallback.or(self.fallback),
+                credits: other.credits.or(self.credits),
+                volume: other.volume.or(self.volume),
             }
         } else {
             Self {
@@ -163,6 +294,11 @@ impl Update {
                 ended: self.ended.or(other.ended),
                 position: self.position.or(other.position),
                 current_song: self.current_song.or(other.current_song),
+                jam: self.jam.or(other.jam),
+                handover: self.handover.or(other.handover),
+                fallback: self.fallback.or(other.fallback),
+                credits: self.credits.or(other.credits),
+                volume: self.volume.or(other.volume),
             }
         }
     }
@@ -181,7 +317,7 @@ impl Update {
         let users_future = async {
             if changed.users {
                 let mut transaction = transaction.lock().await;
-                update.clone().users_from_jam(id, &mut ***transaction).await
+                update.clone().users_from_jam(id, *transaction).await
             } else {
                 update.clone()
             }
@@ -237,6 +373,57 @@ impl Update {
             }
         };
 
+        let settings_future = async {
+            if changed.settings {
+                let mut transaction = transaction.lock().await;
+                update
+                    .clone()
+                    .jam_from_jam(id.jam_id(), &mut ***transaction)
+                    .await
+            } else {
+                update.clone()
+            }
+        };
+
+        let handover_future = async {
+            if changed.handover {
+                let mut transaction = transaction.lock().await;
+                update.clone().handover_from_jam(id, *transaction).await
+            } else {
+                update.clone()
+            }
+        };
+
+        let fallback_future = async {
+            if changed.fallback {
+                let mut transaction = transaction.lock().await;
+                update.clone().fallback_from_jam(id, *transaction).await
+            } else {
+                update.clone()
+            }
+        };
+
+        let credits_future = async {
+            if changed.credits {
+                let mut transaction = transaction.lock().await;
+                update.clone().credits_from_jam(id, *transaction).await
+            } else {
+                update.clone()
+            }
+        };
+
+        let volume_future = async {
+            if changed.volume {
+                let mut transaction = transaction.lock().await;
+                update
+                    .clone()
+                    .volume_from_jam(id.jam_id(), &mut ***transaction)
+                    .await
+            } else {
+                update.clone()
+            }
+        };
+
         let (
             users_update,
             songs_update,
@@ -244,13 +431,23 @@ impl Update {
             ended_update,
             position_update,
             current_song_update,
+            settings_update,
+            handover_update,
+            fallback_update,
+            credits_update,
+            volume_update,
         ) = tokio::join!(
             users_future,
             songs_future,
             votes_future,
             ended_future,
             position_future,
-            current_song_future
+            current_song_future,
+            settings_future,
+            handover_future,
+            fallback_future,
+            credits_future,
+            volume_future
         );
 
         update
@@ -260,6 +457,11 @@ impl Update {
             .merge_with_other(ended_update, false)
             .merge_with_other(position_update, false)
             .merge_with_other(current_song_update, false)
+            .merge_with_other(settings_update, false)
+            .merge_with_other(handover_update, false)
+            .merge_with_other(fallback_update, false)
+            .merge_with_other(credits_update, false)
+            .merge_with_other(volume_update, false)
     }
 }
 
@@ -15,6 +15,33 @@ pub struct Song {
     pub duration: u32,
     pub image_url: String,
     pub votes: Vote,
+    #[serde(default)]
+    pub details: SongDetails,
+}
+
+impl Song {
+    /// the link to open the song in spotify
+    pub fn spotify_url(&self) -> String {
+        format!("https://open.spotify.com/track/{}", self.spotify_id)
+    }
+}
+
+/// extra information from spotify, so guests can check a song before adding it
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct SongDetails {
+    /// a 30 second mp3 clip, spotify doesn't have one for every song
+    pub preview_url: Option<String>,
+    pub release_year: Option<u16>,
+    pub explicit: bool,
+    /// 0 to 100, based on how much the song was played recently
+    pub popularity: Option<u8>,
+}
+
+impl SongDetails {
+    /// gets the year out of a spotify release date, which can be just the year, the year and month or the full date
+    pub fn parse_release_year(release_date: Option<&str>) -> Option<u16> {
+        release_date?.get(..4)?.parse().ok()
+    }
 }
 
 
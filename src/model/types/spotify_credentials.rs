@@ -1,10 +1,19 @@
 use rspotify::Credentials;
 use serde::{Deserialize, Serialize};
 
+/// where the web api of spotify is, with the trailing slash rspotify expects
+pub const SPOTIFY_API_URL: &str = "https://api.spotify.com/v1/";
+/// where the login and the tokens of spotify are
+pub const SPOTIFY_ACCOUNTS_URL: &str = "https://accounts.spotify.com/";
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct SpotifyCredentials {
     pub id: String,
     pub secret: String,
+    /// only changed to use a stand-in server, like the mock spotify server
+    pub api_url: String,
+    /// only changed to use a stand-in server, like the mock spotify server
+    pub accounts_url: String,
 }
 
 impl From<Credentials> for SpotifyCredentials {
@@ -12,6 +21,8 @@ impl From<Credentials> for SpotifyCredentials {
         Self {
             id: credentials.id,
             secret: credentials.secret.unwrap(),
+            api_url: SPOTIFY_API_URL.to_string(),
+            accounts_url: SPOTIFY_ACCOUNTS_URL.to_string(),
         }
     }
 }
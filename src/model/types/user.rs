@@ -5,4 +5,31 @@ pub struct User {
     pub id: String,
     pub jam_id: String,
     pub name: String,
+    /// what the user is allowed to do on behalf of the host, all false for normal users
+    pub permissions: Permissions,
+}
+
+/// The moderation permissions the host can delegate to a user, making them a co-host
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Permissions {
+    pub kick: bool,
+    pub remove_songs: bool,
+    pub skip: bool,
+    pub change_settings: bool,
+}
+
+impl Permissions {
+    pub fn all() -> Self {
+        Self {
+            kick: true,
+            remove_songs: true,
+            skip: true,
+            change_settings: true,
+        }
+    }
+
+    /// a user is a co-host if they have at least one permission
+    pub fn is_co_host(&self) -> bool {
+        self.kick || self.remove_songs || self.skip || self.change_settings
+    }
 }
@@ -1,10 +1,10 @@
 use codee::binary::MsgpackSerdeCodec;
 use leptos::{logging::*, prelude::*};
 use leptos_use::{UseWebSocketReturn, core::ConnectionReadyState, use_websocket};
-use std::marker::PhantomData;
+use std::{marker::PhantomData, sync::Arc};
 
 use super::{
-    Song, User, Votes,
+    Credits, ExternalChanges, Fallback, Jam, Permissions, Song, SongLimit, User, Votes,
     real_time::{self, search},
 };
 
@@ -15,7 +15,12 @@ impl Role for HostRole {}
 #[derive(Debug, Clone, Copy)]
 pub struct UserRole;
 impl Role for UserRole {}
+/// only listens to the jam, the socket is opened with the jam id, so it can't change anything
+#[derive(Debug, Clone, Copy)]
+pub struct TvRole;
+impl Role for TvRole {}
 
+#[derive(Clone)]
 pub struct WsClientWrapper<R: Role> {
     pub search_result: Signal<Option<real_time::SearchResult>>,
     set_search_result: WriteSignal<Option<real_time::SearchResult>>,
@@ -33,14 +38,28 @@ pub struct WsClientWrapper<R: Role> {
     set_errors: WriteSignal<Vec<super::Error>>,
     pub ended: Signal<bool>,
     set_ended: WriteSignal<bool>,
+    pub jam: Signal<Option<Jam>>,
+    set_jam: WriteSignal<Option<Jam>>,
+    /// the id of the user the host offered the jam to
+    pub handover: Signal<Option<String>>,
+    set_handover: WriteSignal<Option<String>>,
+    /// only the host gets the fallback list
+    pub fallback: Signal<Option<Fallback>>,
+    set_fallback: WriteSignal<Option<Fallback>>,
+    /// only users in jams with credits get their balance
+    pub credits: Signal<Option<Credits>>,
+    set_credits: WriteSignal<Option<Credits>>,
+    /// the volume the host set, it comes alone and not with the jam
+    pub volume: Signal<Option<u8>>,
+    set_volume_percent: WriteSignal<Option<u8>>,
 
-    initial_update: Signal<Option<Result<real_time::Update, super::Error>>>,
+    initial_update: Signal<Option<Result<real_time::Update, ServerFnError>>>,
 
     pub ready_state: Signal<ConnectionReadyState>,
 
-    send: Box<dyn Fn(&real_time::Request) + Send + Sync>,
+    send: Arc<dyn Fn(&real_time::Request) + Send + Sync>,
     message: Signal<Option<real_time::Update>>,
-    pub close: Box<dyn Fn() + Send + Sync>,
+    pub close: Arc<dyn Fn() + Send + Sync>,
 
     id: String,
 
@@ -48,9 +67,11 @@ pub struct WsClientWrapper<R: Role> {
 }
 
 impl<R: Role> WsClientWrapper<R> {
+    /// the jam id is only needed for hosts with several jams
     pub fn new(
         id: String,
-        initial_update: Signal<Option<Result<real_time::Update, super::Error>>>,
+        jam_id: Option<String>,
+        initial_update: Signal<Option<Result<real_time::Update, ServerFnError>>>,
     ) -> Self {
         let UseWebSocketReturn {
             ready_state,
@@ -58,10 +79,12 @@ impl<R: Role> WsClientWrapper<R> {
             close,
             send,
             ..
-        } = use_websocket::<real_time::Request, real_time::Update, MsgpackSerdeCodec>(&format!(
-            "/socket?id={}",
-            id
-        ));
+        } = use_websocket::<real_time::Request, real_time::Update, MsgpackSerdeCodec>(
+            &match jam_id {
+                Some(jam_id) => format!("/socket?id={}&jam_id={}", id, jam_id),
+                None => format!("/socket?id={}", id),
+            },
+        );
 
         let (search_result, set_search_result) = signal(None);
         let (songs, set_songs) = signal(None);
@@ -71,6 +94,11 @@ impl<R: Role> WsClientWrapper<R> {
         let (current_song, set_current_song) = signal(None);
         let (errors, set_errors) = signal(Vec::new());
         let (ended, set_ended) = signal(false);
+        let (jam, set_jam) = signal(None);
+        let (handover, set_handover) = signal(None);
+        let (fallback, set_fallback) = signal(None);
+        let (credits, set_credits) = signal(None);
+        let (volume, set_volume_percent) = signal(None);
 
         {
             let close = close.clone();
@@ -106,6 +134,21 @@ impl<R: Role> WsClientWrapper<R> {
                     if let Some(current_song) = update.current_song {
                         set_current_song.set(current_song);
                     }
+                    if let Some(jam) = update.jam {
+                        set_jam.set(Some(jam));
+                    }
+                    if let Some(handover) = update.handover {
+                        set_handover.set(handover);
+                    }
+                    if let Some(fallback) = update.fallback {
+                        set_fallback.set(Some(fallback));
+                    }
+                    if let Some(credits) = update.credits {
+                        set_credits.set(Some(credits));
+                    }
+                    if let Some(volume) = update.volume {
+                        set_volume_percent.set(Some(volume));
+                    }
                 }
             });
         }
@@ -127,14 +170,24 @@ impl<R: Role> WsClientWrapper<R> {
             set_errors,
             ended: ended.into(),
             set_ended,
+            jam: jam.into(),
+            set_jam,
+            handover: handover.into(),
+            set_handover,
+            fallback: fallback.into(),
+            set_fallback,
+            credits: credits.into(),
+            set_credits,
+            volume: volume.into(),
+            set_volume_percent,
 
             initial_update,
 
             ready_state,
 
-            send: Box::new(send),
+            send: Arc::new(send),
             message,
-            close: Box::new(close),
+            close: Arc::new(close),
 
             id,
 
@@ -146,17 +199,50 @@ impl<R: Role> WsClientWrapper<R> {
         let request = real_time::Request::RemoveSong { song_id };
         (self.send)(&request);
     }
-}
 
-impl WsClientWrapper<UserRole> {
-    pub fn search(&self, query: String, search_id: String) {
-        let request = real_time::Request::Search {
-            query,
-            id: search_id,
+    /// users search for songs to add, the host for tracks to add to the fallback list,
+    /// an offset of 0 starts a new search, otherwise the next page of the same query is loaded
+    pub fn search(&self, query: real_time::SearchQuery, offset: u32, search_id: String) {
+        let request = query.into_request(offset, search_id);
+        (self.send)(&request);
+    }
+
+    /// only works for the host and co-hosts with the skip permission
+    pub fn skip(&self) {
+        (self.send)(&real_time::Request::Skip);
+    }
+
+    /// only works for the host
+    pub fn seek(&self, position_ms: u32) {
+        (self.send)(&real_time::Request::Seek { position_ms });
+    }
+
+    /// only works for the host
+    pub fn set_volume(&self, percent: u8) {
+        (self.send)(&real_time::Request::Volume { percent });
+    }
+
+    /// only works for the host and co-hosts with the change settings permission
+    pub fn update_settings(
+        &self,
+        name: String,
+        max_song_count: u8,
+        no_repeat_minutes: u32,
+        song_limit: SongLimit,
+        external_changes: ExternalChanges,
+    ) {
+        let request = real_time::Request::UpdateSettings {
+            name,
+            max_song_count,
+            no_repeat_minutes,
+            song_limit,
+            external_changes,
         };
         (self.send)(&request);
     }
+}
 
+impl WsClientWrapper<UserRole> {
     pub fn add_song(&self, song_id: String) {
         let request = real_time::Request::AddSong { song_id };
         (self.send)(&request);
@@ -172,6 +258,15 @@ impl WsClientWrapper<UserRole> {
         (self.send)(&request);
     }
 
+    pub fn boost_song(&self, song_id: String) {
+        let request = real_time::Request::BoostSong { song_id };
+        (self.send)(&request);
+    }
+
+    pub fn decline_hand_over(&self) {
+        (self.send)(&real_time::Request::DeclineHandOver);
+    }
+
     pub fn leave(&self) {
         let request = real_time::Request::KickUser {
             user_id: self.id.clone(),
@@ -185,8 +280,48 @@ impl WsClientWrapper<HostRole> {
         let request = real_time::Request::KickUser { user_id };
         (self.send)(&request);
     }
-    pub fn set_song_position(&self, percentage: f32) {
-        let request = real_time::Request::Position { percentage };
+    pub fn set_song_position(&self, percentage: f32, spotify_id: String) {
+        let request = real_time::Request::Position {
+            percentage,
+            spotify_id,
+        };
+        (self.send)(&request);
+    }
+    pub fn hand_over(&self, user_id: String) {
+        let request = real_time::Request::HandOver { user_id };
+        (self.send)(&request);
+    }
+    pub fn cancel_hand_over(&self) {
+        (self.send)(&real_time::Request::CancelHandOver);
+    }
+    pub fn set_permissions(&self, user_id: String, permissions: Permissions) {
+        let request = real_time::Request::SetPermissions {
+            user_id,
+            permissions,
+        };
+        (self.send)(&request);
+    }
+    pub fn import_fallback_playlist(&self, playlist: String) {
+        let request = real_time::Request::ImportFallbackPlaylist { playlist };
+        (self.send)(&request);
+    }
+    pub fn clear_fallback(&self) {
+        (self.send)(&real_time::Request::ClearFallback);
+    }
+    pub fn add_fallback_track(&self, song_id: String) {
+        let request = real_time::Request::AddFallbackTrack { song_id };
+        (self.send)(&request);
+    }
+    pub fn remove_fallback_track(&self, track_id: String) {
+        let request = real_time::Request::RemoveFallbackTrack { track_id };
+        (self.send)(&request);
+    }
+    pub fn move_fallback_track(&self, track_id: String, position: u32) {
+        let request = real_time::Request::MoveFallbackTrack { track_id, position };
+        (self.send)(&request);
+    }
+    pub fn set_fallback_shuffle(&self, shuffle: bool) {
+        let request = real_time::Request::SetFallbackShuffle { shuffle };
         (self.send)(&request);
     }
 }
@@ -0,0 +1,96 @@
+use leptos::{logging::*, prelude::*};
+use leptos_router::{hooks::*, *};
+
+/// saves the token if the user allowed access, returns the id of the jam of the user,
+/// so they can go back to it either way
+#[server]
+async fn connect_spotify_account(
+    code: Option<String>,
+    user_id: String,
+) -> Result<String, ServerFnError> {
+    use crate::model::{AppState, check_id_type, functions};
+    let app_state = expect_context::<AppState>();
+    let mut transaction = app_state.db.pool.begin().await?;
+
+    let jam_id = match code {
+        Some(code) => match functions::connect_user_spotify(
+            &code,
+            &user_id,
+            &app_state.spotify_credentials,
+            &app_state.reqwest_client,
+            &mut transaction,
+            &format!("{}/connect-spotify", app_state.site_url),
+        )
+        .await
+        {
+            Ok(jam_id) => jam_id,
+            Err(e) => return Err(ServerFnError::ServerError(format!("{:#?}", e))),
+        },
+        None => {
+            let id = check_id_type(&user_id, None, &mut transaction).await?;
+            if !id.is_user() {
+                return Err(ServerFnError::Request("id is not a user id".to_string()));
+            }
+            id.jam_id().to_string()
+        }
+    };
+    transaction.commit().await?;
+
+    Ok(jam_id)
+}
+
+/// spotify redirects guests here after they logged in to browse their own library
+#[component]
+pub fn ConnectSpotifyPage() -> impl IntoView {
+    let queries = use_query_map();
+    let code = move || queries.with(|queries| queries.get("code"));
+    let user_id = move || queries.with(|queries| queries.get("state"));
+
+    let connect_action = Action::new(|input: &(Option<String>, String)| {
+        let input = input.clone();
+        async move { connect_spotify_account(input.0, input.1).await }
+    });
+
+    let (feedback, set_feedback) = signal(String::from("Logging in with Spotify..."));
+
+    Effect::new(move |_| {
+        if let Some(user_id) = user_id() {
+            log!("Connecting spotify of user: {}", user_id);
+            connect_action.dispatch((code(), user_id));
+        } else {
+            set_feedback.set("Error logging in with Spotify: missing state".to_string());
+        }
+    });
+
+    Effect::new(move |_| {
+        if let Some(res) = connect_action.value().get() {
+            match res {
+                Ok(jam_id) => {
+                    if code().is_some() {
+                        set_feedback.set("Logged in with Spotify!".to_string());
+                    } else {
+                        set_feedback.set("Spotify login canceled".to_string());
+                    }
+                    let navigate = use_navigate();
+                    navigate(&format!("/jam/{}", jam_id), NavigateOptions::default());
+                    return;
+                }
+                Err(err) => {
+                    set_feedback.set(format!("Error logging in with Spotify: {:#?}", err));
+                }
+            }
+            let timer = gloo::timers::callback::Timeout::new(2000, || {
+                let navigate = use_navigate();
+                navigate("/", NavigateOptions::default());
+            });
+
+            timer.forget();
+        }
+    });
+
+    view! {
+        <div id="connect-spotify-page">
+            <div id="connect-spotify-island">{feedback}</div>
+        </div>
+    }
+}
@@ -1,15 +1,20 @@
 use leptos::{logging::*, prelude::*};
 use leptos_router::{hooks::*, *};
 
+/// returns the id of the host, which can differ from the given one,
+/// if the spotify account already had a host, and the id of the jam, if the host was created to take over one
 #[server]
-async fn create_host(code: String, host_id: String) -> Result<(), ServerFnError> {
+async fn create_host(
+    code: String,
+    host_id: String,
+) -> Result<(String, Option<String>), ServerFnError> {
     use crate::model::functions;
     use crate::model::AppState;
     let app_state = expect_context::<AppState>();
 
-    if let Err(e) = functions::create_host(
+    let spotify_user_id = match functions::create_host(
         code,
-        host_id,
+        host_id.clone(),
         &app_state.spotify_credentials,
         &app_state.reqwest_client,
         &app_state.db.pool,
@@ -17,10 +22,29 @@ async fn create_host(code: String, host_id: String) -> Result<(), ServerFnError>
     )
     .await
     {
-        return Err(ServerFnError::ServerError(format!("{:#?}", e)));
-    }
+        Ok(spotify_user_id) => spotify_user_id,
+        Err(e) => return Err(ServerFnError::ServerError(format!("{:#?}", e))),
+    };
+
+    let mut transaction = app_state.db.pool.begin().await?;
+    let jam_id = match functions::complete_handover(
+        &host_id,
+        &mut transaction,
+        app_state.spotify_credentials.clone(),
+    )
+    .await
+    {
+        Ok(jam_id) => jam_id,
+        Err(e) => return Err(ServerFnError::ServerError(format!("{:#?}", e))),
+    };
+    let host_id =
+        match functions::claim_host_account(&host_id, &spotify_user_id, &mut transaction).await {
+            Ok(host_id) => host_id,
+            Err(e) => return Err(ServerFnError::ServerError(format!("{:#?}", e))),
+        };
+    transaction.commit().await?;
 
-    Ok(())
+    Ok((host_id, jam_id))
 }
 
 #[component]
@@ -35,10 +59,10 @@ pub fn CreateHostPage() -> impl IntoView {
         let input = input.clone();
         async move {
             let res = create_host(input.0.clone(), input.1.clone()).await;
-            if res.is_ok() {
-                LocalStorage::set("host_id", input.1)?;
+            if let Ok((host_id, _)) = &res {
+                LocalStorage::set("host_id", host_id)?;
             }
-            res
+            res.map(|(_, jam_id)| jam_id)
         }
     });
 
@@ -57,8 +81,17 @@ pub fn CreateHostPage() -> impl IntoView {
     Effect::new(move |_| {
         if let Some(res) = create_host_action.value().get() {
             match res {
-                Ok(_) => {
-                    set_feedback.set("Host created successfully!".to_string());
+                Ok(Some(jam_id)) => {
+                    set_feedback.set("The jam is yours now!".to_string());
+                    let navigate = use_navigate();
+                    navigate(&format!("/jam/host/{}", jam_id), NavigateOptions::default());
+                    return;
+                }
+                Ok(None) => {
+                    set_feedback.set("Logged in successfully!".to_string());
+                    let navigate = use_navigate();
+                    navigate("/dashboard", NavigateOptions::default());
+                    return;
                 }
                 Err(err) => {
                     set_feedback.set(format!("Error creating host: {:#?}", err));
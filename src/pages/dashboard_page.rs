@@ -0,0 +1,202 @@
+use super::host_page::delete_jam;
+use crate::components::{CreateIsland, Modal};
+use crate::model::*;
+use gloo::storage::{LocalStorage, Storage};
+use leptos::{either::Either, logging::*, prelude::*};
+use leptos_meta::Title;
+use leptos_router::{NavigateOptions, hooks::use_navigate};
+
+/// Lists all the jams of the host, a host can run several jams at once
+#[component]
+pub fn DashboardPage() -> impl IntoView {
+    let (host_id, set_host_id) = signal(None::<String>);
+    Effect::new(move |_| match LocalStorage::get::<String>("host_id") {
+        Ok(host_id) if !host_id.is_empty() => set_host_id.set(Some(host_id)),
+        _ => {
+            let navigator = use_navigate();
+            navigator("/", NavigateOptions::default());
+        }
+    });
+
+    let jams = Resource::new(
+        move || host_id.get(),
+        move |host_id| async move {
+            match host_id {
+                Some(host_id) => get_host_jams(host_id).await,
+                None => Err(ServerFnError::ServerError("host_id is empty".to_string())),
+            }
+        },
+    );
+
+    Effect::new(move |_| {
+        if let Some(Err(ServerFnError::Request(e))) = jams.get() {
+            // the host does not exist anymore, for example because it handed over its only jam
+            warn!("could not get the jams of the host: {}", e);
+            LocalStorage::delete("host_id");
+            let navigator = use_navigate();
+            navigator("/", NavigateOptions::default());
+        }
+    });
+
+    let past_jams = Resource::new(
+        move || host_id.get(),
+        move |host_id| async move {
+            match host_id {
+                Some(host_id) => get_past_jams(host_id).await,
+                None => Ok(Vec::new()),
+            }
+        },
+    );
+
+    let log_out = move |_| {
+        LocalStorage::delete("host_id");
+        let navigator = use_navigate();
+        navigator("/", NavigateOptions::default());
+    };
+
+    let (error_message, set_error_message) = signal(String::new());
+
+    let end_jam = Action::new(move |jam_id: &String| {
+        let jam_id = jam_id.clone();
+        async move {
+            if let Some(host_id) = host_id.get_untracked() {
+                match delete_jam(host_id, jam_id).await {
+                    Ok(_) => {
+                        jams.refetch();
+                        past_jams.refetch();
+                    }
+                    Err(e) => set_error_message.set(format!("Error ending jam: {}", e)),
+                }
+            }
+        }
+    });
+
+    view! {
+        <Title text="My Jams" />
+        <Modal visible=Signal::derive(move || {
+            error_message.with(|e| !e.is_empty())
+        })>
+            {error_message}
+            <button on:click=move |_| set_error_message.set(String::new()) class="button">
+                "Close"
+            </button>
+        </Modal>
+        <div class="dashboard-page">
+            <div class="jams">
+                <div class="header">
+                    "My Jams" <button class="button log-out" on:click=log_out>
+                        "Log out"
+                    </button>
+                </div>
+                {move || {
+                    if jams
+                        .get()
+                        .map(|jams| jams.map(|jams| jams.is_empty()).unwrap_or(false))
+                        .unwrap_or(false)
+                    {
+                        Either::Left(
+                            view! {
+                                <div class="no-jams">"You have no jams running, create one!"</div>
+                            },
+                        )
+                    } else {
+                        Either::Right(())
+                    }
+                }}
+                <For
+                    each=move || jams.get().and_then(Result::ok).unwrap_or_default()
+                    key=|jam| jam.id.clone()
+                    children=move |jam| {
+                        let jam_id = jam.id.clone();
+                        view! {
+                            <div class="jam">
+                                <div class="info">
+                                    <div class="name">{jam.name.clone()}</div>
+                                    <div class="code">{jam.id.to_uppercase()}</div>
+                                </div>
+                                <a class="button" href=format!("/jam/host/{}", jam.id)>
+                                    "Open"
+                                </a>
+                                <a
+                                    class="button"
+                                    target="_blank"
+                                    href=format!("/jam/tv/{}", jam.id)
+                                >
+                                    "TV"
+                                </a>
+                                <a class="button" href=format!("/jam/remote/{}", jam.id)>
+                                    "Remote"
+                                </a>
+                                <a
+                                    class="button"
+                                    target="_blank"
+                                    href=format!("/jam/output/{}", jam.id)
+                                >
+                                    "Output"
+                                </a>
+                                <button
+                                    class="button"
+                                    on:click=move |_| {
+                                        end_jam.dispatch(jam_id.clone());
+                                    }
+                                >
+                                    "End"
+                                </button>
+                            </div>
+                        }
+                    }
+                />
+            </div>
+            <CreateIsland />
+            {move || {
+                let past_jams = past_jams.get().and_then(Result::ok).unwrap_or_default();
+                if past_jams.is_empty() {
+                    Either::Right(())
+                } else {
+                    Either::Left(
+                        view! {
+                            <div class="past-jams">
+                                <div class="header">"Past Jams"</div>
+                                {past_jams
+                                    .into_iter()
+                                    .map(|past_jam| {
+                                        view! {
+                                            <div class="past-jam">
+                                                <div class="name">{past_jam.name}</div>
+                                                <div class="code">{past_jam.jam_id.to_uppercase()}</div>
+                                                <div class="date">{past_jam.ended_on}</div>
+                                            </div>
+                                        }
+                                    })
+                                    .collect_view()}
+                            </div>
+                        },
+                    )
+                }
+            }}
+        </div>
+    }
+}
+
+#[server]
+async fn get_host_jams(host_id: String) -> Result<Vec<Jam>, ServerFnError> {
+    use crate::model::{AppState, functions};
+    let app_state = expect_context::<AppState>();
+    let mut transaction = app_state.db.pool.begin().await?;
+    let jams = match functions::get_host_jams(&host_id, &mut transaction).await {
+        Ok(jams) => jams,
+        Err(Error::DoesNotExist(e)) => return Err(ServerFnError::Request(e)),
+        Err(e) => return Err(ServerFnError::ServerError(e.to_string())),
+    };
+    transaction.commit().await?;
+    Ok(jams)
+}
+
+#[server]
+async fn get_past_jams(host_id: String) -> Result<Vec<PastJam>, ServerFnError> {
+    use crate::model::{AppState, functions};
+    let app_state = expect_context::<AppState>();
+    functions::get_past_jams(&host_id, &app_state.db.pool)
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))
+}
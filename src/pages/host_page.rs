@@ -1,8 +1,11 @@
-use crate::components::{host::Player, Modal, Share, SongList, SongListAction, UsersBar};
+use crate::components::{
+    host::{FallbackList, Player, RemoteControls},
+    JamSettings, JamStatus, Modal, Share, SongList, SongListAction, UsersBar,
+};
 use crate::model::types::*;
 use codee::binary::MsgpackSerdeCodec;
 use gloo::storage::{LocalStorage, Storage};
-use leptos::{logging::*, prelude::*};
+use leptos::{either::Either, logging::*, prelude::*};
 use leptos_meta::Title;
 use leptos_router::{
     hooks::{use_navigate, use_params_map},
@@ -10,8 +13,14 @@ use leptos_router::{
 };
 use leptos_use::{use_websocket, UseWebSocketReturn};
 
+/// The page the host runs the jam from, by default it also plays the jam,
+/// as a remote it only controls it, so it can be used from a phone while the output page plays elsewhere
 #[component]
-pub fn HostPage() -> impl IntoView {
+pub fn HostPage(
+    /// no spotify player is started, the position and the current song come from the server
+    #[prop(optional)]
+    remote: bool,
+) -> impl IntoView {
     let (error_message, set_error_message) = signal(String::new());
 
     let (host_id, set_host_id) = signal(String::new());
@@ -31,20 +40,20 @@ pub fn HostPage() -> impl IntoView {
         set_host_id.set(host_id);
     });
 
+    let jam_id = move || use_params_map().with(|params| params.get("id"));
+    let jam_id = Signal::derive(jam_id);
+
     let initial_update = Resource::new(
-        move || host_id.get(),
-        move |host_id| async move {
+        move || (host_id.get(), jam_id.get()),
+        move |(host_id, jam_id)| async move {
             if let Some(host_id) = host_id {
-                get_initial_update(host_id).await
+                get_initial_update(host_id, jam_id).await
             } else {
                 Err(ServerFnError::Request("host_id is empty".to_string()))
             }
         },
     );
 
-    let jam_id = move || use_params_map().with(|params| params.get("id"));
-    let jam_id = Signal::derive(jam_id);
-
     let jam = Resource::new(
         move || jam_id.get(),
         move |jam_id| async move {
@@ -70,6 +79,10 @@ pub fn HostPage() -> impl IntoView {
     let (users, set_users) = signal(None);
     let (songs, set_songs) = signal(None::<Vec<Song>>);
     let (votes, set_votes) = signal(Votes::new());
+    let (fallback, set_fallback) = signal(None::<Fallback>);
+    let (search_result, set_search_result) = signal(None::<real_time::SearchResult>);
+    let (position, set_position) = signal(0.0);
+    let (current_song, set_current_song) = signal(None::<Song>);
 
     let (send_request, set_send_request) = signal(Callback::new(|_: real_time::Request| {
         warn!("wanted to send a message to ws, but the ws is not ready yet");
@@ -90,18 +103,136 @@ pub fn HostPage() -> impl IntoView {
     };
     let kick_user = Callback::new(kick_user);
 
-    let set_song_position = move |percentage| {
-        let request = real_time::Request::Position { percentage };
+    let set_song_position = move |(percentage, spotify_id)| {
+        let request = real_time::Request::Position {
+            percentage,
+            spotify_id,
+        };
         send_request.get_untracked().run(request);
     };
     let set_song_position = Callback::new(set_song_position);
 
+    let set_permissions = move |(user_id, permissions)| {
+        let request = real_time::Request::SetPermissions {
+            user_id,
+            permissions,
+        };
+        send_request.get_untracked().run(request);
+    };
+    let set_permissions = Callback::new(set_permissions);
+
+    let skip = move |_| {
+        send_request.get_untracked().run(real_time::Request::Skip);
+    };
+    let skip = Callback::new(skip);
+
+    let (handover, set_handover) = signal(None::<String>);
+    let hand_over = move |user_id| {
+        let request = real_time::Request::HandOver { user_id };
+        send_request.get_untracked().run(request);
+    };
+    let hand_over = Callback::new(hand_over);
+
+    let cancel_hand_over = move |_| {
+        send_request
+            .get_untracked()
+            .run(real_time::Request::CancelHandOver);
+    };
+
+    let set_jam_state = move |state| {
+        let request = real_time::Request::SetJamState { state };
+        send_request.get_untracked().run(request);
+    };
+    let set_jam_state = Callback::new(set_jam_state);
+
+    let set_paused = Callback::new(move |paused| {
+        set_jam_state.run(if paused {
+            JamState::Paused
+        } else {
+            JamState::Playing
+        })
+    });
+
+    let seek = Callback::new(move |position_ms| {
+        let request = real_time::Request::Seek { position_ms };
+        send_request.get_untracked().run(request);
+    });
+    let set_volume = Callback::new(move |percent| {
+        let request = real_time::Request::Volume { percent };
+        send_request.get_untracked().run(request);
+    });
+    // the volume comes alone when the host changes it, the jam only when the page loads or the settings change
+    let (volume_update, set_volume_update) = signal(None::<u8>);
+    let volume = Signal::derive(move || {
+        volume_update
+            .get()
+            .or_else(|| jam.get().and_then(Result::ok).map(|jam| jam.volume_percent))
+            .unwrap_or(100)
+    });
+
+    let (settings_visible, set_settings_visible) = signal(false);
+    let update_settings =
+        move |(name, max_song_count, no_repeat_minutes, song_limit, external_changes)| {
+            let request = real_time::Request::UpdateSettings {
+                name,
+                max_song_count,
+                no_repeat_minutes,
+                song_limit,
+                external_changes,
+            };
+            send_request.get_untracked().run(request);
+        };
+    let update_settings = Callback::new(update_settings);
+
+    let search = move |(query, offset, id): (real_time::SearchQuery, u32, String)| {
+        let request = query.into_request(offset, id);
+        send_request.get_untracked().run(request);
+    };
+    let search = Callback::new(search);
+
+    let import_fallback_playlist = move |playlist| {
+        let request = real_time::Request::ImportFallbackPlaylist { playlist };
+        send_request.get_untracked().run(request);
+    };
+    let import_fallback_playlist = Callback::new(import_fallback_playlist);
+
+    let clear_fallback = move |_| {
+        send_request
+            .get_untracked()
+            .run(real_time::Request::ClearFallback);
+    };
+    let clear_fallback = Callback::new(clear_fallback);
+
+    let add_fallback_track = move |song_id| {
+        let request = real_time::Request::AddFallbackTrack { song_id };
+        send_request.get_untracked().run(request);
+    };
+    let add_fallback_track = Callback::new(add_fallback_track);
+
+    let remove_fallback_track = move |track_id| {
+        let request = real_time::Request::RemoveFallbackTrack { track_id };
+        send_request.get_untracked().run(request);
+    };
+    let remove_fallback_track = Callback::new(remove_fallback_track);
+
+    let move_fallback_track = move |(track_id, position)| {
+        let request = real_time::Request::MoveFallbackTrack { track_id, position };
+        send_request.get_untracked().run(request);
+    };
+    let move_fallback_track = Callback::new(move_fallback_track);
+
+    let set_fallback_shuffle = move |shuffle| {
+        let request = real_time::Request::SetFallbackShuffle { shuffle };
+        send_request.get_untracked().run(request);
+    };
+    let set_fallback_shuffle = Callback::new(set_fallback_shuffle);
+
     Effect::new(move |_| log!("host_id:{:?}", host_id.get()));
 
     Effect::new(move |_| {
-        let host_id = match host_id.get() {
-            Some(host_id) => host_id,
-            None => return,
+        let (host_id, jam_id) = match (host_id.get(), jam_id.get()) {
+            (Some(host_id), Some(jam_id)) => (host_id, jam_id),
+            _ => return,
         };
 
         let UseWebSocketReturn {
@@ -111,8 +242,8 @@ pub fn HostPage() -> impl IntoView {
             send,
             ..
         } = use_websocket::<real_time::Request, real_time::Update, MsgpackSerdeCodec>(&format!(
-            "/socket?id={}",
-            host_id
+            "/socket?id={}&jam_id={}&remote={}",
+            host_id, jam_id, remote
         ));
 
         Effect::new(move |_| {
@@ -124,7 +255,8 @@ pub fn HostPage() -> impl IntoView {
 
         let delete_jam = Action::new(move |_: &()| {
             let host_id = host_id.clone();
-            async move { delete_jam(host_id).await }
+            let jam_id = jam_id.clone();
+            async move { delete_jam(host_id, jam_id).await }
         });
         let close = Callback::new(move |_: ()| {
             delete_jam.dispatch(());
@@ -152,19 +284,36 @@ pub fn HostPage() -> impl IntoView {
                 if !update.errors.is_empty() {
                     set_error_message.set(format!("Errors: {:#?}", update.errors));
                 }
+                if update.jam.is_some() {
+                    jam.refetch();
+                }
+                if let Some(percent) = update.volume {
+                    set_volume_update.set(Some(percent));
+                }
+                if let Some(user_id) = update.handover.clone() {
+                    set_handover.set(user_id);
+                }
                 if update.ended.is_some() {
                     close_ws();
                     let navigator = use_navigate();
-                    navigator("/", NavigateOptions::default());
+                    navigator("/dashboard", NavigateOptions::default());
                 }
-                if update.search.is_some() {
-                    warn!("Unexpected search update");
+                if let Some(search_result) = update.search {
+                    set_search_result.set(Some(search_result));
                 }
-                if update.position.is_some() {
-                    warn!("Unexpected position update");
+                if let Some(fallback) = update.fallback {
+                    set_fallback.set(Some(fallback));
                 }
-                if update.current_song.is_some() {
-                    warn!("Unexpected current song update");
+                // the player of the host page knows these better, only the remote gets them
+                match (remote, update.position) {
+                    (true, Some(percentage)) => set_position.set(percentage),
+                    (false, Some(_)) => warn!("Unexpected position update"),
+                    _ => (),
+                }
+                match (remote, update.current_song) {
+                    (true, Some(song)) => set_current_song.set(song),
+                    (false, Some(_)) => warn!("Unexpected current song update"),
+                    _ => (),
                 }
             }
         });
@@ -173,6 +322,33 @@ pub fn HostPage() -> impl IntoView {
     let close = Callback::new(move |_| {
         close.get_untracked().run(());
     });
+    let player = if remote {
+        let paused = Signal::derive(move || {
+            jam.get()
+                .and_then(Result::ok)
+                .map(|jam| jam.state != JamState::Playing)
+                .unwrap_or(false)
+        });
+        Either::Left(view! {
+            <RemoteControls
+                host_id
+                jam_id
+                current_song
+                position
+                paused
+                skip
+                set_paused
+                seek
+                volume
+                set_volume
+            />
+        })
+    } else {
+        Either::Right(view! {
+            <Player host_id jam_id set_song_position skip set_paused seek volume set_volume />
+        })
+    };
+
     view! {
         <Modal visible=Signal::derive(move || {
             error_message.with(|e| !e.is_empty())
@@ -189,9 +365,49 @@ pub fn HostPage() -> impl IntoView {
                 .unwrap_or_default()
         } />
         <div class="host-page">
-            <UsersBar close users kick_user />
+            <UsersBar
+                close
+                users
+                kick_user
+                set_permissions
+                hand_over
+                open_settings=Callback::new(move |_| set_settings_visible.set(true))
+            />
+            <JamSettings
+                jam=Signal::derive(move || jam.get().and_then(Result::ok))
+                visible=settings_visible
+                save=update_settings
+                close=Callback::new(move |_| set_settings_visible.set(false))
+            />
+            {move || {
+                handover
+                    .get()
+                    .map(|user_id| {
+                        let name = users
+                            .with(|users: &Option<Vec<User>>| {
+                                users
+                                    .iter()
+                                    .flatten()
+                                    .find(|user| user.id == user_id)
+                                    .map(|user| user.name.clone())
+                            })
+                            .unwrap_or_default();
+                        view! {
+                            <div class="handover">
+                                {format!("Waiting for {} to take over the jam...", name)}
+                                <button class="button" on:click=cancel_hand_over>
+                                    "Cancel"
+                                </button>
+                            </div>
+                        }
+                    })
+            }}
+            <JamStatus
+                jam=Signal::derive(move || jam.get().and_then(Result::ok))
+                set_state=set_jam_state
+            />
             <div class="center">
-                <Player host_id set_song_position />
+                {player}
                 <SongList
                     songs
                     votes
@@ -204,28 +420,50 @@ pub fn HostPage() -> impl IntoView {
                     })
                 />
 
+                <FallbackList
+                    fallback
+                    search_result
+                    search
+                    import_playlist=import_fallback_playlist
+                    clear=clear_fallback
+                    add_track=add_fallback_track
+                    remove_track=remove_fallback_track
+                    move_track=move_fallback_track
+                    set_shuffle=set_fallback_shuffle
+                />
+
                 <Share jam_id=Signal::derive(move || {
                     jam.get()
                         .map(|jam| jam.map(|jam| jam.id))
                         .unwrap_or(Ok("".to_string()))
                         .unwrap_or_default()
-                }) />
+                })>
+                    <a
+                        class="button tv-link"
+                        target="_blank"
+                        href=move || {
+                            format!("/jam/tv/{}", jam_id.get().unwrap_or_default())
+                        }
+                    >
+                        "TV MODE"
+                    </a>
+                </Share>
             </div>
         </div>
     }
 }
 
+/// the host page navigates away by itself once it gets the ended update
 #[server]
-async fn delete_jam(host_id: String) -> Result<(), ServerFnError> {
+pub async fn delete_jam(host_id: String, jam_id: String) -> Result<(), ServerFnError> {
     use crate::model::{self, check_id_type, notify, AppState};
     let app_state = expect_context::<AppState>();
     let mut transaction = app_state.db.pool.begin().await?;
-    let id = check_id_type(&host_id, &mut transaction).await?;
+    let id = check_id_type(&host_id, Some(&jam_id), &mut transaction).await?;
     if !id.is_host() {
         return Err(ServerFnError::Request("id is not a host id".to_string()));
     }
     model::delete_jam(&id.jam_id, &mut *transaction).await?;
-    leptos_axum::redirect("/");
     use crate::model::real_time::Changed;
     notify(Changed::new().ended(), vec![], &id.jam_id, &mut transaction).await?;
     transaction.commit().await?;
@@ -244,12 +482,16 @@ pub async fn get_jam(jam_id: String) -> Result<Jam, ServerFnError> {
     }
 }
 
+/// the jam id is only needed for hosts with several jams
 #[server]
-pub async fn get_initial_update(id: String) -> Result<real_time::Update, ServerFnError> {
+pub async fn get_initial_update(
+    id: String,
+    jam_id: Option<String>,
+) -> Result<real_time::Update, ServerFnError> {
     use crate::model::{check_id_type, AppState};
     let app_state = expect_context::<AppState>();
     let mut transaction = app_state.db.pool.begin().await?;
-    let id = check_id_type(&id, &mut transaction).await?;
+    let id = check_id_type(&id, jam_id.as_deref(), &mut transaction).await?;
     let update =
         real_time::Update::from_changed(real_time::Changed::all(), &id, &mut transaction).await;
     transaction.commit().await?;
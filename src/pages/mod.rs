@@ -12,3 +12,15 @@ pub use home_page::*;
 
 pub mod host_page;
 pub use host_page::*;
+
+pub mod tv_page;
+pub use tv_page::*;
+
+pub mod dashboard_page;
+pub use dashboard_page::*;
+
+pub mod connect_spotify_page;
+pub use connect_spotify_page::*;
+
+pub mod output_page;
+pub use output_page::*;
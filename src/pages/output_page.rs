@@ -0,0 +1,142 @@
+use super::host_page::get_jam;
+use crate::components::{JamStatus, Modal, host::Player};
+use crate::model::types::*;
+use codee::binary::MsgpackSerdeCodec;
+use gloo::storage::{LocalStorage, Storage};
+use leptos::{logging::*, prelude::*};
+use leptos_meta::Title;
+use leptos_router::{
+    NavigateOptions,
+    hooks::{use_navigate, use_params_map},
+};
+use leptos_use::{UseWebSocketReturn, use_websocket};
+
+/// Only plays the jam, for the computer that is connected to the speakers,
+/// the host moderates from the remote page, so there is nothing else on it
+#[component]
+pub fn OutputPage() -> impl IntoView {
+    let (error_message, set_error_message) = signal(String::new());
+
+    let (host_id, set_host_id) = signal(None::<String>);
+    Effect::new(move |_| {
+        let host_id: String = LocalStorage::get("host_id").unwrap_or_default();
+        if host_id.is_empty() {
+            let navigator = use_navigate();
+            navigator("/", NavigateOptions::default());
+            return;
+        }
+        set_host_id.set(Some(host_id));
+    });
+
+    let jam_id = move || use_params_map().with(|params| params.get("id"));
+    let jam_id = Signal::derive(jam_id);
+
+    let jam = Resource::new(
+        move || jam_id.get(),
+        move |jam_id| async move {
+            match jam_id {
+                Some(jam_id) => get_jam(jam_id).await,
+                None => Err(ServerFnError::Request("jam_id is empty".to_string())),
+            }
+        },
+    );
+
+    let (send_request, set_send_request) = signal(Callback::new(|_: real_time::Request| {
+        warn!("wanted to send a message to ws, but the ws is not ready yet");
+    }));
+
+    let set_song_position = Callback::new(move |(percentage, spotify_id)| {
+        let request = real_time::Request::Position {
+            percentage,
+            spotify_id,
+        };
+        send_request.get_untracked().run(request);
+    });
+    let skip = Callback::new(move |_| {
+        send_request.get_untracked().run(real_time::Request::Skip);
+    });
+    let set_paused = Callback::new(move |paused| {
+        let state = if paused {
+            JamState::Paused
+        } else {
+            JamState::Playing
+        };
+        let request = real_time::Request::SetJamState { state };
+        send_request.get_untracked().run(request);
+    });
+
+    let seek = Callback::new(move |position_ms| {
+        let request = real_time::Request::Seek { position_ms };
+        send_request.get_untracked().run(request);
+    });
+    let set_volume = Callback::new(move |percent| {
+        let request = real_time::Request::Volume { percent };
+        send_request.get_untracked().run(request);
+    });
+    // the volume comes alone when the host changes it, the jam only when the page loads or the settings change
+    let (volume_update, set_volume_update) = signal(None::<u8>);
+    let volume = Signal::derive(move || {
+        volume_update
+            .get()
+            .or_else(|| jam.get().and_then(Result::ok).map(|jam| jam.volume_percent))
+            .unwrap_or(100)
+    });
+
+    Effect::new(move |_| {
+        let (host_id, jam_id) = match (host_id.get(), jam_id.get()) {
+            (Some(host_id), Some(jam_id)) => (host_id, jam_id),
+            _ => return,
+        };
+
+        let UseWebSocketReturn {
+            message,
+            close: close_ws,
+            send,
+            ..
+        } = use_websocket::<real_time::Request, real_time::Update, MsgpackSerdeCodec>(&format!(
+            "/socket?id={}&jam_id={}",
+            host_id, jam_id
+        ));
+        set_send_request.set(Callback::new(move |request| send(&request)));
+
+        Effect::new(move |_| {
+            if let Some(update) = message.get() {
+                if update.jam.is_some() {
+                    jam.refetch();
+                }
+                if let Some(percent) = update.volume {
+                    set_volume_update.set(Some(percent));
+                }
+                if !update.errors.is_empty() {
+                    set_error_message.set(format!("Errors: {:#?}", update.errors));
+                }
+                if update.ended.is_some() {
+                    close_ws();
+                    let navigator = use_navigate();
+                    navigator("/dashboard", NavigateOptions::default());
+                }
+            }
+        });
+    });
+
+    view! {
+        <Modal visible=Signal::derive(move || {
+            error_message.with(|e| !e.is_empty())
+        })>
+            {error_message}
+            <button on:click=move |_| {
+                set_error_message.set(String::new());
+            }>"Close"</button>
+        </Modal>
+        <Title text=move || {
+            jam.get()
+                .map(|jam| jam.map(|jam| jam.name.clone()))
+                .unwrap_or(Ok(String::from("Output")))
+                .unwrap_or_default()
+        } />
+        <div class="output-page">
+            <JamStatus jam=Signal::derive(move || jam.get().and_then(Result::ok)) />
+            <Player host_id jam_id set_song_position skip set_paused seek volume set_volume />
+        </div>
+    }
+}
@@ -0,0 +1,159 @@
+use super::host_page::{get_initial_update, get_jam};
+use crate::components::{JamStatus, Player, Share};
+use crate::model::{
+    ws_client_wrapper::{TvRole, WsClientWrapper},
+    *,
+};
+use leptos::{either::Either, logging::*, prelude::*};
+use leptos_meta::Title;
+use leptos_router::{hooks::*, *};
+
+/// how many songs from the top of the queue are shown on the screen
+const QUEUE_LENGTH: usize = 5;
+
+/// A read only view of the jam, meant to be projected on a big screen,
+/// it connects to the socket with only the jam code, so it can't do anything
+#[component]
+pub fn TvPage() -> impl IntoView {
+    let jam_id = move || use_params_map().with(|params| params.get("id"));
+    let jam_id = Signal::derive(move || jam_id().map(|jam_id| jam_id.to_lowercase()));
+
+    let loaded_jam = Resource::new(jam_id, move |id| async move {
+        if let Some(id) = id {
+            get_jam(id).await
+        } else {
+            Err(ServerFnError::Request("jam_id is empty".to_string()))
+        }
+    });
+
+    let initial_update = Resource::new(jam_id, move |id| async move {
+        if let Some(id) = id {
+            get_initial_update(id, None).await
+        } else {
+            Err(ServerFnError::Request("jam_id is empty".to_string()))
+        }
+    });
+
+    let (client, set_client) = signal(None::<WsClientWrapper<TvRole>>);
+
+    Effect::new(move |_| {
+        let jam_id = match jam_id.get() {
+            Some(jam_id) => jam_id,
+            None => {
+                let navigator = use_navigate();
+                navigator("/", NavigateOptions::default());
+                return;
+            }
+        };
+        let initial_update = Signal::derive(move || initial_update.get());
+        set_client.set(Some(WsClientWrapper::new(jam_id, None, initial_update)));
+    });
+
+    let songs = Signal::derive(move || client.with(|client| client.as_ref()?.songs.get()));
+    let votes = Signal::derive(move || {
+        client.with(|client| {
+            client
+                .as_ref()
+                .map(|client| client.votes.get())
+                .unwrap_or_default()
+        })
+    });
+    let position = Signal::derive(move || {
+        client
+            .with(|client| client.as_ref().map(|client| client.position.get()))
+            .unwrap_or(0.0)
+    });
+    let current_song =
+        Signal::derive(move || client.with(|client| client.as_ref()?.current_song.get()));
+    // the jam only comes with the socket when the settings change, until then the loaded one is shown
+    let jam_update = Signal::derive(move || client.with(|client| client.as_ref()?.jam.get()));
+    let jam = Signal::derive(move || {
+        jam_update
+            .get()
+            .or_else(|| loaded_jam.get().and_then(Result::ok))
+    });
+
+    Effect::new(move |_| {
+        client.with(|client| {
+            let Some(client) = client else {
+                return;
+            };
+            if client.ended.get() {
+                let navigator = use_navigate();
+                navigator("/", NavigateOptions::default());
+            }
+            client.errors.with(|errors| {
+                if !errors.is_empty() {
+                    error!("Errors: {:#?}", errors);
+                }
+            });
+        });
+    });
+
+    let queue = Memo::new(move |_| {
+        let mut songs = songs.get().unwrap_or_default();
+        votes.with(|votes| {
+            for song in songs.iter_mut() {
+                if let Some(vote) = votes.get(song.id.as_deref().unwrap_or("")) {
+                    song.votes = *vote;
+                }
+            }
+        });
+        songs.sort_by(|a, b| b.votes.votes.cmp(&a.votes.votes));
+        songs.truncate(QUEUE_LENGTH);
+        songs
+    });
+
+    view! {
+        <Title text=move || {
+            jam.get().map(|jam| jam.name).unwrap_or_else(|| String::from("Jam"))
+        } />
+        <div class="tv-page">
+            <JamStatus jam />
+            <Player
+                position
+                current_song
+                paused=Signal::derive(move || {
+                    jam.get().map(|jam| jam.state != JamState::Playing)
+                        .unwrap_or(false)
+                })
+            />
+            <div class="up-next">
+                <div class="header">"Up next"</div>
+                {move || {
+                    if queue.with(Vec::is_empty) {
+                        Either::Left(
+                            view! {
+                                <div class="no-songs">
+                                    "The queue is empty, scan the code to add a song"
+                                </div>
+                            },
+                        )
+                    } else {
+                        Either::Right(())
+                    }
+                }}
+                <For
+                    each=move || queue.get().into_iter()
+                    key=|song| (song.id.clone(), song.votes.votes)
+                    children=move |song| {
+                        view! {
+                            <div class="queued-song">
+                                <img
+                                    src=song.image_url.clone()
+                                    alt=format!("This is the album cover of {}", song.name)
+                                />
+                                <div class="info">
+                                    <div class="title">{song.name.clone()}</div>
+                                    <div class="artist">{song.artists.join(", ")}</div>
+                                </div>
+                                <div class="votes">{song.votes.votes}</div>
+                            </div>
+                        }
+                    }
+                />
+            </div>
+            <Share jam_id=Signal::derive(move || jam_id.get().unwrap_or_default()) />
+        </div>
+    }
+}
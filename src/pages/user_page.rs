@@ -1,13 +1,16 @@
 use std::ops::Deref;
 
 use super::host_page::get_jam;
-use crate::components::{Player, SongList, SongListAction, UsersBar, user::Search};
+use crate::components::{
+    JamSettings, JamStatus, Modal, Player, PlayingPreview, SongList, SongListAction, UsersBar,
+    user::{CreditsBalance, Search},
+};
 use crate::model::{self, *};
 use crate::pages::host_page::get_initial_update;
 use codee::binary::MsgpackSerdeCodec;
 use gloo::storage::{LocalStorage, Storage};
 use itertools::Itertools;
-use leptos::{logging::*, prelude::*};
+use leptos::{either::Either, logging::*, prelude::*};
 use leptos_meta::Title;
 use leptos_router::{hooks::*, *};
 use leptos_use::{UseWebSocketReturn, core::ConnectionReadyState, use_websocket};
@@ -26,6 +29,9 @@ pub fn UserPage() -> impl IntoView {
     });
     Effect::new(move |_| log!("jam_id:{:?}", jam_id.get()));
 
+    // only one song preview plays at a time, across the search results and the queue
+    provide_context(PlayingPreview::new());
+
     let jam = Resource::new(jam_id, move |id| async move {
         if let Some(id) = id {
             get_jam(id).await
@@ -65,7 +71,7 @@ pub fn UserPage() -> impl IntoView {
 
     let initial_update = Resource::new(user_id, move |id| async move {
         if let Some(id) = id {
-            get_initial_update(id).await
+            get_initial_update(id, None).await
         } else {
             Err(ServerFnError::Request("user_id is empty".to_string()))
         }
@@ -77,6 +83,7 @@ pub fn UserPage() -> impl IntoView {
     let (users, set_users) = signal(None);
     let (position, set_position) = signal(0.0);
     let (current_song, set_current_song) = signal(None);
+    let (credits, set_credits) = signal(None::<Credits>);
     let (ready_state, set_ready_state) = signal(ConnectionReadyState::Connecting);
 
     let (send_request, set_send_request) = signal(Callback::new(|_: real_time::Request| {
@@ -86,16 +93,25 @@ pub fn UserPage() -> impl IntoView {
         warn!("wanted to close ws, but the ws is not ready yet");
     }));
 
-    let search = move |query_id: (String, String)| {
-        let request = real_time::Request::Search {
-            query: query_id.0,
-            id: query_id.1,
-        };
+    let search = move |(query, offset, id): (real_time::SearchQuery, u32, String)| {
+        let request = query.into_request(offset, id);
         send_request.get_untracked().run(request);
     };
     let search = Callback::new(search);
 
-    let add_song = move |song_id: String| {
+    let has_credits = Memo::new(move |_| {
+        jam.get()
+            .and_then(Result::ok)
+            .map(|jam| jam.song_limit == SongLimit::Credits)
+            .unwrap_or(false)
+    });
+
+    // none in jams with credits, the server checks the balance, the songs don't tell how many credits the user has left
+    let songs_left = Memo::new(move |_| {
+        if has_credits.get() {
+            return None;
+        }
+
         let your_song_count = songs
             .get()
             .as_ref()
@@ -104,7 +120,7 @@ pub fn UserPage() -> impl IntoView {
                     .iter()
                     .filter(|song| {
                         if let Some(id) = &song.user_id
-                            && let Some(user_id) = user_id.get_untracked()
+                            && let Some(user_id) = user_id.get()
                         {
                             *id == user_id
                         } else {
@@ -115,18 +131,21 @@ pub fn UserPage() -> impl IntoView {
             })
             .unwrap_or(0);
 
-        if jam
+        let max_song_count = jam
             .get()
             .map(|jam| jam.map(|jam| jam.max_song_count))
             .unwrap_or(Ok(0))
-            .unwrap_or_default()
-            > your_song_count as u8
-        {
-            let request = real_time::Request::AddSong { song_id };
-            send_request.get_untracked().run(request);
-        } else {
+            .unwrap_or_default();
+        Some((max_song_count as u32).saturating_sub(your_song_count as u32))
+    });
+
+    let add_song = move |song_id: String| {
+        if songs_left.get_untracked() == Some(0) {
             warn!("You have reached the maximum song count");
+            return;
         }
+        let request = real_time::Request::AddSong { song_id };
+        send_request.get_untracked().run(request);
     };
     let add_song = Callback::new(add_song);
 
@@ -150,6 +169,90 @@ pub fn UserPage() -> impl IntoView {
     };
     let remove_song = Callback::new(remove_song);
 
+    let boost_song = move |song_id: String| {
+        let request = real_time::Request::BoostSong { song_id };
+        send_request.get_untracked().run(request);
+    };
+    let boost_song = Callback::new(boost_song);
+
+    let kick_user = move |id| {
+        let request = real_time::Request::KickUser { user_id: id };
+        send_request.get_untracked().run(request);
+    };
+    let kick_user = Callback::new(kick_user);
+
+    let skip = move |_: ()| {
+        send_request.get_untracked().run(real_time::Request::Skip);
+    };
+
+    let set_jam_state = move |state| {
+        let request = real_time::Request::SetJamState { state };
+        send_request.get_untracked().run(request);
+    };
+    let set_jam_state = Callback::new(set_jam_state);
+
+    let (settings_visible, set_settings_visible) = signal(false);
+    let update_settings =
+        move |(name, max_song_count, no_repeat_minutes, song_limit, external_changes)| {
+            let request = real_time::Request::UpdateSettings {
+                name,
+                max_song_count,
+                no_repeat_minutes,
+                song_limit,
+                external_changes,
+            };
+            send_request.get_untracked().run(request);
+        };
+    let update_settings = Callback::new(update_settings);
+
+    let (handover, set_handover) = signal(None::<String>);
+    let is_offered_the_jam = Memo::new(move |_| {
+        handover.with(|handover| handover.is_some() && *handover == user_id.get())
+    });
+    let decline_hand_over = move |_| {
+        send_request
+            .get_untracked()
+            .run(real_time::Request::DeclineHandOver);
+    };
+    let accept_hand_over = Action::new(move |_: &()| async move {
+        if let Some(user_id) = user_id.get_untracked() {
+            if let Err(e) = accept_handover(user_id).await {
+                error!("Error accepting handover: {:#?}", e);
+            }
+        }
+    });
+
+    let spotify_connected = Resource::new(
+        move || user_id.get(),
+        |user_id| async move {
+            match user_id {
+                Some(user_id) => is_spotify_connected(user_id).await.unwrap_or(false),
+                None => false,
+            }
+        },
+    );
+    let connect_spotify_action = Action::new(move |_: &()| async move {
+        if let Some(user_id) = user_id.get_untracked()
+            && let Err(e) = connect_spotify(user_id).await
+        {
+            error!("Error logging in with spotify: {:#?}", e);
+        }
+    });
+    let disconnect_spotify_action = Action::new(move |_: &()| async move {
+        if let Some(user_id) = user_id.get_untracked() {
+            if let Err(e) = disconnect_spotify(user_id).await {
+                error!("Error logging out of spotify: {:#?}", e);
+            }
+            spotify_connected.refetch();
+        }
+    });
+    let connect_spotify = Callback::new(move |_| {
+        connect_spotify_action.dispatch(());
+    });
+    let disconnect_spotify = Callback::new(move |_| {
+        disconnect_spotify_action.dispatch(());
+    });
+
     let leave = move || {
         let request = real_time::Request::KickUser {
             user_id: user_id.get_untracked(),
@@ -236,6 +339,15 @@ pub fn UserPage() -> impl IntoView {
                 if let Some(song) = update.current_song {
                     set_current_song.set(song);
                 }
+                if let Some(credits) = update.credits {
+                    set_credits.set(Some(credits));
+                }
+                if update.jam.is_some() {
+                    jam.refetch();
+                }
+                if let Some(user_id) = update.handover {
+                    set_handover.set(user_id);
+                }
                 if update.ended.is_some() {
                     close_ws.run(());
                     delete_user_id_from_local_storage.run(());
@@ -263,6 +375,17 @@ pub fn UserPage() -> impl IntoView {
         close.get().run(());
     });
 
+    let my_permissions = Memo::new(move |_| {
+        users.with(|users: &Option<Vec<User>>| {
+            users
+                .iter()
+                .flatten()
+                .find(|user| Some(&user.id) == user_id.read().as_ref())
+                .map(|user| user.permissions)
+                .unwrap_or_default()
+        })
+    });
+
     view! {
         <Title text=move || {
             jam.value()
@@ -272,34 +395,183 @@ pub fn UserPage() -> impl IntoView {
                 .unwrap_or_default()
         } />
         <div class="user-page">
-            <UsersBar users close />
+            {move || {
+                let permissions = my_permissions.get();
+                view! {
+                    <UsersBar
+                        users
+                        close
+                        kick_user=permissions.kick.then_some(kick_user)
+                        open_settings=permissions
+                            .change_settings
+                            .then_some(Callback::new(move |_| set_settings_visible.set(true)))
+                    />
+                }
+            }}
+            <JamSettings
+                jam=Signal::derive(move || jam.get().and_then(Result::ok))
+                visible=settings_visible
+                save=update_settings
+                close=Callback::new(move |_| set_settings_visible.set(false))
+            />
+            {move || {
+                view! {
+                    <JamStatus
+                        jam=Signal::derive(move || jam.get().and_then(Result::ok))
+                        set_state=my_permissions
+                            .get()
+                            .change_settings
+                            .then_some(set_jam_state)
+                    />
+                }
+            }}
+            <Modal visible=is_offered_the_jam>
+                <div class="handover">
+                    "The host wants to hand the jam over to you, log in with spotify to become the new host"
+                    <div class="buttons">
+                        <button
+                            class="button"
+                            on:click=move |_| {
+                                accept_hand_over.dispatch(());
+                            }
+                        >
+                            "Accept"
+                        </button>
+                        <button class="button" on:click=decline_hand_over>
+                            "Decline"
+                        </button>
+                    </div>
+                </div>
+            </Modal>
             <div class="center">
                 <Search
                     search_result
                     search
                     add_song
                     loaded=Signal::derive(move || ready_state.get() == ConnectionReadyState::Open)
+                    songs_left
+                    spotify_connected=Signal::derive(move || {
+                        spotify_connected.get().unwrap_or(false)
+                    })
+                    connect_spotify
+                    disconnect_spotify
                 />
-                <SongList
-                    songs
-                    votes
-                    song_list_action=SongListAction::Vote {
-                        add_vote,
-                        remove_vote,
-                        remove_song,
+                <CreditsBalance credits=Signal::derive(move || {
+                    credits.get().filter(|_| has_credits.get())
+                }) />
+                {move || {
+                    view! {
+                        <SongList
+                            songs
+                            votes
+                            song_list_action=SongListAction::Vote {
+                                add_vote,
+                                remove_vote,
+                                remove_song,
+                                boost: has_credits.get().then_some(boost_song),
+                            }
+
+                            max_song_count=Signal::derive(move || {
+                                jam.value()
+                                    .get()
+                                    .map(|jam| jam.map(|jam| jam.max_song_count))
+                                    .unwrap_or(Ok(0))
+                                    .unwrap_or_default()
+                            })
+                        />
                     }
+                }}
 
-                    max_song_count=Signal::derive(move || {
-                        jam.value()
-                            .get()
-                            .map(|jam| jam.map(|jam| jam.max_song_count))
-                            .unwrap_or(Ok(0))
-                            .unwrap_or_default()
+                <Player
+                    position
+                    current_song
+                    paused=Signal::derive(move || {
+                        jam.get()
+                            .and_then(Result::ok)
+                            .map(|jam| jam.state != JamState::Playing)
+                            .unwrap_or(false)
                     })
-                />
-
-                <Player position current_song />
+                >
+                    {move || {
+                        if my_permissions.with(|permissions| permissions.skip) {
+                            Either::Left(
+                                view! {
+                                    <button on:click=move |_| skip(()) class="skip" title="skip">
+                                        <svg
+                                            viewBox=icondata::BsSkipEndFill.view_box
+                                            inner_html=icondata::BsSkipEndFill.data
+                                        ></svg>
+                                    </button>
+                                },
+                            )
+                        } else {
+                            Either::Right(())
+                        }
+                    }}
+                </Player>
             </div>
         </div>
     }
 }
+
+/// creates the new host and redirects the user to spotify, the jam is handed over once spotify redirects back
+#[server]
+async fn accept_handover(user_id: String) -> Result<(), ServerFnError> {
+    use crate::model::{AppState, check_id_type, functions, spotify_authorize_url};
+    let app_state = expect_context::<AppState>();
+    let mut transaction = app_state.db.pool.begin().await?;
+    let id = check_id_type(&user_id, None, &mut transaction).await?;
+    if !id.is_user() {
+        return Err(ServerFnError::Request("id is not a user id".to_string()));
+    }
+    let host_id = match functions::accept_handover(&user_id, &mut transaction).await {
+        Ok(host_id) => host_id,
+        Err(e) => return Err(ServerFnError::Request(e.to_string())),
+    };
+    transaction.commit().await?;
+    leptos_axum::redirect(&spotify_authorize_url(
+        &app_state.spotify_credentials,
+        &app_state.site_url,
+        &host_id,
+    ));
+    Ok(())
+}
+
+/// redirects the user to spotify to log in, so they can add songs from their own library
+#[server]
+async fn connect_spotify(user_id: String) -> Result<(), ServerFnError> {
+    use crate::model::{AppState, check_id_type, spotify_user_authorize_url};
+    let app_state = expect_context::<AppState>();
+    let mut transaction = app_state.db.pool.begin().await?;
+    let id = check_id_type(&user_id, None, &mut transaction).await?;
+    if !id.is_user() {
+        return Err(ServerFnError::Request("id is not a user id".to_string()));
+    }
+    leptos_axum::redirect(&spotify_user_authorize_url(
+        &app_state.spotify_credentials,
+        &app_state.site_url,
+        &user_id,
+    ));
+    Ok(())
+}
+
+#[server]
+async fn is_spotify_connected(user_id: String) -> Result<bool, ServerFnError> {
+    use crate::model::{AppState, functions};
+    let app_state = expect_context::<AppState>();
+    match functions::is_user_spotify_connected(&user_id, &app_state.db.pool).await {
+        Ok(connected) => Ok(connected),
+        Err(e) => Err(ServerFnError::ServerError(e.to_string())),
+    }
+}
+
+/// forgets the spotify token of the user, the songs they added stay in the jam
+#[server]
+async fn disconnect_spotify(user_id: String) -> Result<(), ServerFnError> {
+    use crate::model::{AppState, functions};
+    let app_state = expect_context::<AppState>();
+    match functions::disconnect_user_spotify(&user_id, &app_state.db.pool).await {
+        Ok(()) => Ok(()),
+        Err(e) => Err(ServerFnError::ServerError(e.to_string())),
+    }
+}
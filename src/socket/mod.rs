@@ -21,15 +21,27 @@ pub async fn socket(
     State(state): State<AppState>,
 ) -> Response {
     println!("ws: {:?}", id);
-    ws.on_upgrade(|socket| handle_socket(socket, state, id.id))
+    ws.on_upgrade(move |socket| handle_socket(socket, state, id.id, id.jam_id, id.remote))
 }
 
 #[derive(Debug, serde::Deserialize)]
 pub struct QueryId {
     pub id: String,
+    /// which jam the connection targets, only needed for hosts with several jams
+    pub jam_id: Option<String>,
+    /// the host controls the jam from the remote page, which has no player,
+    /// so it gets the position and the current song from the server like users do
+    #[serde(default)]
+    pub remote: bool,
 }
 
-async fn handle_socket(socket: WebSocket, app_state: AppState, id: String) {
+async fn handle_socket(
+    socket: WebSocket,
+    app_state: AppState,
+    id: String,
+    jam_id: Option<String>,
+    remote: bool,
+) {
     let (sender, receiver) = socket.split();
     let (mpsc_sender, mpsc_receiver) = mpsc::channel(3);
 
@@ -47,7 +59,7 @@ async fn handle_socket(socket: WebSocket, app_state: AppState, id: String) {
         }
     };
 
-    let id = match check_id_type(&id, &mut transaction).await {
+    let id = match check_id_type(&id, jam_id.as_deref(), &mut transaction).await {
         Ok(id) => id,
         Err(e) => {
             eprintln!("Error checking id type: {:?}", e);
@@ -69,12 +81,22 @@ async fn handle_socket(socket: WebSocket, app_state: AppState, id: String) {
         app_state.clone(),
     ));
 
+    let has_player = id.is_host() && !remote;
     let send_task = tokio::spawn(write::write(
         mpsc_sender.clone(),
         id.clone(),
+        has_player,
         app_state.clone(),
     ));
 
+    if id.is_host() {
+        app_state.jam_clocks.start(
+            id.jam_id.clone(),
+            pool.clone(),
+            spotify_credentials.clone(),
+        );
+    }
+
     let checkup = if id.is_host() {
         let handle = tokio::spawn(occasional_notify(
             pool.clone(),
@@ -101,9 +123,18 @@ async fn occasional_notify(
     jam_id: String,
     spotify_credentials: SpotifyCredentials,
 ) -> Result<(), Error> {
-    use std::time::Duration;
+    use std::time::{Duration, Instant};
+    /// asking spotify what the player is playing counts against the rate limit, so it is done less often
+    const PLAYER_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+    let mut last_player_check: Option<Instant> = None;
     while dose_jam_exist(&jam_id, &pool).await.unwrap_or(true) {
         println!("Occasional notify");
+        let check_player = last_player_check
+            .map(|checked| checked.elapsed() >= PLAYER_CHECK_INTERVAL)
+            .unwrap_or(true);
+        if check_player {
+            last_player_check = Some(Instant::now());
+        }
 
         let mut transaction = match pool.begin().await {
             Ok(transaction) => transaction,
@@ -122,15 +153,20 @@ async fn occasional_notify(
             let spotify_credentials = spotify_credentials.clone();
             let jam_id = jam_id.clone();
             async move {
-                play_the_current_song_if_player_is_not_playing_it(
-                    jam_id,
-                    &mut transaction,
-                    spotify_credentials,
-                )
-                .await
-                .unwrap_or_else(|e| {
-                    eprintln!("Error playing current song in occasional notify: {:?}", e);
-                });
+                // skips, pauses and songs picked in the spotify app are adopted or overridden here
+                if check_player {
+                    match sync_with_player(&jam_id, &mut transaction, spotify_credentials).await {
+                        Ok(changed) => {
+                            if let Err(e) = notify(changed, vec![], &jam_id, &mut transaction).await
+                            {
+                                eprintln!("Error notifying the changes of the player: {:?}", e);
+                            }
+                        }
+                        Err(e) => {
+                            eprintln!("Error syncing with the player in occasional notify: {:?}", e)
+                        }
+                    }
+                }
                 if let Err(e) = transaction.commit().await {
                     eprintln!("Error committing transaction in occasional notify: {:?}", e);
                 }
@@ -143,35 +179,6 @@ async fn occasional_notify(
     Ok(())
 }
 
-async fn play_the_current_song_if_player_is_not_playing_it<'e>(
-    jam_id: String,
-    transaction: &mut sqlx::Transaction<'e, sqlx::Postgres>,
-    credentials: SpotifyCredentials,
-) -> Result<(), Error> {
-    use crate::model::functions::{get_current_song_from_player, play_song};
-    let jam_id = &jam_id;
-    let current_song = get_current_song(jam_id, &mut **transaction).await?;
-    let song = match current_song {
-        Some(song) => song,
-        None => return Ok(()),
-    };
-    let player_current_song =
-        get_current_song_from_player(jam_id, transaction, credentials.clone()).await?;
-    if player_current_song
-        .as_ref()
-        .map(|s| s.spotify_id != song.spotify_id)
-        .unwrap_or(true)
-    {
-        println!(
-            "playing song: {:?}, set away from: {}",
-            song.name,
-            player_current_song.map(|s| s.name).unwrap_or_default()
-        );
-        play_song(&song.spotify_id, jam_id, transaction, credentials).await?;
-    }
-    Ok(())
-}
-
 async fn handle_error(error: Error, close: bool, sender: &mpsc::Sender<ws::Message>) {
     eprintln!("Error: {:?}", error);
 
@@ -2,8 +2,10 @@ use super::handle_error;
 use crate::model::*;
 use axum::extract::ws::{self, WebSocket};
 use futures_util::{StreamExt, stream::SplitStream};
-use real_time::SearchResult;
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, watch};
+
+/// searches are sent on every keystroke, so the server waits a bit before asking spotify
+const SEARCH_DEBOUNCE: std::time::Duration = std::time::Duration::from_millis(300);
 
 pub async fn read(
     mut receiver: SplitStream<WebSocket>,
@@ -13,6 +15,8 @@ pub async fn read(
 ) {
     let pool = &app_state.db.pool.clone();
     let credentials = app_state.spotify_credentials;
+    // the id of the newest search of this connection, older searches are dropped
+    let (latest_search, _) = watch::channel(String::new());
 
     while let Some(message) = receiver.next().await {
         let message = match message {
@@ -22,6 +26,18 @@ pub async fn read(
                 break;
             }
         };
+        // decoded here, so the newest search is known before the messages are handled in parallel
+        let message: types::real_time::Request = match rmp_serde::from_slice(&message.into_data()) {
+            Ok(m) => m,
+            Err(e) => {
+                let error = Error::Decode(format!("Error decoding message sent in ws: {:#?}", e));
+                handle_error(error, true, &sender).await;
+                continue;
+            }
+        };
+        if let Some(search_id) = message.search_id() {
+            latest_search.send_replace(search_id.to_string());
+        }
 
         tokio::spawn(handle_message(
             message,
@@ -29,16 +45,20 @@ pub async fn read(
             id.clone(),
             pool.clone(),
             credentials.clone(),
+            app_state.search_cache.clone(),
+            latest_search.subscribe(),
         ));
     }
 }
 
 async fn handle_message(
-    message: ws::Message,
+    message: types::real_time::Request,
     sender: mpsc::Sender<ws::Message>,
     id: Id,
     pool: sqlx::PgPool,
     credentials: SpotifyCredentials,
+    search_cache: SearchCache,
+    latest_search: watch::Receiver<String>,
 ) {
     let mut transaction = match pool.begin().await {
         Ok(t) => t,
@@ -48,15 +68,15 @@ async fn handle_message(
             return;
         }
     };
-    let message: types::real_time::Request = match rmp_serde::from_slice(&message.into_data()) {
-        Ok(m) => m,
-        Err(e) => {
-            use Error;
-            let error = Error::Decode(format!("Error decoding message sent in ws: {:#?}", e));
-            handle_error(error, true, &sender).await;
-            return;
-        }
-    };
+    if id.is_general() {
+        let error = Error::Forbidden(
+            "Spectators can only watch the jam, this is a bug, terminating socket connection"
+                .to_string(),
+        );
+        handle_error(error, true, &sender).await;
+        return;
+    }
+
     let mut changed = real_time::Changed::new();
     let mut errors: Vec<Error> = Vec::new();
 
@@ -73,12 +93,18 @@ async fn handle_message(
                     return;
                 }
             };
-            if !(&user_id == your_id || user_id.is_empty()) && id.is_user() {
-                let error = Error::Forbidden(
-                    "A user only can kick themselves, this is a bug, terminating socket connection"
-                        .to_string(),
-                );
-                handle_error(error, true, &sender).await;
+            if !(&user_id == your_id || user_id.is_empty())
+                && id.is_user()
+                && only_host_or_co_host(
+                    &id,
+                    |permissions| permissions.kick,
+                    "A user only can kick themselves, unless they are a co-host with the kick permission",
+                    &mut transaction,
+                    &sender,
+                )
+                .await
+                .is_err()
+            {
                 return;
             }
             if user_id.is_empty() && id.is_host() {
@@ -97,7 +123,7 @@ async fn handle_message(
                 }
             }
             if errors.is_empty() {
-                match kick_user(user_id, &mut *transaction).await {
+                match kick_user(user_id, id.jam_id(), &mut *transaction).await {
                     Ok(changed_new) => {
                         changed = changed.merge_with_other(changed_new);
                     }
@@ -137,7 +163,19 @@ async fn handle_message(
             };
         }
         real_time::Request::RemoveSong { song_id } => {
-            match remove_song(&song_id, &id, &mut transaction).await {
+            let can_remove_any_song = match &id.id {
+                IdType::Host(_) => true,
+                IdType::User(user_id) => match get_permissions(user_id, &mut *transaction).await {
+                    Ok(permissions) => permissions.remove_songs,
+                    Err(e) => {
+                        errors.push(e);
+                        false
+                    }
+                },
+                IdType::General => false,
+            };
+
+            match remove_song(&song_id, &id, can_remove_any_song, &mut transaction).await {
                 Ok(changed_new) => {
                     changed = changed.merge_with_other(changed_new);
                 }
@@ -158,7 +196,7 @@ async fn handle_message(
                 Err(_) => return,
             };
 
-            match add_vote(&song_id, id, &mut *transaction).await {
+            match add_vote(&song_id, id, &mut transaction).await {
                 Ok(changed_new) => {
                     changed = changed.merge_with_other(changed_new);
                 }
@@ -180,7 +218,7 @@ async fn handle_message(
                 Err(_) => return,
             };
 
-            match remove_vote(&song_id, your_id, &mut *transaction).await {
+            match remove_vote(&song_id, your_id, &mut transaction).await {
                 Ok(changed_new) => {
                     changed = changed.merge_with_other(changed_new);
                 }
@@ -190,13 +228,42 @@ async fn handle_message(
                 }
             };
         }
+        real_time::Request::BoostSong { song_id } => {
+            let your_id = match only_user(
+                &id,
+                "Only users can boost songs, this is a bug, terminating socket connection",
+                &sender,
+            )
+            .await
+            {
+                Ok(id) => id,
+                Err(_) => return,
+            };
+
+            match boost_song(&song_id, your_id, id.jam_id(), &mut transaction).await {
+                Ok(changed_new) => {
+                    changed = changed.merge_with_other(changed_new);
+                }
+                Err(e) => {
+                    errors.push(e);
+                }
+            };
+        }
         real_time::Request::Search {
             query,
             id: search_id,
+            kind,
         } => {
-            if only_user(
+            let query = real_time::SearchQuery::Text { query, kind };
+            if send_search_result(
+                query,
+                0,
+                search_id,
                 &id,
-                "Only users can search, this is a bug, terminating socket connection",
+                &mut transaction,
+                credentials.clone(),
+                &search_cache,
+                &latest_search,
                 &sender,
             )
             .await
@@ -204,49 +271,397 @@ async fn handle_message(
             {
                 return;
             }
+        }
+        real_time::Request::OpenSearchCollection {
+            collection,
+            id: search_id,
+        } => {
+            let query = real_time::SearchQuery::Collection(collection);
+            if send_search_result(
+                query,
+                0,
+                search_id,
+                &id,
+                &mut transaction,
+                credentials.clone(),
+                &search_cache,
+                &latest_search,
+                &sender,
+            )
+            .await
+            .is_err()
+            {
+                return;
+            }
+        }
+        real_time::Request::BrowseLibrary {
+            source,
+            id: search_id,
+        } => {
+            let query = real_time::SearchQuery::Library(source);
+            if send_search_result(
+                query,
+                0,
+                search_id,
+                &id,
+                &mut transaction,
+                credentials.clone(),
+                &search_cache,
+                &latest_search,
+                &sender,
+            )
+            .await
+            .is_err()
+            {
+                return;
+            }
+        }
+        real_time::Request::LoadMoreSearchResults {
+            query,
+            offset,
+            id: search_id,
+        } => {
+            if send_search_result(
+                query,
+                offset,
+                search_id,
+                &id,
+                &mut transaction,
+                credentials.clone(),
+                &search_cache,
+                &latest_search,
+                &sender,
+            )
+            .await
+            .is_err()
+            {
+                return;
+            }
+        }
+        real_time::Request::Position {
+            percentage,
+            spotify_id,
+        } => {
+            if only_host(
+                &id,
+                "Only a host can update the current position of a song, this is a bug, terminating socket connection",
+                &sender,
+            )
+            .await
+            .is_err() {
+                return;
+            }
 
-            let songs =
-                match search(&query, &mut transaction, id.jam_id(), credentials.clone()).await {
-                    Ok(songs) => songs,
-                    Err(e) => {
-                        handle_error(e, false, &sender).await;
-                        return;
-                    }
-                };
+            match report_song_position(id.jam_id(), percentage, &spotify_id, &mut transaction).await
+            {
+                Ok(changed_new) => {
+                    changed = changed.merge_with_other(changed_new);
+                }
+                Err(e) => {
+                    errors.push(e);
+                }
+            };
+        }
+        real_time::Request::SetPermissions {
+            user_id,
+            permissions,
+        } => {
+            if only_host(
+                &id,
+                "Only the host can change the permissions of users, this is a bug, terminating socket connection",
+                &sender,
+            )
+            .await
+            .is_err()
+            {
+                return;
+            }
 
-            let update = real_time::Update::new().search(SearchResult { songs, search_id });
-            let message = match rmp_serde::to_vec(&update) {
-                Ok(m) => m,
+            match set_permissions(&user_id, id.jam_id(), permissions, &mut transaction).await {
+                Ok(changed_new) => {
+                    changed = changed.merge_with_other(changed_new);
+                }
                 Err(e) => {
-                    let error = Error::Decode(format!("Error encoding search result: {:#?}", e));
-                    handle_error(error, true, &sender).await;
-                    return;
+                    errors.push(e);
                 }
             };
-            let message = ws::Message::Binary(message);
-            if let Err(e) = sender.send(message).await {
-                eprintln!("Error sending ws message: {:?}", e);
+        }
+        real_time::Request::Skip => {
+            if only_host_or_co_host(
+                &id,
+                |permissions| permissions.skip,
+                "Only the host and co-hosts with the skip permission can skip songs",
+                &mut transaction,
+                &sender,
+            )
+            .await
+            .is_err()
+            {
                 return;
             }
+
+            // read before the jam is locked, so a skip that waited for the clock doesn't skip the next song too
+            let result = match get_current_song(id.jam_id(), &mut *transaction).await {
+                Ok(song) => {
+                    let skipped_song_id = song.and_then(|song| song.id);
+                    go_to_next_song(
+                        id.jam_id(),
+                        skipped_song_id.as_deref(),
+                        &mut transaction,
+                        credentials.clone(),
+                    )
+                    .await
+                }
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(changed_new) => {
+                    changed = changed.merge_with_other(changed_new.position());
+                }
+                Err(e) => {
+                    errors.push(e);
+                }
+            };
         }
-        real_time::Request::Position { percentage } => {
+        real_time::Request::Seek { position_ms } => {
+            // only the host, the controls are only on the pages of the host, and co-hosts have no permission for it
             if only_host(
                 &id,
-                "Only a host can update the current position of a song, this is a bug, terminating socket connection",
+                "Only the host can seek, this is a bug, terminating socket connection",
                 &sender,
             )
             .await
-            .is_err() {
+            .is_err()
+            {
                 return;
             }
 
-            match set_current_song_position(
+            match seek_song(
                 id.jam_id(),
-                percentage,
+                position_ms,
+                &mut transaction,
                 credentials.clone(),
+            )
+            .await
+            {
+                Ok(changed_new) => {
+                    changed = changed.merge_with_other(changed_new);
+                }
+                Err(e) => {
+                    errors.push(e);
+                }
+            };
+        }
+        real_time::Request::Volume { percent } => {
+            // only the host, the controls are only on the pages of the host, and co-hosts have no permission for it
+            if only_host(
+                &id,
+                "Only the host can change the volume, this is a bug, terminating socket connection",
+                &sender,
+            )
+            .await
+            .is_err()
+            {
+                return;
+            }
+
+            match set_volume(id.jam_id(), percent, &mut transaction, credentials.clone()).await {
+                Ok(changed_new) => {
+                    changed = changed.merge_with_other(changed_new);
+                }
+                Err(e) => {
+                    errors.push(e);
+                }
+            };
+        }
+        real_time::Request::UpdateSettings {
+            name,
+            max_song_count,
+            no_repeat_minutes,
+            song_limit,
+            external_changes,
+        } => {
+            if only_host_or_co_host(
+                &id,
+                |permissions| permissions.change_settings,
+                "Only the host and co-hosts with the change settings permission can change the settings of the jam",
+                &mut transaction,
+                &sender,
+            )
+            .await
+            .is_err()
+            {
+                return;
+            }
+
+            match update_jam_settings(
+                id.jam_id(),
+                &name,
+                max_song_count,
+                no_repeat_minutes,
+                song_limit,
+                external_changes,
+                &mut *transaction,
+            )
+            .await
+            {
+                Ok(changed_new) => {
+                    changed = changed.merge_with_other(changed_new);
+                }
+                Err(e) => {
+                    errors.push(e);
+                }
+            };
+        }
+        real_time::Request::SetJamState { state } => {
+            if only_host_or_co_host(
+                &id,
+                |permissions| permissions.change_settings,
+                "Only the host and co-hosts with the change settings permission can start the jam",
+                &mut transaction,
+                &sender,
+            )
+            .await
+            .is_err()
+            {
+                return;
+            }
+
+            match set_jam_state(id.jam_id(), state, &mut transaction, credentials.clone()).await {
+                Ok(changed_new) => {
+                    changed = changed.merge_with_other(changed_new);
+                }
+                Err(e) => {
+                    errors.push(e);
+                }
+            };
+        }
+        real_time::Request::HandOver { user_id } => {
+            if only_host(
+                &id,
+                "Only the host can hand over the jam, this is a bug, terminating socket connection",
+                &sender,
+            )
+            .await
+            .is_err()
+            {
+                return;
+            }
+
+            match offer_handover(id.jam_id(), &user_id, &mut *transaction).await {
+                Ok(changed_new) => {
+                    changed = changed.merge_with_other(changed_new);
+                }
+                Err(e) => {
+                    errors.push(e);
+                }
+            };
+        }
+        real_time::Request::CancelHandOver => {
+            if only_host(
+                &id,
+                "Only the host can cancel a handover, this is a bug, terminating socket connection",
+                &sender,
+            )
+            .await
+            .is_err()
+            {
+                return;
+            }
+
+            match cancel_handover(id.jam_id(), &mut *transaction).await {
+                Ok(changed_new) => {
+                    changed = changed.merge_with_other(changed_new);
+                }
+                Err(e) => {
+                    errors.push(e);
+                }
+            };
+        }
+        real_time::Request::DeclineHandOver => {
+            let user_id = match only_user(
+                &id,
+                "Only users can decline a handover, this is a bug, terminating socket connection",
+                &sender,
+            )
+            .await
+            {
+                Ok(user_id) => user_id,
+                Err(_) => return,
+            };
+
+            match decline_handover(user_id, &mut *transaction).await {
+                Ok(changed_new) => {
+                    changed = changed.merge_with_other(changed_new);
+                }
+                Err(e) => {
+                    errors.push(e);
+                }
+            };
+        }
+        real_time::Request::ImportFallbackPlaylist { playlist } => {
+            if only_host(
+                &id,
+                "Only the host can change the fallback list, this is a bug, terminating socket connection",
+                &sender,
+            )
+            .await
+            .is_err()
+            {
+                return;
+            }
+
+            match import_fallback_playlist(
+                &playlist,
+                id.jam_id(),
                 &mut transaction,
+                credentials.clone(),
+            )
+            .await
+            {
+                Ok(changed_new) => {
+                    changed = changed.merge_with_other(changed_new);
+                }
+                Err(e) => {
+                    errors.push(e);
+                }
+            };
+        }
+        real_time::Request::ClearFallback => {
+            if only_host(
+                &id,
+                "Only the host can change the fallback list, this is a bug, terminating socket connection",
+                &sender,
+            )
+            .await
+            .is_err()
+            {
+                return;
+            }
+
+            match clear_fallback(id.jam_id(), &mut transaction).await {
+                Ok(changed_new) => {
+                    changed = changed.merge_with_other(changed_new);
+                }
+                Err(e) => {
+                    errors.push(e);
+                }
+            };
+        }
+        real_time::Request::AddFallbackTrack { song_id } => {
+            if only_host(
+                &id,
+                "Only the host can change the fallback list, this is a bug, terminating socket connection",
+                &sender,
             )
             .await
+            .is_err()
+            {
+                return;
+            }
+
+            match add_fallback_track(&song_id, id.jam_id(), &mut transaction, credentials.clone())
+                .await
             {
                 Ok(changed_new) => {
                     changed = changed.merge_with_other(changed_new);
@@ -256,6 +671,69 @@ async fn handle_message(
                 }
             };
         }
+        real_time::Request::RemoveFallbackTrack { track_id } => {
+            if only_host(
+                &id,
+                "Only the host can change the fallback list, this is a bug, terminating socket connection",
+                &sender,
+            )
+            .await
+            .is_err()
+            {
+                return;
+            }
+
+            match remove_fallback_track(&track_id, id.jam_id(), &mut *transaction).await {
+                Ok(changed_new) => {
+                    changed = changed.merge_with_other(changed_new);
+                }
+                Err(e) => {
+                    errors.push(e);
+                }
+            };
+        }
+        real_time::Request::MoveFallbackTrack { track_id, position } => {
+            if only_host(
+                &id,
+                "Only the host can change the fallback list, this is a bug, terminating socket connection",
+                &sender,
+            )
+            .await
+            .is_err()
+            {
+                return;
+            }
+
+            match move_fallback_track(&track_id, position, id.jam_id(), &mut transaction).await {
+                Ok(changed_new) => {
+                    changed = changed.merge_with_other(changed_new);
+                }
+                Err(e) => {
+                    errors.push(e);
+                }
+            };
+        }
+        real_time::Request::SetFallbackShuffle { shuffle } => {
+            if only_host(
+                &id,
+                "Only the host can change the fallback list, this is a bug, terminating socket connection",
+                &sender,
+            )
+            .await
+            .is_err()
+            {
+                return;
+            }
+
+            match set_fallback_shuffle(shuffle, id.jam_id(), &mut *transaction).await {
+                Ok(changed_new) => {
+                    changed = changed.merge_with_other(changed_new);
+                }
+                Err(e) => {
+                    errors.push(e);
+                }
+            };
+        }
     }
 
     if let Err(e) = notify(changed, errors, id.jam_id(), &mut transaction).await {
@@ -270,6 +748,83 @@ async fn handle_message(
 
 use super::Id;
 
+/// users search for songs to add, the host for tracks to add to the fallback list,
+/// the results only go to the one who searched, returns an error if the socket should stop reading
+async fn send_search_result(
+    query: real_time::SearchQuery,
+    offset: u32,
+    search_id: String,
+    id: &Id,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    credentials: SpotifyCredentials,
+    search_cache: &SearchCache,
+    latest_search: &watch::Receiver<String>,
+    sender: &mpsc::Sender<ws::Message>,
+) -> Result<(), ()> {
+    if !id.is_host()
+        && only_user(
+            id,
+            "Only users and hosts can search, this is a bug, terminating socket connection",
+            sender,
+        )
+        .await
+        .is_err()
+    {
+        return Err(());
+    }
+
+    let is_stale = || *latest_search.borrow() != search_id;
+    // typing sends a search for every character, only the last one is worth asking spotify
+    if matches!(query, real_time::SearchQuery::Text { .. }) && offset == 0 {
+        tokio::time::sleep(SEARCH_DEBOUNCE).await;
+    }
+    if is_stale() {
+        return Ok(());
+    }
+
+    let user_id = match &id.id {
+        IdType::User(user_id) => Some(user_id.as_str()),
+        _ => None,
+    };
+    let result = match search(
+        query,
+        offset,
+        search_id.clone(),
+        transaction,
+        id.jam_id(),
+        user_id,
+        search_cache,
+        credentials,
+    )
+    .await
+    {
+        Ok(result) => result,
+        Err(e) => {
+            handle_error(e, false, sender).await;
+            return Err(());
+        }
+    };
+    // a newer search came in while spotify answered, the client would ignore these results anyway
+    if is_stale() {
+        return Ok(());
+    }
+
+    let update = real_time::Update::new().search(result);
+    let message = match rmp_serde::to_vec(&update) {
+        Ok(m) => m,
+        Err(e) => {
+            let error = Error::Decode(format!("Error encoding search result: {:#?}", e));
+            handle_error(error, true, sender).await;
+            return Err(());
+        }
+    };
+    if let Err(e) = sender.send(ws::Message::Binary(message)).await {
+        eprintln!("Error sending ws message: {:?}", e);
+        return Err(());
+    }
+    Ok(())
+}
+
 ///returns id of host, if the id is not a host, it returns an error
 async fn only_host<'a>(
     id: &'a Id,
@@ -303,3 +858,35 @@ async fn only_user<'a>(
         }
     }
 }
+
+/// returns ok if the id is the host, or a co-host who has the permission checked by `has_permission`,
+/// otherwise sends an error message and returns an error,
+/// the connection is only terminated for spectators, because the permissions of a co-host can change at any time
+async fn only_host_or_co_host(
+    id: &Id,
+    has_permission: impl Fn(Permissions) -> bool,
+    message: &str,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    sender: &mpsc::Sender<ws::Message>,
+) -> Result<(), ()> {
+    match &id.id {
+        IdType::Host(_) => Ok(()),
+        IdType::User(user_id) => match get_permissions(user_id, &mut **transaction).await {
+            Ok(permissions) if has_permission(permissions) => Ok(()),
+            Ok(_) => {
+                let error = Error::Forbidden(message.to_string());
+                handle_error(error, false, sender).await;
+                Err(())
+            }
+            Err(e) => {
+                handle_error(e, false, sender).await;
+                Err(())
+            }
+        },
+        IdType::General => {
+            let error = Error::Forbidden(message.to_string());
+            handle_error(error, true, sender).await;
+            Err(())
+        }
+    }
+}
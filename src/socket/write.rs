@@ -4,7 +4,14 @@ use axum::extract::ws;
 use sqlx::postgres::PgListener;
 use tokio::sync::mpsc;
 
-pub async fn write(sender: mpsc::Sender<ws::Message>, id: Id, app_state: AppState) {
+/// has player is true for the connection of the host page that plays the jam,
+/// its player knows the position and the current song better than the server
+pub async fn write(
+    sender: mpsc::Sender<ws::Message>,
+    id: Id,
+    has_player: bool,
+    app_state: AppState,
+) {
     let pool = app_state.db.pool;
     let mut listener = match create_listener(&pool, &id).await {
         Ok(listener) => listener,
@@ -38,7 +45,7 @@ pub async fn write(sender: mpsc::Sender<ws::Message>, id: Id, app_state: AppStat
 
                 let mut changed = update.changed;
                 let errors = update.errors;
-                if id.is_host() {
+                if has_player {
                     changed.position = false;
                     changed.current_song = false;
                 }